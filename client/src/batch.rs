@@ -0,0 +1,309 @@
+//! Chunked batch order execution.
+//!
+//! [`BpxClient::execute_orders`] posts a single batch and returns the exchange's
+//! responses in input order. [`BpxClient::execute_orders_chunked`] splits larger
+//! batches into requests of at most [`MAX_BATCH_ORDERS`] orders, runs them with
+//! bounded concurrency and pairs every payload with its outcome in a
+//! [`BatchOrderReport`]. Payloads that fail validation, e.g. with a malformed
+//! symbol, are reported on their own and never sent.
+//!
+//! An order whose request failed may still have been accepted by the exchange,
+//! e.g. after a timeout or a server error, so resubmitting it risks a duplicate.
+//! [`BatchOrderReport::retry_failed`] therefore only resubmits rejected orders,
+//! and [`BatchOrderReport::retry_unconfirmed`] resubmits failed orders only after
+//! looking them up by client id.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::Arc,
+};
+
+use bpx_api_types::{
+    fill::FillsHistoryParams,
+    history::SortDirection,
+    order::{BatchOrderResponse, ExecuteOrderPayload, Order, OrderError},
+};
+use futures_util::{StreamExt, stream};
+
+use crate::{BpxClient, Error, Result, routes::market_symbol};
+
+/// Maximum number of orders the exchange accepts in a single batch request.
+pub const MAX_BATCH_ORDERS: usize = 50;
+
+/// Number of recent fills searched per market for unconfirmed client ids.
+const UNCONFIRMED_FILLS_LIMIT: u64 = 1000;
+
+/// The outcome of a single order within a chunked batch execution.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum BatchOrderOutcome {
+    /// The exchange accepted the order.
+    Order(Order),
+    /// The exchange rejected the order.
+    Rejected(OrderError),
+    /// The request carrying this order failed before the exchange answered for it.
+    /// Every order of the failed chunk shares the same error.
+    Failed(Arc<Error>),
+    /// The payload is invalid, so the order was not sent.
+    NotSent(Arc<Error>),
+}
+
+impl BatchOrderOutcome {
+    /// Returns `true` if the exchange accepted the order.
+    pub const fn is_order(&self) -> bool {
+        matches!(self, Self::Order(_))
+    }
+
+    /// Returns the accepted order, if any.
+    pub const fn order(&self) -> Option<&Order> {
+        match self {
+            Self::Order(order) => Some(order),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the request carrying the order failed, so the exchange
+    /// may or may not have accepted it.
+    pub const fn is_failed(&self) -> bool {
+        matches!(self, Self::Failed(_))
+    }
+}
+
+impl From<BatchOrderResponse> for BatchOrderOutcome {
+    fn from(response: BatchOrderResponse) -> Self {
+        match response {
+            BatchOrderResponse::Order(order) => Self::Order(order),
+            BatchOrderResponse::Error(error) => Self::Rejected(error),
+        }
+    }
+}
+
+/// An input payload paired with its outcome.
+#[derive(Debug, Clone)]
+pub struct BatchOrderItem {
+    pub payload: ExecuteOrderPayload,
+    pub outcome: BatchOrderOutcome,
+}
+
+/// The result of a chunked batch execution, in the same order as the input payloads.
+#[derive(Debug, Clone, Default)]
+pub struct BatchOrderReport {
+    pub items: Vec<BatchOrderItem>,
+}
+
+impl BatchOrderReport {
+    /// Returns `true` if every order was accepted.
+    pub fn is_complete(&self) -> bool {
+        self.items.iter().all(|item| item.outcome.is_order())
+    }
+
+    /// Iterates over the accepted orders.
+    pub fn orders(&self) -> impl Iterator<Item = &Order> {
+        self.items.iter().filter_map(|item| item.outcome.order())
+    }
+
+    /// Iterates over the items that were rejected or whose request failed.
+    pub fn failures(&self) -> impl Iterator<Item = &BatchOrderItem> {
+        self.items.iter().filter(|item| !item.outcome.is_order())
+    }
+
+    /// Returns the payloads of every item that was not accepted.
+    pub fn failed_payloads(&self) -> Vec<ExecuteOrderPayload> {
+        self.failures().map(|item| item.payload.clone()).collect()
+    }
+
+    /// Resubmits the items the exchange rejected and merges the new outcomes into
+    /// this report, keeping the original input order.
+    ///
+    /// Items whose request [failed](BatchOrderOutcome::Failed) are not
+    /// resubmitted: the exchange may have accepted them before the request
+    /// failed, and sending them again would create duplicate orders. See
+    /// [`Self::retry_unconfirmed`].
+    pub async fn retry_failed(&mut self, client: &BpxClient, concurrency: usize) {
+        let indices = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| matches!(item.outcome, BatchOrderOutcome::Rejected(_)))
+            .map(|(index, _)| index)
+            .collect();
+        self.resubmit(client, indices, concurrency).await;
+    }
+
+    /// Resubmits the items whose request failed, unless the exchange turns out to
+    /// have accepted them.
+    ///
+    /// Only items with a `client_id` are considered, since nothing else ties an
+    /// order on the exchange to its payload. For each market, the open orders and
+    /// the most recent fills are fetched first: an item found among the open
+    /// orders is recorded as accepted, an item found among the fills is left as
+    /// failed, and only the remaining items are resubmitted. Orders that were
+    /// accepted and then cancelled, or filled beyond the recent fills, cannot be
+    /// seen, so a duplicate remains possible; client ids must be unique for the
+    /// lookup to be meaningful.
+    ///
+    /// Fails without resubmitting anything if the lookup fails.
+    pub async fn retry_unconfirmed(
+        &mut self,
+        client: &BpxClient,
+        concurrency: usize,
+    ) -> Result<()> {
+        let unconfirmed = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.outcome.is_failed() && item.payload.client_id.is_some())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let symbols = unconfirmed
+            .iter()
+            .map(|&index| self.items[index].payload.symbol.as_str())
+            .collect::<BTreeSet<_>>();
+
+        let mut open = HashMap::new();
+        let mut filled = HashSet::new();
+        for symbol in symbols {
+            for order in client.get_open_orders(Some(symbol)).await? {
+                if let Some(client_id) = order_client_id(&order) {
+                    open.insert((symbol.to_string(), client_id), order);
+                }
+            }
+            let params = FillsHistoryParams::default()
+                .with_symbol(symbol)
+                .with_limit(UNCONFIRMED_FILLS_LIMIT)
+                .with_sort_direction(SortDirection::Desc);
+            for fill in client.get_historical_fills(params).await? {
+                if let Some(client_id) = fill.client_id {
+                    filled.insert((symbol.to_string(), client_id));
+                }
+            }
+        }
+
+        let mut resubmit = Vec::new();
+        for index in unconfirmed {
+            let item = &mut self.items[index];
+            let key = (
+                item.payload.symbol.clone(),
+                item.payload.client_id.unwrap_or_default(),
+            );
+            if let Some(order) = open.remove(&key) {
+                item.outcome = BatchOrderOutcome::Order(order);
+            } else if !filled.contains(&key) {
+                resubmit.push(index);
+            }
+        }
+        self.resubmit(client, resubmit, concurrency).await;
+        Ok(())
+    }
+
+    /// Resubmits the items at `indices` and replaces their outcomes.
+    async fn resubmit(&mut self, client: &BpxClient, indices: Vec<usize>, concurrency: usize) {
+        if indices.is_empty() {
+            return;
+        }
+        let payloads = indices
+            .iter()
+            .map(|&index| self.items[index].payload.clone())
+            .collect();
+        let retried = client.execute_orders_chunked(payloads, concurrency).await;
+        for (index, item) in indices.into_iter().zip(retried.items) {
+            self.items[index] = item;
+        }
+    }
+}
+
+fn order_client_id(order: &Order) -> Option<u32> {
+    match order {
+        Order::Market(order) => order.client_id,
+        Order::Limit(order) => order.client_id,
+    }
+}
+
+impl BpxClient {
+    /// Executes any number of orders by splitting them into batches of at most
+    /// [`MAX_BATCH_ORDERS`] and submitting up to `concurrency` batches at a time.
+    ///
+    /// Unlike [`BpxClient::execute_orders`] this never fails as a whole: an
+    /// invalid payload is reported as [not sent](BatchOrderOutcome::NotSent) on
+    /// its own, and a failed request is reported against every payload of its
+    /// chunk.
+    pub async fn execute_orders_chunked(
+        &self,
+        payloads: Vec<ExecuteOrderPayload>,
+        concurrency: usize,
+    ) -> BatchOrderReport {
+        let mut items = Vec::with_capacity(payloads.len());
+        let mut valid = Vec::with_capacity(payloads.len());
+        for (index, payload) in payloads.into_iter().enumerate() {
+            match market_symbol(&payload.symbol) {
+                Ok(_) => valid.push((index, payload)),
+                Err(err) => items.push((
+                    index,
+                    BatchOrderItem {
+                        payload,
+                        outcome: BatchOrderOutcome::NotSent(Arc::new(err)),
+                    },
+                )),
+            }
+        }
+
+        let chunks = valid
+            .chunks(MAX_BATCH_ORDERS)
+            .map(<[(usize, ExecuteOrderPayload)]>::to_vec)
+            .collect::<Vec<_>>();
+        let sent = stream::iter(chunks)
+            .map(|chunk| async move {
+                let (indices, chunk): (Vec<_>, Vec<_>) = chunk.into_iter().unzip();
+                let result = self.execute_orders(chunk.clone()).await;
+                indices.into_iter().zip(pair_chunk(chunk, result))
+            })
+            .buffered(concurrency.max(1))
+            .flat_map(stream::iter)
+            .collect::<Vec<_>>()
+            .await;
+
+        items.extend(sent);
+        items.sort_by_key(|(index, _)| *index);
+        BatchOrderReport {
+            items: items.into_iter().map(|(_, item)| item).collect(),
+        }
+    }
+}
+
+/// Pairs the payloads of a chunk with the exchange's responses, which are returned
+/// in submission order.
+fn pair_chunk(
+    chunk: Vec<ExecuteOrderPayload>,
+    result: Result<Vec<BatchOrderResponse>>,
+) -> Vec<BatchOrderItem> {
+    let error = match result {
+        Ok(responses) if responses.len() == chunk.len() => {
+            return chunk
+                .into_iter()
+                .zip(responses)
+                .map(|(payload, response)| BatchOrderItem {
+                    payload,
+                    outcome: response.into(),
+                })
+                .collect();
+        }
+        Ok(responses) => Error::UnexpectedResponse(
+            format!(
+                "batch of {} orders returned {} responses",
+                chunk.len(),
+                responses.len()
+            )
+            .into(),
+        ),
+        Err(err) => err,
+    };
+
+    let error = Arc::new(error);
+    chunk
+        .into_iter()
+        .map(|payload| BatchOrderItem {
+            payload,
+            outcome: BatchOrderOutcome::Failed(Arc::clone(&error)),
+        })
+        .collect()
+}
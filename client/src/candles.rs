@@ -0,0 +1,337 @@
+//! Candle aggregation from trades.
+//!
+//! [`CandleAggregator`] builds OHLCV bars from `trade` websocket events
+//! ([`TradeUpdate`]) or historical [`Trade`]s, for intervals the exchange does
+//! not serve, sub-minute bars, volume bars or tick bars. Each bar splits its
+//! volume by taker side and tracks its VWAP.
+//!
+//! Trades are expected in chronological order. Bars only close when a trade
+//! belonging to the next bar arrives, or when [`CandleAggregator::flush`] or
+//! [`CandleAggregator::close_until`] is called. Time bars are aligned to the
+//! Unix epoch and intervals without trades produce no bar.
+//!
+//! ## Example
+//! ```no_run
+//! use std::time::Duration;
+//! use bpx_api_client::candles::{BarSpec, CandleAggregator};
+//! use bpx_api_client::types::trade::TradeUpdate;
+//! use futures_util::{StreamExt, stream};
+//! use tokio::sync::mpsc::Receiver;
+//!
+//! # async fn run(rx: Receiver<TradeUpdate>) {
+//! // `rx` receives the `trade.SOL_USDC` stream.
+//! let trades = stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|t| (t, rx)) });
+//! let aggregator = CandleAggregator::new(BarSpec::Time(Duration::from_secs(10)));
+//! let mut bars = std::pin::pin!(aggregator.into_stream(trades));
+//! while let Some(bar) = bars.next().await {
+//!     println!("{} close {} vwap {:?}", bar.start, bar.close, bar.vwap());
+//! }
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use bpx_api_types::trade::{Trade, TradeUpdate};
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt, stream};
+use rust_decimal::Decimal;
+
+/// A trade as seen by the aggregator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeTick {
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub timestamp: DateTime<Utc>,
+    /// `true` if the buyer was the maker, i.e. the seller took liquidity.
+    pub buyer_is_maker: bool,
+}
+
+impl From<&TradeUpdate> for TradeTick {
+    fn from(trade: &TradeUpdate) -> Self {
+        Self {
+            price: trade.price,
+            quantity: trade.quantity,
+            timestamp: DateTime::from_timestamp_micros(trade.timestamp).unwrap_or_default(),
+            buyer_is_maker: trade.buyer_is_maker,
+        }
+    }
+}
+
+impl From<TradeUpdate> for TradeTick {
+    fn from(trade: TradeUpdate) -> Self {
+        Self::from(&trade)
+    }
+}
+
+impl From<&Trade> for TradeTick {
+    fn from(trade: &Trade) -> Self {
+        Self {
+            price: trade.price,
+            quantity: trade.quantity,
+            timestamp: DateTime::from_timestamp_millis(trade.timestamp).unwrap_or_default(),
+            buyer_is_maker: trade.is_buyer_maker,
+        }
+    }
+}
+
+impl From<Trade> for TradeTick {
+    fn from(trade: Trade) -> Self {
+        Self::from(&trade)
+    }
+}
+
+/// How trades are grouped into bars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarSpec {
+    /// Bars covering a fixed duration, aligned to the Unix epoch.
+    Time(Duration),
+    /// Bars that close on the trade bringing their base volume to at least this
+    /// quantity. Trades are not split, so bars may exceed it.
+    Volume(Decimal),
+    /// Bars of this many trades.
+    Tick(u64),
+}
+
+/// An OHLCV bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bar {
+    /// Start of the bar interval for time bars, otherwise the time of the first trade.
+    pub start: DateTime<Utc>,
+    /// End of the bar interval for time bars, otherwise the time of the last trade.
+    pub end: DateTime<Utc>,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    /// Base volume.
+    pub volume: Decimal,
+    /// Quote volume.
+    pub quote_volume: Decimal,
+    /// Base volume of trades where the buyer took liquidity.
+    pub buy_volume: Decimal,
+    /// Base volume of trades where the seller took liquidity.
+    pub sell_volume: Decimal,
+    pub trades: u64,
+}
+
+impl Bar {
+    fn open(tick: &TradeTick, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            end,
+            open: tick.price,
+            high: tick.price,
+            low: tick.price,
+            close: tick.price,
+            volume: Decimal::ZERO,
+            quote_volume: Decimal::ZERO,
+            buy_volume: Decimal::ZERO,
+            sell_volume: Decimal::ZERO,
+            trades: 0,
+        }
+    }
+
+    fn add(&mut self, tick: &TradeTick) {
+        self.high = self.high.max(tick.price);
+        self.low = self.low.min(tick.price);
+        self.close = tick.price;
+        self.volume += tick.quantity;
+        self.quote_volume += tick.price * tick.quantity;
+        if tick.buyer_is_maker {
+            self.sell_volume += tick.quantity;
+        } else {
+            self.buy_volume += tick.quantity;
+        }
+        self.trades += 1;
+    }
+
+    /// Volume weighted average price.
+    pub fn vwap(&self) -> Option<Decimal> {
+        (!self.volume.is_zero()).then(|| self.quote_volume / self.volume)
+    }
+}
+
+/// Aggregates trades into bars. See the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    spec: BarSpec,
+    current: Option<Bar>,
+}
+
+impl CandleAggregator {
+    /// Creates an aggregator producing bars according to `spec`.
+    pub fn new(spec: BarSpec) -> Self {
+        Self {
+            spec,
+            current: None,
+        }
+    }
+
+    /// Returns the bar being built.
+    pub fn current(&self) -> Option<&Bar> {
+        self.current.as_ref()
+    }
+
+    /// Adds a trade and returns the bar it completed, if any.
+    pub fn push(&mut self, tick: impl Into<TradeTick>) -> Option<Bar> {
+        let tick = tick.into();
+        match self.spec {
+            BarSpec::Time(duration) => {
+                let (start, end) = time_bucket(tick.timestamp, duration);
+                let completed = self.current.take_if(|bar| bar.start != start);
+                self.current
+                    .get_or_insert_with(|| Bar::open(&tick, start, end))
+                    .add(&tick);
+                completed
+            }
+            BarSpec::Volume(threshold) => {
+                let bar = self.add_to_current(&tick);
+                (bar.volume >= threshold).then(|| self.current.take())?
+            }
+            BarSpec::Tick(count) => {
+                let bar = self.add_to_current(&tick);
+                (bar.trades >= count).then(|| self.current.take())?
+            }
+        }
+    }
+
+    /// Closes and returns the bar being built.
+    pub fn flush(&mut self) -> Option<Bar> {
+        self.current.take()
+    }
+
+    /// Closes and returns the current time bar if it ends at or before `now`.
+    /// Lets callers emit time bars without waiting for the next trade.
+    pub fn close_until(&mut self, now: DateTime<Utc>) -> Option<Bar> {
+        match self.spec {
+            BarSpec::Time(_) => self.current.take_if(|bar| bar.end <= now),
+            BarSpec::Volume(_) | BarSpec::Tick(_) => None,
+        }
+    }
+
+    /// Consumes a stream of trades and yields completed bars. The bar being built
+    /// when the trade stream ends is yielded last.
+    pub fn into_stream<S>(self, trades: S) -> impl Stream<Item = Bar>
+    where
+        S: Stream,
+        S::Item: Into<TradeTick>,
+    {
+        let trades = Box::pin(trades);
+        stream::unfold(Some((trades, self)), |state| async move {
+            let (mut trades, mut aggregator) = state?;
+            loop {
+                match trades.next().await {
+                    Some(trade) => {
+                        if let Some(bar) = aggregator.push(trade) {
+                            return Some((bar, Some((trades, aggregator))));
+                        }
+                    }
+                    None => return aggregator.flush().map(|bar| (bar, None)),
+                }
+            }
+        })
+    }
+
+    fn add_to_current(&mut self, tick: &TradeTick) -> &Bar {
+        let bar = self
+            .current
+            .get_or_insert_with(|| Bar::open(tick, tick.timestamp, tick.timestamp));
+        bar.end = tick.timestamp;
+        bar.add(tick);
+        bar
+    }
+}
+
+/// Returns the epoch aligned interval of length `duration` containing `time`.
+fn time_bucket(time: DateTime<Utc>, duration: Duration) -> (DateTime<Utc>, DateTime<Utc>) {
+    let length = (duration.as_micros() as i64).max(1);
+    let micros = time.timestamp_micros();
+    let start = micros - micros.rem_euclid(length);
+    (
+        DateTime::from_timestamp_micros(start).unwrap_or_default(),
+        DateTime::from_timestamp_micros(start + length).unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn tick(seconds: i64, price: Decimal, quantity: Decimal, buyer_is_maker: bool) -> TradeTick {
+        TradeTick {
+            price,
+            quantity,
+            timestamp: DateTime::from_timestamp(seconds, 0).unwrap(),
+            buyer_is_maker,
+        }
+    }
+
+    #[test]
+    fn builds_time_bars_with_taker_split_and_vwap() {
+        let mut aggregator = CandleAggregator::new(BarSpec::Time(Duration::from_secs(10)));
+        assert_eq!(aggregator.push(tick(100, dec!(10), dec!(1), false)), None);
+        assert_eq!(aggregator.push(tick(105, dec!(12), dec!(3), true)), None);
+        assert_eq!(aggregator.push(tick(109, dec!(11), dec!(1), false)), None);
+
+        let bar = aggregator
+            .push(tick(125, dec!(13), dec!(1), false))
+            .unwrap();
+        assert_eq!(bar.start.timestamp(), 100);
+        assert_eq!(bar.end.timestamp(), 110);
+        assert_eq!(
+            (bar.open, bar.high, bar.low, bar.close),
+            (dec!(10), dec!(12), dec!(10), dec!(11))
+        );
+        assert_eq!(bar.volume, dec!(5));
+        assert_eq!(bar.buy_volume, dec!(2));
+        assert_eq!(bar.sell_volume, dec!(3));
+        assert_eq!(bar.trades, 3);
+        assert_eq!(bar.vwap(), Some(dec!(11.4)));
+
+        // The bar for [120, 130) closes once its interval is over.
+        let now = DateTime::from_timestamp(129, 0).unwrap();
+        assert_eq!(aggregator.close_until(now), None);
+        let now = DateTime::from_timestamp(130, 0).unwrap();
+        assert_eq!(aggregator.close_until(now).unwrap().start.timestamp(), 120);
+        assert_eq!(aggregator.flush(), None);
+    }
+
+    #[test]
+    fn builds_volume_and_tick_bars() {
+        let mut aggregator = CandleAggregator::new(BarSpec::Volume(dec!(5)));
+        assert_eq!(aggregator.push(tick(1, dec!(10), dec!(2), false)), None);
+        let bar = aggregator.push(tick(2, dec!(11), dec!(4), true)).unwrap();
+        assert_eq!(bar.volume, dec!(6));
+        assert_eq!((bar.start.timestamp(), bar.end.timestamp()), (1, 2));
+        assert!(aggregator.current().is_none());
+
+        let mut aggregator = CandleAggregator::new(BarSpec::Tick(2));
+        assert_eq!(aggregator.push(tick(1, dec!(10), dec!(1), false)), None);
+        assert_eq!(
+            aggregator
+                .push(tick(2, dec!(9), dec!(1), false))
+                .unwrap()
+                .low,
+            dec!(9)
+        );
+        assert_eq!(aggregator.push(tick(3, dec!(8), dec!(1), false)), None);
+        assert_eq!(aggregator.flush().unwrap().trades, 1);
+    }
+
+    #[tokio::test]
+    async fn streams_completed_bars() {
+        let trades = stream::iter([
+            tick(1, dec!(10), dec!(1), false),
+            tick(2, dec!(11), dec!(1), false),
+            tick(3, dec!(12), dec!(1), false),
+        ]);
+        let bars = CandleAggregator::new(BarSpec::Tick(2))
+            .into_stream(trades)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].close, dec!(11));
+        assert_eq!(bars[1].open, dec!(12));
+    }
+}
@@ -0,0 +1,394 @@
+//! Client order id allocation.
+//!
+//! The exchange accepts a `u32` `client_id` on orders, cancels, RFQs and quotes.
+//! [`ClientIdAllocator`] splits that space into partitions, one per strategy or
+//! process tag, so processes sharing an account never hand out the same id.
+//! The upper `partition_bits` of every id hold the partition index and the lower
+//! bits a monotonically increasing sequence.
+//!
+//! With persistence enabled, the allocator reserves sequences in blocks and
+//! stores the reserved high-water mark on disk, so a restarted process resumes
+//! above every id it may have issued before. Processes may share one marks file:
+//! each write holds an exclusive lock on a `.lock` file next to it and merges
+//! the marks already on disk, keeping the highest mark of every partition.
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::client_id::ClientIdAllocator;
+//!
+//! # fn main() -> bpx_api_client::Result<()> {
+//! let allocator = ClientIdAllocator::new(8)
+//!     .with_partition("market-maker", 1)?
+//!     .with_partition("hedger", 2)?
+//!     .with_persistence("client_ids.json")?;
+//!
+//! let client_id = allocator.next_id("market-maker")?;
+//! assert_eq!(allocator.tag_of(client_id), Some("market-maker"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use bpx_api_types::{fill::Fill, order::OrderUpdate};
+
+use crate::{Error, Result};
+
+/// Number of sequences reserved on disk at a time when persistence is enabled.
+const DEFAULT_RESERVE_BLOCK: u32 = 1000;
+
+/// Allocates collision-free client ids partitioned by tag. See the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct ClientIdAllocator {
+    partition_bits: u32,
+    partitions: HashMap<String, u32>,
+    tags: HashMap<u32, String>,
+    reserve_block: u32,
+    persistence: Option<PathBuf>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Next sequence to hand out, per partition.
+    next: HashMap<u32, u32>,
+    /// Sequence up to which ids are reserved on disk, per partition.
+    reserved: BTreeMap<u32, u32>,
+}
+
+impl ClientIdAllocator {
+    /// Creates an allocator that uses the upper `partition_bits` of each id for the
+    /// partition index, allowing `2^partition_bits` partitions of
+    /// `2^(32 - partition_bits)` ids each.
+    ///
+    /// # Panics
+    /// If `partition_bits` is not between 1 and 31.
+    pub fn new(partition_bits: u32) -> Self {
+        assert!(
+            (1..32).contains(&partition_bits),
+            "partition_bits must be between 1 and 31"
+        );
+        Self {
+            partition_bits,
+            partitions: HashMap::new(),
+            tags: HashMap::new(),
+            reserve_block: DEFAULT_RESERVE_BLOCK,
+            persistence: None,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Assigns `tag` to `partition`. Processes sharing an account must agree on
+    /// these assignments.
+    pub fn with_partition(mut self, tag: impl Into<String>, partition: u32) -> Result<Self> {
+        let tag = tag.into();
+        if partition >= self.partition_count() {
+            return Err(Error::InvalidRequest(
+                format!(
+                    "partition {partition} out of range, maximum is {}",
+                    self.partition_count() - 1
+                )
+                .into(),
+            ));
+        }
+        if let Some(existing) = self.tags.get(&partition) {
+            return Err(Error::InvalidRequest(
+                format!("partition {partition} is already assigned to {existing}").into(),
+            ));
+        }
+        if self.partitions.contains_key(&tag) {
+            return Err(Error::InvalidRequest(
+                format!("tag {tag} is already assigned to a partition").into(),
+            ));
+        }
+        self.partitions.insert(tag.clone(), partition);
+        self.tags.insert(partition, tag);
+        Ok(self)
+    }
+
+    /// Sets how many sequences are reserved on disk at a time. Larger blocks write
+    /// less often but skip more ids after a restart. Defaults to 1000.
+    pub fn with_reserve_block(mut self, reserve_block: u32) -> Self {
+        self.reserve_block = reserve_block.max(1);
+        self
+    }
+
+    /// Persists high-water marks to `path`, resuming from any marks already stored there.
+    pub fn with_persistence(mut self, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let reserved = {
+            let _lock = lock_marks(&path)?;
+            read_marks(&path)?
+        };
+        {
+            let state = self.state.get_mut().expect("client id state poisoned");
+            for (&partition, &mark) in &reserved {
+                let next = state.next.entry(partition).or_default();
+                *next = (*next).max(mark);
+            }
+            state.reserved = reserved;
+        }
+        self.persistence = Some(path);
+        Ok(self)
+    }
+
+    /// Returns the number of partitions available.
+    pub const fn partition_count(&self) -> u32 {
+        1 << self.partition_bits
+    }
+
+    /// Returns the number of ids available in each partition.
+    pub const fn partition_size(&self) -> u32 {
+        1 << (32 - self.partition_bits)
+    }
+
+    /// Allocates the next id for `tag`.
+    pub fn next_id(&self, tag: &str) -> Result<u32> {
+        let partition = *self
+            .partitions
+            .get(tag)
+            .ok_or_else(|| Error::InvalidRequest(format!("unknown client id tag {tag}").into()))?;
+
+        let mut state = self.state.lock().expect("client id state poisoned");
+        let sequence = state.next.get(&partition).copied().unwrap_or_default();
+        if sequence >= self.partition_size() {
+            return Err(Error::InvalidRequest(
+                format!("client ids exhausted for tag {tag}").into(),
+            ));
+        }
+
+        if let Some(path) = &self.persistence {
+            let reserved = state.reserved.get(&partition).copied().unwrap_or_default();
+            if sequence >= reserved {
+                let mark = sequence
+                    .saturating_add(self.reserve_block)
+                    .min(self.partition_size());
+                state.reserved.insert(partition, mark);
+                state.reserved = write_marks(path, &state.reserved)?;
+            }
+        }
+
+        state.next.insert(partition, sequence + 1);
+        Ok((partition << (32 - self.partition_bits)) | sequence)
+    }
+
+    /// Splits an id into its partition index and sequence.
+    pub const fn decode(&self, client_id: u32) -> (u32, u32) {
+        let shift = 32 - self.partition_bits;
+        (client_id >> shift, client_id & ((1 << shift) - 1))
+    }
+
+    /// Returns the tag whose partition contains `client_id`.
+    pub fn tag_of(&self, client_id: u32) -> Option<&str> {
+        let (partition, _) = self.decode(client_id);
+        self.tags.get(&partition).map(String::as_str)
+    }
+
+    /// Returns the tag that allocated the client id of an order update.
+    pub fn tag_of_order_update(&self, update: &OrderUpdate) -> Option<&str> {
+        let client_id = u32::try_from(update.client_order_id?).ok()?;
+        self.tag_of(client_id)
+    }
+
+    /// Returns the tag that allocated the client id of a fill.
+    pub fn tag_of_fill(&self, fill: &Fill) -> Option<&str> {
+        let client_id = fill.client_id?;
+        self.tag_of(client_id)
+    }
+}
+
+/// Reads the marks stored at `path`, if any.
+fn read_marks(path: &Path) -> Result<BTreeMap<u32, u32>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Takes an exclusive lock on the lock file of the marks at `path`. The lock is
+/// released when the returned file is dropped.
+fn lock_marks(path: &Path) -> Result<File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+    lock.lock()?;
+    Ok(lock)
+}
+
+/// Merges `marks` with the marks on disk, keeping the highest mark of every
+/// partition, and writes the result. Returns the merged marks.
+fn write_marks(path: &Path, marks: &BTreeMap<u32, u32>) -> Result<BTreeMap<u32, u32>> {
+    let _lock = lock_marks(path)?;
+    let mut merged = read_marks(path)?;
+    for (&partition, &mark) in marks {
+        let merged_mark = merged.entry(partition).or_default();
+        *merged_mark = (*merged_mark).max(mark);
+    }
+    write_atomically(path, &serde_json::to_vec(&merged)?)?;
+    Ok(merged)
+}
+
+/// Replaces the file at `path` without leaving a partially written file behind.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("bpx-client-id-{name}-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn allocates_monotonic_ids_within_partitions() {
+        let allocator = ClientIdAllocator::new(8)
+            .with_partition("maker", 1)
+            .unwrap()
+            .with_partition("hedger", 2)
+            .unwrap();
+
+        let first = allocator.next_id("maker").unwrap();
+        let second = allocator.next_id("maker").unwrap();
+        let hedge = allocator.next_id("hedger").unwrap();
+
+        assert_eq!(first, 1 << 24);
+        assert_eq!(second, first + 1);
+        assert_eq!(hedge, 2 << 24);
+        assert_eq!(allocator.decode(second), (1, 1));
+        assert_eq!(allocator.tag_of(second), Some("maker"));
+        assert_eq!(allocator.tag_of(hedge), Some("hedger"));
+        assert_eq!(allocator.tag_of(3 << 24), None);
+        assert!(allocator.next_id("unknown").is_err());
+    }
+
+    #[test]
+    fn rejects_conflicting_partitions() {
+        let allocator = ClientIdAllocator::new(2).with_partition("a", 0).unwrap();
+        assert!(allocator.with_partition("b", 0).is_err());
+
+        let allocator = ClientIdAllocator::new(2).with_partition("a", 0).unwrap();
+        assert!(allocator.with_partition("a", 1).is_err());
+
+        assert!(ClientIdAllocator::new(2).with_partition("a", 4).is_err());
+    }
+
+    #[test]
+    fn errors_when_partition_is_exhausted() {
+        let allocator = ClientIdAllocator::new(31).with_partition("a", 1).unwrap();
+        assert_eq!(allocator.next_id("a").unwrap(), 1 << 1);
+        assert_eq!(allocator.next_id("a").unwrap(), (1 << 1) | 1);
+        assert!(allocator.next_id("a").is_err());
+    }
+
+    #[test]
+    fn resumes_above_persisted_high_water_mark() {
+        let path = temp_path("resume");
+        let build = || {
+            ClientIdAllocator::new(8)
+                .with_partition("maker", 1)
+                .unwrap()
+                .with_reserve_block(10)
+                .with_persistence(&path)
+                .unwrap()
+        };
+
+        let allocator = build();
+        for expected in 0..12 {
+            assert_eq!(
+                allocator.decode(allocator.next_id("maker").unwrap()).1,
+                expected
+            );
+        }
+        drop(allocator);
+
+        let allocator = build();
+        let (_, sequence) = allocator.decode(allocator.next_id("maker").unwrap());
+        assert_eq!(sequence, 20);
+
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+    }
+
+    #[test]
+    fn processes_sharing_a_file_keep_each_others_marks() {
+        let path = temp_path("shared");
+        let build = |tag: &str, partition| {
+            ClientIdAllocator::new(8)
+                .with_partition(tag, partition)
+                .unwrap()
+                .with_reserve_block(10)
+                .with_persistence(&path)
+                .unwrap()
+        };
+
+        // Both start before either has written, as two processes would.
+        let maker = build("maker", 1);
+        let hedger = build("hedger", 2);
+        maker.next_id("maker").unwrap();
+        hedger.next_id("hedger").unwrap();
+
+        assert_eq!(
+            read_marks(&path).unwrap(),
+            BTreeMap::from([(1, 10), (2, 10)])
+        );
+        let maker = build("maker", 1);
+        assert_eq!(maker.decode(maker.next_id("maker").unwrap()).1, 10);
+
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+    }
+
+    #[test]
+    fn maps_order_updates_and_fills_to_tags() {
+        let allocator = ClientIdAllocator::new(8)
+            .with_partition("maker", 5)
+            .unwrap();
+        let client_id = allocator.next_id("maker").unwrap();
+
+        let fill: Fill = serde_json::from_value(serde_json::json!({
+            "tradeId": 1,
+            "clientId": client_id.to_string(),
+            "orderId": "1",
+            "symbol": "SOL_USDC",
+            "feeSymbol": "USDC",
+            "price": "1",
+            "quantity": "1",
+            "fee": "0",
+            "side": "Bid",
+            "timestamp": "2025-01-01T00:00:00",
+            "isMaker": true,
+            "systemOrderType": null,
+        }))
+        .unwrap();
+        assert_eq!(allocator.tag_of_fill(&fill), Some("maker"));
+
+        let update: OrderUpdate = serde_json::from_str(&format!(
+            r#"{{"E":1,"O":"USER","S":"Bid","T":1,"V":"RejectTaker","X":"New","Z":"0","c":{client_id},"e":"orderAccepted","f":"GTC","i":"1","o":"LIMIT","p":"1","q":"1","s":"SOL_USDC","z":"0"}}"#
+        ))
+        .unwrap();
+        assert_eq!(allocator.tag_of_order_update(&update), Some("maker"));
+    }
+}
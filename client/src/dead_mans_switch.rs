@@ -0,0 +1,580 @@
+//! Client-side dead-man's switch.
+//!
+//! The switch cancels every open order on a configured set of symbols when the
+//! application stops sending heartbeats, when the private websocket stays
+//! disconnected for too long, when the process receives a shutdown signal, or
+//! when every handle is dropped, e.g. because the task feeding it panicked.
+//!
+//! Orders are cancelled in rounds: each round cancels on every pending symbol at
+//! once and only the symbols that failed are retried in the next one. Errors
+//! that retrying cannot fix, such as a rejected request, are reported and not
+//! retried. On shutdown, and once every handle is dropped, the switch stops once
+//! every symbol is clear or once the shutdown timeout has passed.
+//!
+//! With [`DeadMansSwitchConfig::with_shutdown_signals`] the switch handles
+//! Ctrl-C and `SIGTERM` itself, so these signals no longer terminate the
+//! process: the application must exit once the switch stops, e.g. after
+//! [`DeadMansSwitch::join`] returns.
+//!
+//! ## Example
+//! ```no_run
+//! use std::time::Duration;
+//! use bpx_api_client::{
+//!     BpxClient,
+//!     dead_mans_switch::{DeadMansSwitch, DeadMansSwitchConfig},
+//! };
+//! use tokio::sync::mpsc;
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let config = DeadMansSwitchConfig::new(["SOL_USDC"], Duration::from_secs(10))?
+//!     .with_disconnect_timeout(Duration::from_secs(5))
+//!     .with_shutdown_signals(true);
+//! let (tx, mut rx) = mpsc::channel(16);
+//! let switch = DeadMansSwitch::spawn(client, config, tx);
+//!
+//! tokio::spawn(async move {
+//!     while let Some(event) = rx.recv().await {
+//!         println!("{event:?}");
+//!     }
+//! });
+//!
+//! let handle = switch.handle();
+//! tokio::spawn(async move {
+//!     loop {
+//!         // ... strategy work ...
+//!         handle.heartbeat();
+//!         tokio::time::sleep(Duration::from_secs(1)).await;
+//!     }
+//! });
+//!
+//! // Returns once a shutdown signal arrived and the orders are cancelled.
+//! switch.join().await;
+//! Ok(())
+//! # }
+//! ```
+
+use std::{sync::Arc, time::Duration};
+
+use bpx_api_types::{
+    order::{CancelOpenOrdersPayload, Order},
+    symbol::Symbol,
+};
+use futures_util::future::join_all;
+use reqwest::StatusCode;
+use tokio::{
+    sync::mpsc::{self, Sender, UnboundedReceiver, UnboundedSender, error::TrySendError},
+    task::JoinHandle,
+    time::{Instant, MissedTickBehavior},
+};
+
+use crate::{BpxClient, Error, Result, routes::market_symbol};
+
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configuration for a [`DeadMansSwitch`].
+#[derive(Debug, Clone)]
+pub struct DeadMansSwitchConfig {
+    symbols: Vec<String>,
+    heartbeat_timeout: Duration,
+    disconnect_timeout: Option<Duration>,
+    retry_interval: Duration,
+    check_interval: Duration,
+    shutdown_signals: bool,
+    shutdown_timeout: Duration,
+}
+
+impl DeadMansSwitchConfig {
+    /// Creates a configuration that cancels all open orders on `symbols` once no
+    /// heartbeat has been received for `heartbeat_timeout`.
+    ///
+    /// Fails if `symbols` is empty or contains a malformed symbol, so that a typo
+    /// is caught before the switch is relied upon.
+    pub fn new<I, S>(symbols: I, heartbeat_timeout: Duration) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Symbol>,
+    {
+        let symbols = symbols
+            .into_iter()
+            .map(|symbol| market_symbol(symbol).map(String::from))
+            .collect::<Result<Vec<_>>>()?;
+        if symbols.is_empty() {
+            return Err(Error::InvalidRequest(
+                "a dead-man's switch needs at least one symbol".into(),
+            ));
+        }
+        Ok(Self {
+            symbols,
+            heartbeat_timeout,
+            disconnect_timeout: None,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            shutdown_signals: false,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+        })
+    }
+
+    /// Also trips the switch once the private websocket has been reported
+    /// disconnected for longer than `timeout`.
+    pub fn with_disconnect_timeout(mut self, timeout: Duration) -> Self {
+        self.disconnect_timeout = Some(timeout);
+        self
+    }
+
+    /// Delay between cancellation rounds until the book is confirmed clear.
+    /// Defaults to one second.
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// How often the deadlines are checked. Defaults to 250 milliseconds.
+    pub fn with_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+        self
+    }
+
+    /// Cancels all orders and stops the switch when the process receives
+    /// Ctrl-C, or `SIGTERM` on Unix.
+    ///
+    /// The switch installs its own handlers for these signals, which replace
+    /// the default ones for the rest of the process: the signals no longer
+    /// terminate it, and signals received after the first are ignored. The
+    /// switch emits [`DeadMansSwitchEvent::ShutdownSignal`] and stops once the
+    /// orders are cancelled or the shutdown timeout has passed; the application
+    /// must then exit itself, e.g. once [`DeadMansSwitch::join`] returns.
+    pub fn with_shutdown_signals(mut self, shutdown_signals: bool) -> Self {
+        self.shutdown_signals = shutdown_signals;
+        self
+    }
+
+    /// Longest time spent cancelling orders on shutdown before the switch stops
+    /// anyway. Defaults to ten seconds.
+    pub fn with_shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+}
+
+/// Why the switch cancelled the open orders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriggerReason {
+    /// No heartbeat arrived within the configured interval.
+    HeartbeatTimeout,
+    /// The private websocket stayed disconnected for too long.
+    Disconnected,
+    /// A shutdown signal was received, or [`DeadMansSwitchHandle::shutdown`] was called.
+    Shutdown,
+    /// [`DeadMansSwitchHandle::trigger`] was called.
+    Manual,
+    /// Every handle was dropped without a shutdown, e.g. because the task
+    /// holding them panicked.
+    HandlesDropped,
+}
+
+/// Events emitted by a running [`DeadMansSwitch`].
+///
+/// Events are dropped rather than awaited when the channel is full.
+#[derive(Debug, Clone)]
+pub enum DeadMansSwitchEvent {
+    /// The process received Ctrl-C or `SIGTERM`. The switch cancels all orders
+    /// and stops; the application must exit itself, see
+    /// [`DeadMansSwitchConfig::with_shutdown_signals`].
+    ShutdownSignal,
+    /// The switch tripped and is cancelling orders.
+    Triggered(TriggerReason),
+    /// Orders on `symbol` were cancelled.
+    Cancelled { symbol: String, orders: Vec<Order> },
+    /// A cancellation attempt failed and will be retried in the next round.
+    CancelFailed {
+        symbol: String,
+        attempt: u32,
+        error: Arc<Error>,
+    },
+    /// Cancelling on `symbol` failed with an error that retrying cannot fix, such
+    /// as a rejected request. Orders may remain open.
+    CancelAbandoned { symbol: String, error: Arc<Error> },
+    /// The shutdown timeout passed before the orders on `symbols` were confirmed
+    /// cancelled.
+    ShutdownTimedOut { symbols: Vec<String> },
+    /// No open orders remain on any configured symbol.
+    Confirmed(TriggerReason),
+    /// A heartbeat arrived after the switch tripped, arming it again.
+    Rearmed,
+}
+
+#[derive(Debug)]
+enum Command {
+    Heartbeat,
+    Connected(bool),
+    Trigger,
+    Shutdown,
+}
+
+/// A handle used to feed a running [`DeadMansSwitch`].
+///
+/// Dropping every handle trips the switch: it cancels all orders and stops, as on
+/// [`DeadMansSwitchHandle::shutdown`].
+#[derive(Debug, Clone)]
+pub struct DeadMansSwitchHandle {
+    commands: UnboundedSender<Command>,
+}
+
+impl DeadMansSwitchHandle {
+    /// Signals that the application is alive.
+    pub fn heartbeat(&self) {
+        let _ = self.commands.send(Command::Heartbeat);
+    }
+
+    /// Reports that the private websocket connected.
+    pub fn ws_connected(&self) {
+        let _ = self.commands.send(Command::Connected(true));
+    }
+
+    /// Reports that the private websocket disconnected.
+    pub fn ws_disconnected(&self) {
+        let _ = self.commands.send(Command::Connected(false));
+    }
+
+    /// Trips the switch immediately.
+    pub fn trigger(&self) {
+        let _ = self.commands.send(Command::Trigger);
+    }
+
+    /// Cancels all orders and stops the switch.
+    pub fn shutdown(&self) {
+        let _ = self.commands.send(Command::Shutdown);
+    }
+}
+
+/// A spawned dead-man's switch. See the [module documentation](self).
+#[derive(Debug)]
+pub struct DeadMansSwitch {
+    handle: DeadMansSwitchHandle,
+    task: JoinHandle<()>,
+}
+
+impl DeadMansSwitch {
+    /// Spawns the switch on the current tokio runtime. The switch is armed
+    /// immediately, as if a heartbeat had just been received.
+    pub fn spawn(
+        client: BpxClient,
+        config: DeadMansSwitchConfig,
+        tx: Sender<DeadMansSwitchEvent>,
+    ) -> Self {
+        let (commands, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run(client, config, rx, tx));
+        Self {
+            handle: DeadMansSwitchHandle { commands },
+            task,
+        }
+    }
+
+    /// Returns a cloneable handle to the switch.
+    pub fn handle(&self) -> DeadMansSwitchHandle {
+        self.handle.clone()
+    }
+
+    /// Drops this handle and waits for the switch to stop. Unless the switch was
+    /// shut down, this trips it once no other handle remains.
+    pub async fn join(self) {
+        let Self { handle, task } = self;
+        drop(handle);
+        let _ = task.await;
+    }
+}
+
+impl std::ops::Deref for DeadMansSwitch {
+    type Target = DeadMansSwitchHandle;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+async fn run(
+    client: BpxClient,
+    config: DeadMansSwitchConfig,
+    mut commands: UnboundedReceiver<Command>,
+    tx: Sender<DeadMansSwitchEvent>,
+) {
+    let mut last_heartbeat = Instant::now();
+    let mut disconnected_since: Option<Instant> = None;
+    let mut armed = true;
+    let mut stopping = false;
+    let mut cancellation: Option<Cancellation> = None;
+
+    let mut ticker = tokio::time::interval(config.check_interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let shutdown = shutdown_signal(config.shutdown_signals);
+    tokio::pin!(shutdown);
+
+    loop {
+        let next_round = cancellation
+            .as_ref()
+            .map(|cancellation| cancellation.next_round);
+        tokio::select! {
+            // Commands no longer matter once stopping, and the handles may be gone.
+            command = commands.recv(), if !stopping => match command {
+                Some(Command::Heartbeat) => {
+                    last_heartbeat = Instant::now();
+                    if !armed {
+                        armed = true;
+                        emit(&tx, DeadMansSwitchEvent::Rearmed);
+                    }
+                }
+                Some(Command::Connected(true)) => disconnected_since = None,
+                Some(Command::Connected(false)) => {
+                    disconnected_since.get_or_insert_with(Instant::now);
+                }
+                Some(Command::Trigger) => {
+                    cancellation = Some(trip(&config, TriggerReason::Manual, &tx));
+                    armed = false;
+                }
+                Some(Command::Shutdown) => {
+                    cancellation = Some(trip(&config, TriggerReason::Shutdown, &tx));
+                    stopping = true;
+                }
+                None => {
+                    cancellation = Some(trip(&config, TriggerReason::HandlesDropped, &tx));
+                    stopping = true;
+                }
+            },
+            _ = &mut shutdown, if !stopping => {
+                tracing::warn!("Dead-man's switch received a shutdown signal");
+                emit(&tx, DeadMansSwitchEvent::ShutdownSignal);
+                cancellation = Some(trip(&config, TriggerReason::Shutdown, &tx));
+                stopping = true;
+            }
+            _ = ticker.tick() => {
+                if !armed || stopping {
+                    continue;
+                }
+                let reason = if last_heartbeat.elapsed() > config.heartbeat_timeout {
+                    Some(TriggerReason::HeartbeatTimeout)
+                } else if let (Some(since), Some(timeout)) =
+                    (disconnected_since, config.disconnect_timeout)
+                {
+                    (since.elapsed() > timeout).then_some(TriggerReason::Disconnected)
+                } else {
+                    None
+                };
+                if let Some(reason) = reason {
+                    cancellation = Some(trip(&config, reason, &tx));
+                    armed = false;
+                }
+            }
+            _ = tokio::time::sleep_until(next_round.unwrap_or_else(Instant::now)),
+                if next_round.is_some() =>
+            {
+                let pending = cancellation.as_mut().expect("a cancellation is pending");
+                if cancel_round(&client, &config, pending, &tx).await {
+                    cancellation = None;
+                    if stopping {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Cancellation of the open orders, carried out in rounds so that commands are
+/// still handled between attempts.
+#[derive(Debug)]
+struct Cancellation {
+    reason: TriggerReason,
+    /// Symbols whose orders are not confirmed cancelled yet.
+    pending: Vec<String>,
+    attempt: u32,
+    next_round: Instant,
+    /// Set when the switch stops afterwards, after which the remaining symbols
+    /// are given up.
+    deadline: Option<Instant>,
+    abandoned: bool,
+}
+
+/// Reports that the switch tripped and starts cancelling on every configured symbol.
+fn trip(
+    config: &DeadMansSwitchConfig,
+    reason: TriggerReason,
+    tx: &Sender<DeadMansSwitchEvent>,
+) -> Cancellation {
+    tracing::warn!(?reason, symbols = ?config.symbols, "Dead-man's switch triggered");
+    emit(tx, DeadMansSwitchEvent::Triggered(reason));
+    let now = Instant::now();
+    Cancellation {
+        reason,
+        pending: config.symbols.clone(),
+        attempt: 0,
+        next_round: now,
+        deadline: matches!(
+            reason,
+            TriggerReason::Shutdown | TriggerReason::HandlesDropped
+        )
+        .then(|| now + config.shutdown_timeout),
+        abandoned: false,
+    }
+}
+
+/// Cancels the open orders on every pending symbol at once, keeping the symbols
+/// that failed with a retryable error for the next round. Returns `true` once no
+/// symbol is left to retry.
+async fn cancel_round(
+    client: &BpxClient,
+    config: &DeadMansSwitchConfig,
+    cancellation: &mut Cancellation,
+    tx: &Sender<DeadMansSwitchEvent>,
+) -> bool {
+    cancellation.attempt += 1;
+    let attempt = cancellation.attempt;
+    let round = join_all(
+        cancellation
+            .pending
+            .iter()
+            .map(|symbol| cancel_symbol(client, symbol)),
+    );
+    let results = match cancellation.deadline {
+        Some(deadline) => match tokio::time::timeout_at(deadline, round).await {
+            Ok(results) => results,
+            Err(_) => return give_up(cancellation, tx),
+        },
+        None => round.await,
+    };
+
+    let mut failed = Vec::new();
+    for (symbol, result) in std::mem::take(&mut cancellation.pending)
+        .into_iter()
+        .zip(results)
+    {
+        match result {
+            Ok(orders) => emit(tx, DeadMansSwitchEvent::Cancelled { symbol, orders }),
+            Err(error) if is_retryable(&error) => {
+                tracing::error!(%error, symbol, attempt, "Dead-man's switch cancel failed");
+                emit(
+                    tx,
+                    DeadMansSwitchEvent::CancelFailed {
+                        symbol: symbol.clone(),
+                        attempt,
+                        error: Arc::new(error),
+                    },
+                );
+                failed.push(symbol);
+            }
+            Err(error) => {
+                tracing::error!(%error, symbol, "Dead-man's switch gave up cancelling");
+                emit(
+                    tx,
+                    DeadMansSwitchEvent::CancelAbandoned {
+                        symbol,
+                        error: Arc::new(error),
+                    },
+                );
+                cancellation.abandoned = true;
+            }
+        }
+    }
+    cancellation.pending = failed;
+
+    if cancellation.pending.is_empty() {
+        if !cancellation.abandoned {
+            emit(tx, DeadMansSwitchEvent::Confirmed(cancellation.reason));
+        }
+        return true;
+    }
+    cancellation.next_round = Instant::now() + config.retry_interval;
+    if cancellation
+        .deadline
+        .is_some_and(|deadline| cancellation.next_round >= deadline)
+    {
+        return give_up(cancellation, tx);
+    }
+    false
+}
+
+/// Stops retrying once the shutdown deadline has passed.
+fn give_up(cancellation: &mut Cancellation, tx: &Sender<DeadMansSwitchEvent>) -> bool {
+    let symbols = std::mem::take(&mut cancellation.pending);
+    tracing::error!(?symbols, "Dead-man's switch shutdown timed out");
+    emit(tx, DeadMansSwitchEvent::ShutdownTimedOut { symbols });
+    true
+}
+
+/// Client errors other than rate limiting fail again however often they are
+/// retried.
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::BpxApiError { status_code, .. } => {
+            !status_code.is_client_error() || *status_code == StatusCode::TOO_MANY_REQUESTS
+        }
+        Error::InvalidRequest(_) | Error::NotAuthenticated | Error::SecretKey => false,
+        _ => true,
+    }
+}
+
+/// Sends an event without waiting, so that a consumer that stopped reading
+/// never delays a cancellation. Events that do not fit in the channel are
+/// dropped.
+fn emit(tx: &Sender<DeadMansSwitchEvent>, event: DeadMansSwitchEvent) {
+    if let Err(TrySendError::Full(event)) = tx.try_send(event) {
+        tracing::warn!(
+            ?event,
+            "Dead-man's switch event dropped, the channel is full"
+        );
+    }
+}
+
+async fn cancel_symbol(client: &BpxClient, symbol: &str) -> Result<Vec<Order>> {
+    let orders = client
+        .cancel_open_orders(CancelOpenOrdersPayload {
+            symbol: symbol.to_string(),
+        })
+        .await?;
+    let remaining = client.get_open_orders(Some(symbol)).await?;
+    if remaining.is_empty() {
+        Ok(orders)
+    } else {
+        Err(Error::UnexpectedResponse(
+            format!("{} orders still open on {symbol}", remaining.len()).into(),
+        ))
+    }
+}
+
+/// Resolves when the process receives a shutdown signal, or never if `enabled` is false.
+async fn shutdown_signal(enabled: bool) {
+    if !enabled {
+        return std::future::pending().await;
+    }
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(error) => {
+                tracing::error!(%error, "Could not install SIGTERM handler");
+                ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    ctrl_c().await;
+}
+
+/// Resolves on Ctrl-C. Never resolves if the handler cannot be installed.
+async fn ctrl_c() {
+    if let Err(error) = tokio::signal::ctrl_c().await {
+        tracing::error!(%error, "Could not install Ctrl-C handler");
+        std::future::pending::<()>().await;
+    }
+}
@@ -20,6 +20,11 @@ pub enum Error {
         message: Box<str>,
     },
 
+    /// Error writing a CSV export.
+    #[cfg(feature = "export")]
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+
     /// Invalid HTTP header value.
     #[error(transparent)]
     InvalidHeaderValue(#[from] reqwest::header::InvalidHeaderValue),
@@ -28,10 +33,19 @@ pub enum Error {
     #[error("Invalid request: {0}")]
     InvalidRequest(Box<str>),
 
+    /// I/O error, e.g. while reading or writing local state.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     /// Client needs to be authenticated to perform the requested action.
     #[error("Client is not authenticated")]
     NotAuthenticated,
 
+    /// Error writing a Parquet export.
+    #[cfg(feature = "export")]
+    #[error(transparent)]
+    Parquet(#[from] parquet::errors::ParquetError),
+
     /// General HTTP client error from `reqwest`.
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
@@ -40,6 +54,11 @@ pub enum Error {
     #[error("Invalid secret key")]
     SecretKey,
 
+    /// Error from the SQLite history store.
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+
     /// Error during JSON serialization or deserialization.
     #[error(transparent)]
     SerdeJson(#[from] serde_json::error::Error),
@@ -48,6 +67,10 @@ pub enum Error {
     #[error(transparent)]
     SystemTime(#[from] std::time::SystemTimeError),
 
+    /// The exchange answered with a response the client could not reconcile with the request.
+    #[error("Unexpected response: {0}")]
+    UnexpectedResponse(Box<str>),
+
     /// UTF-8 decoding error.
     #[error(transparent)]
     Utf8(#[from] std::str::Utf8Error),
@@ -0,0 +1,222 @@
+//! Iceberg orders.
+//!
+//! An iceberg order rests a limit order for at most the display quantity and,
+//! once that order has filled, places the next one until the total quantity is
+//! filled. Fills of resting orders are only observed through forwarded
+//! `account.orderUpdate` events, so [`ExecutionHandle::on_order_update`] must be
+//! fed for the execution to progress.
+//!
+//! Pausing or cancelling the execution cancels the resting order. If the
+//! remaining quantity falls below the market's minimum order quantity, the
+//! execution ends as expired with that part unfilled.
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::{BpxClient, execution::iceberg::IcebergParams, types::order::Side};
+//! use rust_decimal_macros::dec;
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let markets = client.get_markets().await?;
+//! let market = markets.iter().find(|m| m.symbol == "SOL_USDC").unwrap();
+//!
+//! let params = IcebergParams::new(market, Side::Ask, dec!(500), dec!(10), dec!(200))?;
+//! let execution = client.iceberg(params);
+//! // Forward `account.orderUpdate.SOL_USDC` events with `execution.on_order_update`.
+//! let progress = execution.wait().await;
+//! println!("filled {}", progress.filled_quantity);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{sync::Arc, time::Duration};
+
+use bpx_api_types::{
+    markets::{Market, QuantityFilter},
+    order::{ExecuteOrderPayload, OrderType, Side, TimeInForce},
+};
+use rust_decimal::Decimal;
+use tokio::time::Instant;
+
+use super::{Execution, ExecutionHandle, ExecutionState, tradable_quantity};
+use crate::{BpxClient, Error, Result, client_id::ClientIdAllocator};
+
+/// Default delay before retrying a rejected child order.
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Parameters of an iceberg execution.
+#[derive(Debug, Clone)]
+pub struct IcebergParams {
+    symbol: String,
+    side: Side,
+    quantity: Decimal,
+    display_quantity: Decimal,
+    price: Decimal,
+    quantity_filter: QuantityFilter,
+    post_only: bool,
+    refill_delay: Duration,
+    retry_interval: Duration,
+    client_ids: Option<(Arc<ClientIdAllocator>, String)>,
+}
+
+impl IcebergParams {
+    /// Creates parameters to fill `quantity` on `market` at `price`, showing at
+    /// most `display_quantity` at a time.
+    ///
+    /// Fails if `display_quantity`, rounded down to the market's step size, is
+    /// zero or below the minimum order quantity.
+    pub fn new(
+        market: &Market,
+        side: Side,
+        quantity: Decimal,
+        display_quantity: Decimal,
+        price: Decimal,
+    ) -> Result<Self> {
+        if tradable_quantity(display_quantity, &market.filters.quantity).is_none() {
+            return Err(Error::InvalidRequest(
+                format!(
+                    "display quantity {display_quantity} is below the minimum order quantity {} of {}",
+                    market.filters.quantity.min_quantity, market.symbol
+                )
+                .into(),
+            ));
+        }
+        Ok(Self {
+            symbol: market.symbol.clone(),
+            side,
+            quantity,
+            display_quantity,
+            price,
+            quantity_filter: market.filters.quantity.clone(),
+            post_only: false,
+            refill_delay: Duration::ZERO,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            client_ids: None,
+        })
+    }
+
+    /// Places child orders as post-only, so they never take liquidity.
+    pub fn with_post_only(mut self, post_only: bool) -> Self {
+        self.post_only = post_only;
+        self
+    }
+
+    /// Waits this long after a child order fills before showing the next one.
+    pub fn with_refill_delay(mut self, refill_delay: Duration) -> Self {
+        self.refill_delay = refill_delay;
+        self
+    }
+
+    /// Sets the delay before retrying a rejected child order. Defaults to one second.
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Allocates child order client ids from `allocator` under `tag`.
+    pub fn with_client_ids(
+        mut self,
+        allocator: Arc<ClientIdAllocator>,
+        tag: impl Into<String>,
+    ) -> Self {
+        self.client_ids = Some((allocator, tag.into()));
+        self
+    }
+}
+
+impl BpxClient {
+    /// Starts an iceberg execution.
+    pub fn iceberg(&self, params: IcebergParams) -> ExecutionHandle {
+        let client = self.clone();
+        Execution::spawn(
+            params.symbol.clone(),
+            params.side,
+            params.quantity,
+            params.client_ids.clone(),
+            move |execution| run(client, params, execution),
+        )
+    }
+}
+
+async fn run(client: BpxClient, params: IcebergParams, mut execution: Execution) {
+    let mut resting: Option<String> = None;
+    let mut next_order_at = Instant::now();
+
+    loop {
+        execution.drain_updates();
+        match execution.state() {
+            ExecutionState::Cancelled
+            | ExecutionState::Completed
+            | ExecutionState::Expired
+            | ExecutionState::Failed => {
+                execution
+                    .cancel_and_finish(&client, &params.symbol, resting.as_deref())
+                    .await;
+                return;
+            }
+            ExecutionState::Paused => {
+                if let Some(order_id) = &resting {
+                    if execution
+                        .cancel_child(&client, &params.symbol, order_id)
+                        .await
+                        .is_err()
+                    {
+                        execution.retry_cancel_later().await;
+                        continue;
+                    }
+                    resting = None;
+                }
+                execution.changed(None).await;
+                continue;
+            }
+            ExecutionState::Running => {}
+        }
+
+        if let Some(order_id) = &resting {
+            if execution.is_open(order_id) {
+                execution.changed(None).await;
+                continue;
+            }
+            resting = None;
+            next_order_at = Instant::now() + params.refill_delay;
+        }
+        if Instant::now() < next_order_at {
+            execution.changed(Some(next_order_at)).await;
+            continue;
+        }
+
+        let remaining = params.quantity - execution.filled_quantity();
+        let Some(quantity) = tradable_quantity(
+            remaining.min(params.display_quantity),
+            &params.quantity_filter,
+        ) else {
+            break;
+        };
+
+        let payload = ExecuteOrderPayload {
+            client_id: execution.next_client_id(),
+            order_type: OrderType::Limit,
+            post_only: params.post_only.then_some(true),
+            price: Some(params.price),
+            quantity: Some(quantity),
+            side: params.side,
+            symbol: params.symbol.clone(),
+            time_in_force: Some(TimeInForce::GTC),
+            ..Default::default()
+        };
+        match client.execute_order(payload).await {
+            Ok(order) => {
+                resting = Some(execution.track(&order));
+            }
+            Err(err) => {
+                execution.record_error(err.to_string());
+                next_order_at = Instant::now() + params.retry_interval;
+            }
+        }
+    }
+
+    if execution.filled_quantity() >= params.quantity {
+        execution.finish(ExecutionState::Completed);
+    } else {
+        execution.finish(ExecutionState::Expired);
+    }
+}
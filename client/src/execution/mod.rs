@@ -0,0 +1,441 @@
+//! Client-side execution algorithms built on top of [`BpxClient::execute_order`].
+//!
+//! Every algorithm runs as a spawned task and is controlled through an
+//! [`ExecutionHandle`], which exposes progress, pause, resume and cancel
+//! controls. Fills are tracked from the REST responses of child orders and from
+//! `account.orderUpdate` events forwarded with [`ExecutionHandle::on_order_update`].
+//!
+//! Algorithms that rest orders on the book additionally need `bookTicker` events
+//! forwarded with [`ExecutionHandle::on_book_ticker`].
+//!
+//! - [`slicer`]: TWAP and VWAP slicing of a parent order over a time horizon.
+//! - [`iceberg`]: a limit order that only shows part of its quantity at a time.
+//! - [`peg`]: a post-only limit order pegged to the best bid or ask.
+//!
+//! [`BpxClient::execute_order`]: crate::BpxClient::execute_order
+
+use std::{future, sync::Arc, time::Duration};
+
+use bpx_api_types::{
+    markets::{QuantityFilter, TickerUpdate},
+    order::{Order, OrderUpdate, Side},
+};
+use rust_decimal::Decimal;
+use tokio::{
+    sync::{
+        mpsc::{UnboundedReceiver, UnboundedSender},
+        watch,
+    },
+    task::JoinHandle,
+    time::Instant,
+};
+
+use crate::{BpxClient, Result, client_id::ClientIdAllocator, order_tracker::OrderTracker};
+
+pub mod iceberg;
+pub mod peg;
+pub mod slicer;
+
+/// Number of attempts made to cancel a resting child order before an execution
+/// stops.
+const CANCEL_ATTEMPTS: u32 = 3;
+
+/// Delay between attempts to cancel a resting child order.
+const CANCEL_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The lifecycle state of an execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExecutionState {
+    Running,
+    Paused,
+    Cancelled,
+    Completed,
+    /// The execution ran out of time before filling its target quantity.
+    Expired,
+    /// The execution stopped, but its resting child order could not be
+    /// cancelled and may still be on the book. See
+    /// [`ExecutionProgress::last_error`].
+    Failed,
+}
+
+impl ExecutionState {
+    /// Returns `true` once the execution has stopped for good.
+    pub const fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            Self::Cancelled | Self::Completed | Self::Expired | Self::Failed
+        )
+    }
+}
+
+/// A snapshot of an execution's progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionProgress {
+    pub symbol: String,
+    pub side: Side,
+    pub state: ExecutionState,
+    /// Total quantity the execution aims to fill.
+    pub target_quantity: Decimal,
+    /// Base quantity filled so far across all child orders.
+    pub filled_quantity: Decimal,
+    /// Quote quantity filled so far across all child orders.
+    pub filled_quote_quantity: Decimal,
+    /// Number of child orders submitted.
+    pub child_orders: u32,
+    /// The most recent error returned while submitting a child order.
+    pub last_error: Option<String>,
+}
+
+impl ExecutionProgress {
+    fn new(symbol: String, side: Side, target_quantity: Decimal) -> Self {
+        Self {
+            symbol,
+            side,
+            state: ExecutionState::Running,
+            target_quantity,
+            filled_quantity: Decimal::ZERO,
+            filled_quote_quantity: Decimal::ZERO,
+            child_orders: 0,
+            last_error: None,
+        }
+    }
+
+    /// Quantity still to be filled.
+    pub fn remaining_quantity(&self) -> Decimal {
+        (self.target_quantity - self.filled_quantity).max(Decimal::ZERO)
+    }
+
+    /// Volume weighted average fill price.
+    pub fn average_price(&self) -> Option<Decimal> {
+        (!self.filled_quantity.is_zero()).then(|| self.filled_quote_quantity / self.filled_quantity)
+    }
+
+    /// Filled fraction of the target quantity, between zero and one.
+    pub fn completion(&self) -> Decimal {
+        if self.target_quantity.is_zero() {
+            Decimal::ONE
+        } else {
+            (self.filled_quantity / self.target_quantity).min(Decimal::ONE)
+        }
+    }
+}
+
+/// Controls a running execution.
+#[derive(Debug)]
+pub struct ExecutionHandle {
+    control: watch::Sender<ExecutionState>,
+    progress: watch::Receiver<ExecutionProgress>,
+    updates: UnboundedSender<OrderUpdate>,
+    quotes: watch::Sender<Option<TickerUpdate>>,
+    task: JoinHandle<()>,
+}
+
+impl ExecutionHandle {
+    /// Returns the latest progress snapshot.
+    pub fn progress(&self) -> ExecutionProgress {
+        self.progress.borrow().clone()
+    }
+
+    /// Returns a receiver notified on every progress change.
+    pub fn watch_progress(&self) -> watch::Receiver<ExecutionProgress> {
+        self.progress.clone()
+    }
+
+    /// Stops submitting child orders until [`ExecutionHandle::resume`] is called.
+    /// A resting child order is cancelled first, and the execution is reported
+    /// as running until the cancel succeeds.
+    pub fn pause(&self) {
+        self.set_state(ExecutionState::Paused);
+    }
+
+    /// Resumes a paused execution.
+    pub fn resume(&self) {
+        self.set_state(ExecutionState::Running);
+    }
+
+    /// Cancels the execution and any resting child order.
+    pub fn cancel(&self) {
+        self.set_state(ExecutionState::Cancelled);
+    }
+
+    /// Forwards an `account.orderUpdate` event so fills of child orders are tracked.
+    /// Updates for unrelated orders are ignored.
+    pub fn on_order_update(&self, update: OrderUpdate) {
+        let _ = self.updates.send(update);
+    }
+
+    /// Forwards a `bookTicker` event of the execution's market. Only the latest
+    /// best bid and ask are kept.
+    pub fn on_book_ticker(&self, ticker: TickerUpdate) {
+        self.quotes.send_replace(Some(ticker));
+    }
+
+    /// Waits for the execution to finish and returns its final progress.
+    pub async fn wait(self) -> ExecutionProgress {
+        let Self { progress, task, .. } = self;
+        let _ = task.await;
+        progress.borrow().clone()
+    }
+
+    fn set_state(&self, state: ExecutionState) {
+        self.control.send_if_modified(|current| {
+            if current.is_finished() || *current == state {
+                false
+            } else {
+                *current = state;
+                true
+            }
+        });
+    }
+}
+
+/// Shared plumbing of an execution task: control state, order updates, progress
+/// reporting and child order tracking.
+struct Execution {
+    control: watch::Receiver<ExecutionState>,
+    updates: UnboundedReceiver<OrderUpdate>,
+    quotes: watch::Receiver<Option<TickerUpdate>>,
+    progress: watch::Sender<ExecutionProgress>,
+    tracker: OrderTracker,
+    client_ids: Option<(Arc<ClientIdAllocator>, String)>,
+    /// Set once the handle is dropped; nothing can control or feed the execution anymore.
+    detached: bool,
+}
+
+impl Execution {
+    /// Spawns `run` with a fresh execution context and returns its handle.
+    fn spawn<F, Fut>(
+        symbol: String,
+        side: Side,
+        target_quantity: Decimal,
+        client_ids: Option<(Arc<ClientIdAllocator>, String)>,
+        run: F,
+    ) -> ExecutionHandle
+    where
+        F: FnOnce(Execution) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (control_tx, control_rx) = watch::channel(ExecutionState::Running);
+        let (progress_tx, progress_rx) =
+            watch::channel(ExecutionProgress::new(symbol, side, target_quantity));
+        let (updates_tx, updates_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (quotes_tx, quotes_rx) = watch::channel(None);
+
+        let execution = Execution {
+            control: control_rx,
+            updates: updates_rx,
+            quotes: quotes_rx,
+            progress: progress_tx,
+            tracker: OrderTracker::new(),
+            client_ids,
+            detached: false,
+        };
+        let task = tokio::spawn(run(execution));
+
+        ExecutionHandle {
+            control: control_tx,
+            progress: progress_rx,
+            updates: updates_tx,
+            quotes: quotes_tx,
+            task,
+        }
+    }
+
+    /// Returns the requested state. A detached execution is treated as cancelled.
+    fn state(&self) -> ExecutionState {
+        if self.detached {
+            ExecutionState::Cancelled
+        } else {
+            *self.control.borrow()
+        }
+    }
+
+    /// Returns the latest forwarded best bid and ask.
+    fn quote(&self) -> Option<TickerUpdate> {
+        self.quotes.borrow().clone()
+    }
+
+    /// Waits for a control change, an order update, a new quote or `deadline`,
+    /// whichever comes first.
+    async fn changed(&mut self, deadline: Option<Instant>) {
+        let deadline = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => future::pending().await,
+            }
+        };
+        tokio::select! {
+            changed = self.control.changed() => {
+                if changed.is_err() {
+                    self.detached = true;
+                }
+            }
+            Some(update) = self.updates.recv() => self.apply(&update),
+            Ok(()) = self.quotes.changed() => {}
+            _ = deadline => {}
+        }
+        self.refresh();
+    }
+
+    /// Allocates a client id for the next child order, if an allocator is configured.
+    fn next_client_id(&mut self) -> Option<u32> {
+        let (allocator, tag) = self.client_ids.as_ref()?;
+        match allocator.next_id(tag) {
+            Ok(client_id) => Some(client_id),
+            Err(err) => {
+                self.record_error(err.to_string());
+                None
+            }
+        }
+    }
+
+    /// Starts tracking a submitted child order and returns its order id.
+    fn track(&mut self, order: &Order) -> String {
+        let order_id = self.tracker.track(order).order_id.clone();
+        self.progress
+            .send_modify(|progress| progress.child_orders += 1);
+        self.refresh();
+        order_id
+    }
+
+    /// Returns `true` while the child order with `order_id` can still trade.
+    fn is_open(&self, order_id: &str) -> bool {
+        self.tracker
+            .get(order_id)
+            .is_some_and(|order| order.is_open())
+    }
+
+    /// Cancels a resting child order and records its final state. Fails if the
+    /// cancel was rejected and the order may still be open; the order is kept
+    /// so that the cancel can be retried.
+    async fn cancel_child(
+        &mut self,
+        client: &BpxClient,
+        symbol: &str,
+        order_id: &str,
+    ) -> Result<()> {
+        match client.cancel_order(symbol, Some(order_id), None).await {
+            Ok(order) => {
+                self.tracker.track(&order);
+                self.refresh();
+                Ok(())
+            }
+            Err(err) => {
+                // The order may have been filled or cancelled in the meantime.
+                self.drain_updates();
+                if !self.is_open(order_id) {
+                    return Ok(());
+                }
+                self.record_error(err.to_string());
+                Err(err)
+            }
+        }
+    }
+
+    /// Cancels the resting child order of a stopping execution, retrying
+    /// failed cancels, and finishes as [`ExecutionState::Cancelled`], or as
+    /// [`ExecutionState::Failed`] if the order may still be open.
+    async fn cancel_and_finish(
+        &mut self,
+        client: &BpxClient,
+        symbol: &str,
+        order_id: Option<&str>,
+    ) {
+        if let Some(order_id) = order_id {
+            for attempt in 1..=CANCEL_ATTEMPTS {
+                if self.cancel_child(client, symbol, order_id).await.is_ok() {
+                    break;
+                }
+                if attempt == CANCEL_ATTEMPTS {
+                    self.finish(ExecutionState::Failed);
+                    return;
+                }
+                tokio::time::sleep(CANCEL_RETRY_INTERVAL).await;
+            }
+        }
+        self.finish(ExecutionState::Cancelled);
+    }
+
+    /// Waits before retrying a cancel that failed.
+    async fn retry_cancel_later(&mut self) {
+        self.changed(Some(Instant::now() + CANCEL_RETRY_INTERVAL))
+            .await;
+    }
+
+    /// Applies an order update if it belongs to a child order.
+    fn apply(&mut self, update: &OrderUpdate) {
+        if self.tracker.get(&update.order_id).is_some() {
+            self.tracker.apply(update);
+            self.refresh();
+        }
+    }
+
+    /// Applies every order update received so far.
+    fn drain_updates(&mut self) {
+        while let Ok(update) = self.updates.try_recv() {
+            self.apply(&update);
+        }
+    }
+
+    fn record_error(&self, error: String) {
+        tracing::warn!(%error, "Execution child order failed");
+        self.progress
+            .send_modify(|progress| progress.last_error = Some(error));
+    }
+
+    fn finish(&self, state: ExecutionState) {
+        self.progress.send_modify(|progress| progress.state = state);
+    }
+
+    /// Recomputes filled quantities from the tracked child orders.
+    fn refresh(&self) {
+        let (filled, quote) =
+            self.tracker
+                .orders()
+                .fold((Decimal::ZERO, Decimal::ZERO), |(base, quote), order| {
+                    (
+                        base + order.executed_quantity,
+                        quote + order.executed_quote_quantity,
+                    )
+                });
+        let state = match self.state() {
+            // A paused execution keeps running until its resting order is off
+            // the book.
+            ExecutionState::Paused if self.tracker.orders().any(|order| order.is_open()) => {
+                ExecutionState::Running
+            }
+            state => state,
+        };
+        self.progress.send_if_modified(|progress| {
+            let changed = progress.filled_quantity != filled
+                || progress.filled_quote_quantity != quote
+                || progress.state != state;
+            progress.filled_quantity = filled;
+            progress.filled_quote_quantity = quote;
+            progress.state = state;
+            changed
+        });
+    }
+
+    fn filled_quantity(&self) -> Decimal {
+        self.progress.borrow().filled_quantity
+    }
+}
+
+/// Rounds `quantity` down to a multiple of the filter's step size.
+pub fn round_to_step(quantity: Decimal, filter: &QuantityFilter) -> Decimal {
+    if filter.step_size.is_zero() {
+        return quantity;
+    }
+    ((quantity / filter.step_size).floor() * filter.step_size).normalize()
+}
+
+/// Rounds `quantity` down to the step size and returns it if the exchange would
+/// accept it, or `None` if it is below the minimum order quantity.
+pub fn tradable_quantity(quantity: Decimal, filter: &QuantityFilter) -> Option<Decimal> {
+    let mut quantity = round_to_step(quantity, filter);
+    if let Some(max_quantity) = filter.max_quantity {
+        quantity = quantity.min(round_to_step(max_quantity, filter));
+    }
+    (quantity > Decimal::ZERO && quantity >= filter.min_quantity).then_some(quantity)
+}
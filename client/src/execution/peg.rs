@@ -0,0 +1,306 @@
+//! Post-only orders pegged to the best bid or ask.
+//!
+//! A pegged bid rests at the best bid minus the offset and a pegged ask at the
+//! best ask plus the offset, rounded away from the spread to the tick size. When
+//! the best bid or ask moves, the resting order is cancelled and placed again
+//! at the new price, at most once per requote interval so the execution stays
+//! within the exchange's rate limits.
+//!
+//! Quotes come from `bookTicker` events forwarded with
+//! [`ExecutionHandle::on_book_ticker`] and fills from `account.orderUpdate`
+//! events forwarded with [`ExecutionHandle::on_order_update`]. Pausing or
+//! cancelling the execution cancels the resting order.
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::{BpxClient, execution::peg::PegParams, types::order::Side};
+//! use rust_decimal_macros::dec;
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let markets = client.get_markets().await?;
+//! let market = markets.iter().find(|m| m.symbol == "SOL_USDC").unwrap();
+//!
+//! let params = PegParams::new(market, Side::Bid, dec!(25), dec!(0.01)).with_price_limit(dec!(150));
+//! let execution = client.peg(params);
+//! // Forward `bookTicker.SOL_USDC` events with `execution.on_book_ticker` and
+//! // `account.orderUpdate.SOL_USDC` events with `execution.on_order_update`.
+//! let progress = execution.wait().await;
+//! println!("filled {} at {:?}", progress.filled_quantity, progress.average_price());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{sync::Arc, time::Duration};
+
+use bpx_api_types::{
+    markets::{Market, PriceFilter, QuantityFilter, TickerUpdate},
+    order::{ExecuteOrderPayload, OrderType, Side, TimeInForce},
+};
+use rust_decimal::Decimal;
+use tokio::time::Instant;
+
+use super::{Execution, ExecutionHandle, ExecutionState, tradable_quantity};
+use crate::{BpxClient, client_id::ClientIdAllocator};
+
+/// Default minimum time between two requotes.
+const DEFAULT_MIN_REQUOTE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Parameters of a pegged execution.
+#[derive(Debug, Clone)]
+pub struct PegParams {
+    symbol: String,
+    side: Side,
+    quantity: Decimal,
+    offset: Decimal,
+    price_filter: PriceFilter,
+    quantity_filter: QuantityFilter,
+    price_limit: Option<Decimal>,
+    min_requote_interval: Duration,
+    client_ids: Option<(Arc<ClientIdAllocator>, String)>,
+}
+
+impl PegParams {
+    /// Creates parameters to fill `quantity` on `market` with a post-only order
+    /// resting `offset` away from the best price on its own side of the book.
+    /// A zero offset joins the best bid or ask.
+    pub fn new(market: &Market, side: Side, quantity: Decimal, offset: Decimal) -> Self {
+        Self {
+            symbol: market.symbol.clone(),
+            side,
+            quantity,
+            offset,
+            price_filter: market.filters.price.clone(),
+            quantity_filter: market.filters.quantity.clone(),
+            price_limit: None,
+            min_requote_interval: DEFAULT_MIN_REQUOTE_INTERVAL,
+            client_ids: None,
+        }
+    }
+
+    /// Never bids above, or offers below, this price.
+    pub fn with_price_limit(mut self, price_limit: Decimal) -> Self {
+        self.price_limit = Some(price_limit);
+        self
+    }
+
+    /// Sets the minimum time between two requotes. Defaults to one second.
+    pub fn with_min_requote_interval(mut self, min_requote_interval: Duration) -> Self {
+        self.min_requote_interval = min_requote_interval;
+        self
+    }
+
+    /// Allocates child order client ids from `allocator` under `tag`.
+    pub fn with_client_ids(
+        mut self,
+        allocator: Arc<ClientIdAllocator>,
+        tag: impl Into<String>,
+    ) -> Self {
+        self.client_ids = Some((allocator, tag.into()));
+        self
+    }
+
+    /// Returns the price to rest at for the given best bid and ask.
+    pub fn peg_price(&self, ticker: &TickerUpdate) -> Decimal {
+        let tick_size = self.price_filter.tick_size;
+        let round = |price: Decimal, up: bool| {
+            if tick_size.is_zero() {
+                price
+            } else if up {
+                ((price / tick_size).ceil() * tick_size).normalize()
+            } else {
+                ((price / tick_size).floor() * tick_size).normalize()
+            }
+        };
+        match self.side {
+            Side::Bid => {
+                let price = round(ticker.bid_price - self.offset, false);
+                self.price_limit.map_or(price, |limit| price.min(limit))
+            }
+            Side::Ask => {
+                let price = round(ticker.ask_price + self.offset, true);
+                self.price_limit.map_or(price, |limit| price.max(limit))
+            }
+        }
+    }
+}
+
+impl BpxClient {
+    /// Starts a pegged execution.
+    pub fn peg(&self, params: PegParams) -> ExecutionHandle {
+        let client = self.clone();
+        Execution::spawn(
+            params.symbol.clone(),
+            params.side,
+            params.quantity,
+            params.client_ids.clone(),
+            move |execution| run(client, params, execution),
+        )
+    }
+}
+
+async fn run(client: BpxClient, params: PegParams, mut execution: Execution) {
+    // Order id and price of the resting child order.
+    let mut resting: Option<(String, Decimal)> = None;
+    let mut next_quote_at = Instant::now();
+
+    loop {
+        execution.drain_updates();
+        match execution.state() {
+            ExecutionState::Cancelled
+            | ExecutionState::Completed
+            | ExecutionState::Expired
+            | ExecutionState::Failed => {
+                let order_id = resting.as_ref().map(|(order_id, _)| order_id.as_str());
+                execution
+                    .cancel_and_finish(&client, &params.symbol, order_id)
+                    .await;
+                return;
+            }
+            ExecutionState::Paused => {
+                if let Some((order_id, _)) = &resting {
+                    if execution
+                        .cancel_child(&client, &params.symbol, order_id)
+                        .await
+                        .is_err()
+                    {
+                        execution.retry_cancel_later().await;
+                        continue;
+                    }
+                    resting = None;
+                }
+                execution.changed(None).await;
+                continue;
+            }
+            ExecutionState::Running => {}
+        }
+
+        if resting
+            .as_ref()
+            .is_some_and(|(order_id, _)| !execution.is_open(order_id))
+        {
+            resting = None;
+        }
+
+        let remaining = params.quantity - execution.filled_quantity();
+        let quantity = tradable_quantity(remaining, &params.quantity_filter);
+        if quantity.is_none() && resting.is_none() {
+            break;
+        }
+
+        let Some(ticker) = execution.quote() else {
+            execution.changed(None).await;
+            continue;
+        };
+        let price = params.peg_price(&ticker);
+        if resting
+            .as_ref()
+            .is_some_and(|(_, resting_price)| *resting_price == price)
+        {
+            execution.changed(None).await;
+            continue;
+        }
+
+        if Instant::now() < next_quote_at {
+            execution.changed(Some(next_quote_at)).await;
+            continue;
+        }
+        next_quote_at = Instant::now() + params.min_requote_interval;
+
+        if let Some((order_id, _)) = &resting {
+            // Place the replacement on the next pass, once the fills of the
+            // cancelled order are known. A failed cancel keeps the order and
+            // is retried at the next requote instead.
+            if execution
+                .cancel_child(&client, &params.symbol, order_id)
+                .await
+                .is_ok()
+            {
+                resting = None;
+                next_quote_at = Instant::now();
+            }
+            continue;
+        }
+        let Some(quantity) = quantity else {
+            continue;
+        };
+
+        let payload = ExecuteOrderPayload {
+            client_id: execution.next_client_id(),
+            order_type: OrderType::Limit,
+            post_only: Some(true),
+            price: Some(price),
+            quantity: Some(quantity),
+            side: params.side,
+            symbol: params.symbol.clone(),
+            time_in_force: Some(TimeInForce::GTC),
+            ..Default::default()
+        };
+        match client.execute_order(payload).await {
+            Ok(order) => resting = Some((execution.track(&order), price)),
+            Err(err) => execution.record_error(err.to_string()),
+        }
+    }
+
+    execution.finish(ExecutionState::Completed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn params(side: Side, offset: Decimal) -> PegParams {
+        let market: Market = serde_json::from_value(serde_json::json!({
+            "symbol": "SOL_USDC",
+            "baseSymbol": "SOL",
+            "quoteSymbol": "USDC",
+            "marketType": "SPOT",
+            "filters": {
+                "price": { "minPrice": "0.01", "tickSize": "0.01" },
+                "quantity": { "minQuantity": "0.1", "stepSize": "0.1" }
+            },
+            "orderBookState": "Open",
+            "createdAt": "2025-01-21T06:34:54.691858",
+            "visible": true
+        }))
+        .unwrap();
+        PegParams::new(&market, side, dec!(1), offset)
+    }
+
+    fn ticker(bid: Decimal, ask: Decimal) -> TickerUpdate {
+        TickerUpdate {
+            event_type: "bookTicker".to_string(),
+            event_time: 0,
+            symbol: "SOL_USDC".to_string(),
+            ask_price: ask,
+            ask_quantity: dec!(1),
+            bid_price: bid,
+            bid_quantity: dec!(1),
+            update_id: 0,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn pegs_away_from_the_spread_on_tick_size() {
+        let quote = ticker(dec!(100.00), dec!(100.10));
+        assert_eq!(params(Side::Bid, dec!(0)).peg_price(&quote), dec!(100));
+        assert_eq!(
+            params(Side::Bid, dec!(0.015)).peg_price(&quote),
+            dec!(99.98)
+        );
+        assert_eq!(
+            params(Side::Ask, dec!(0.015)).peg_price(&quote),
+            dec!(100.12)
+        );
+    }
+
+    #[test]
+    fn respects_the_price_limit() {
+        let quote = ticker(dec!(100), dec!(101));
+        let bid = params(Side::Bid, dec!(0)).with_price_limit(dec!(99.5));
+        assert_eq!(bid.peg_price(&quote), dec!(99.5));
+        let ask = params(Side::Ask, dec!(0)).with_price_limit(dec!(102));
+        assert_eq!(ask.peg_price(&quote), dec!(102));
+    }
+}
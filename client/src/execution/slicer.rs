@@ -0,0 +1,432 @@
+//! TWAP and VWAP slicing of a parent order.
+//!
+//! The parent quantity is split into `slices` child orders spread evenly over the
+//! horizon. A TWAP schedule targets equal quantities per slice, while a VWAP
+//! schedule weights each slice by the volume traded in the same position of a
+//! historical window. Each slice tops the execution up to its cumulative target,
+//! so quantities that were not filled earlier roll into later slices.
+//!
+//! Pausing shifts the remaining schedule by the time spent paused, so slices
+//! keep their spacing after a resume and the horizon is extended accordingly,
+//! rather than the missed slices being sent at once.
+//!
+//! Child orders are market orders, or immediate-or-cancel limit orders at the
+//! price limit when one is set, so no child order rests on the book. Child
+//! quantities are rounded down to [`QuantityFilter::step_size`].
+//!
+//! The execution ends [`Completed`](ExecutionState::Completed) once the parent
+//! quantity is filled, or [`Expired`](ExecutionState::Expired) if the horizon
+//! ends first, e.g. because of the participation cap, unfilled limit orders or
+//! failed slices. The last failure is kept in
+//! [`ExecutionProgress::last_error`](super::ExecutionProgress::last_error).
+//!
+//! ## Example
+//! ```no_run
+//! use std::time::Duration;
+//! use bpx_api_client::{BpxClient, execution::slicer::SliceParams, types::order::Side};
+//! use rust_decimal_macros::dec;
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let markets = client.get_markets().await?;
+//! let market = markets.iter().find(|m| m.symbol == "SOL_USDC").unwrap();
+//!
+//! let params = SliceParams::new(market, Side::Bid, dec!(100), Duration::from_secs(3600), 60)
+//!     .with_max_participation(dec!(0.1))
+//!     .with_price_limit(dec!(200));
+//! let execution = client.twap(params);
+//! let progress = execution.wait().await;
+//! println!("filled {} at {:?}", progress.filled_quantity, progress.average_price());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{sync::Arc, time::Duration};
+
+use bpx_api_types::{
+    markets::{Kline, KlineInterval, Market, QuantityFilter},
+    order::{ExecuteOrderPayload, OrderType, Side, TimeInForce},
+};
+use futures_util::TryStreamExt;
+use rust_decimal::Decimal;
+use tokio::time::Instant;
+
+use super::{Execution, ExecutionHandle, ExecutionState, tradable_quantity};
+use crate::{BpxClient, Result, client_id::ClientIdAllocator, now_millis};
+
+/// Number of historical trades requested per page to measure market volume.
+const VOLUME_PAGE_SIZE: u64 = 1000;
+
+/// Maximum number of historical trade pages read to measure market volume.
+const VOLUME_MAX_PAGES: u64 = 10;
+
+/// Parameters of a sliced execution.
+#[derive(Debug, Clone)]
+pub struct SliceParams {
+    symbol: String,
+    side: Side,
+    quantity: Decimal,
+    horizon: Duration,
+    slices: u32,
+    quantity_filter: QuantityFilter,
+    max_participation: Option<Decimal>,
+    price_limit: Option<Decimal>,
+    client_ids: Option<(Arc<ClientIdAllocator>, String)>,
+}
+
+impl SliceParams {
+    /// Creates parameters to fill `quantity` on `market` in `slices` child orders
+    /// spread over `horizon`.
+    pub fn new(
+        market: &Market,
+        side: Side,
+        quantity: Decimal,
+        horizon: Duration,
+        slices: u32,
+    ) -> Self {
+        Self {
+            symbol: market.symbol.clone(),
+            side,
+            quantity,
+            horizon,
+            slices: slices.max(1),
+            quantity_filter: market.filters.quantity.clone(),
+            max_participation: None,
+            price_limit: None,
+            client_ids: None,
+        }
+    }
+
+    /// Caps each child order at this fraction (e.g. `0.1` for 10%) of the market
+    /// volume traded during the previous slice interval.
+    pub fn with_max_participation(mut self, max_participation: Decimal) -> Self {
+        self.max_participation = Some(max_participation);
+        self
+    }
+
+    /// Never buys above, or sells below, this price.
+    pub fn with_price_limit(mut self, price_limit: Decimal) -> Self {
+        self.price_limit = Some(price_limit);
+        self
+    }
+
+    /// Allocates child order client ids from `allocator` under `tag`.
+    pub fn with_client_ids(
+        mut self,
+        allocator: Arc<ClientIdAllocator>,
+        tag: impl Into<String>,
+    ) -> Self {
+        self.client_ids = Some((allocator, tag.into()));
+        self
+    }
+
+    fn interval(&self) -> Duration {
+        self.horizon / self.slices
+    }
+}
+
+impl BpxClient {
+    /// Starts a TWAP execution that targets equal quantities per slice.
+    pub fn twap(&self, params: SliceParams) -> ExecutionHandle {
+        let weights = vec![Decimal::ONE; params.slices as usize];
+        self.start_slicer(params, weights)
+    }
+
+    /// Starts a VWAP execution whose slices follow the volume profile of the
+    /// window of the same length as the horizon starting at `history_start`
+    /// (in seconds), fetched with `get_k_lines` at `kline_interval`.
+    pub async fn vwap(
+        &self,
+        params: SliceParams,
+        kline_interval: KlineInterval,
+        history_start: i64,
+    ) -> Result<ExecutionHandle> {
+        let history_end = history_start + params.horizon.as_secs() as i64;
+        let klines = self
+            .get_k_lines(
+                &params.symbol,
+                kline_interval,
+                history_start,
+                Some(history_end),
+            )
+            .await?;
+        let weights = volume_weights(&klines, params.slices);
+        Ok(self.start_slicer(params, weights))
+    }
+
+    fn start_slicer(&self, params: SliceParams, weights: Vec<Decimal>) -> ExecutionHandle {
+        let client = self.clone();
+        Execution::spawn(
+            params.symbol.clone(),
+            params.side,
+            params.quantity,
+            params.client_ids.clone(),
+            move |execution| run(client, params, weights, execution),
+        )
+    }
+}
+
+/// Returns the cumulative quantity to have filled by the end of each slice.
+pub fn cumulative_targets(quantity: Decimal, weights: &[Decimal]) -> Vec<Decimal> {
+    let total: Decimal = weights.iter().sum();
+    if total.is_zero() {
+        return cumulative_targets(quantity, &vec![Decimal::ONE; weights.len()]);
+    }
+    let mut cumulative = Decimal::ZERO;
+    weights
+        .iter()
+        .enumerate()
+        .map(|(index, weight)| {
+            cumulative += weight;
+            if index + 1 == weights.len() {
+                quantity
+            } else {
+                quantity * cumulative / total
+            }
+        })
+        .collect()
+}
+
+/// Splits historical klines into `slices` consecutive buckets and returns the
+/// traded volume of each bucket.
+pub fn volume_weights(klines: &[Kline], slices: u32) -> Vec<Decimal> {
+    let slices = slices.max(1) as usize;
+    let mut weights = vec![Decimal::ZERO; slices];
+    for (index, kline) in klines.iter().enumerate() {
+        weights[index * slices / klines.len()] += kline.volume;
+    }
+    weights
+}
+
+async fn run(
+    client: BpxClient,
+    params: SliceParams,
+    weights: Vec<Decimal>,
+    mut execution: Execution,
+) {
+    let mut start = Instant::now();
+    let interval = params.interval();
+    let targets = cumulative_targets(params.quantity, &weights);
+
+    for (index, target) in targets.into_iter().enumerate() {
+        if !wait_until(&mut execution, &mut start, interval * index as u32).await {
+            execution.finish(ExecutionState::Cancelled);
+            return;
+        }
+        execution.drain_updates();
+
+        let mut due = target - execution.filled_quantity();
+        if let Some(max_participation) = params.max_participation {
+            match market_volume(&client, &params.symbol, interval).await {
+                Ok(volume) => due = due.min(volume * max_participation),
+                Err(err) => {
+                    execution.record_error(err.to_string());
+                    continue;
+                }
+            }
+        }
+        let Some(quantity) = tradable_quantity(due, &params.quantity_filter) else {
+            continue;
+        };
+
+        let payload = ExecuteOrderPayload {
+            client_id: execution.next_client_id(),
+            order_type: if params.price_limit.is_some() {
+                OrderType::Limit
+            } else {
+                OrderType::Market
+            },
+            price: params.price_limit,
+            time_in_force: params.price_limit.map(|_| TimeInForce::IOC),
+            quantity: Some(quantity),
+            side: params.side,
+            symbol: params.symbol.clone(),
+            ..Default::default()
+        };
+        match client.execute_order(payload).await {
+            Ok(order) => {
+                execution.track(&order);
+            }
+            Err(err) => execution.record_error(err.to_string()),
+        }
+    }
+
+    execution.drain_updates();
+    execution.refresh();
+    // Participation caps, unfilled IOC children and failed slices can all leave
+    // part of the parent quantity unfilled at the end of the horizon.
+    if execution.filled_quantity() >= params.quantity {
+        execution.finish(ExecutionState::Completed);
+    } else {
+        execution.finish(ExecutionState::Expired);
+    }
+}
+
+/// Waits until `offset` after `start`, applying order updates and holding
+/// while paused. Time spent paused moves `start` forward, shifting the rest of
+/// the schedule. Returns `false` if the execution was cancelled.
+async fn wait_until(execution: &mut Execution, start: &mut Instant, offset: Duration) -> bool {
+    let mut paused_since: Option<Instant> = None;
+    loop {
+        let state = execution.state();
+        if state == ExecutionState::Paused {
+            paused_since.get_or_insert_with(Instant::now);
+        } else if let Some(since) = paused_since.take() {
+            *start += since.elapsed();
+        }
+        let deadline = *start + offset;
+        match state {
+            ExecutionState::Cancelled
+            | ExecutionState::Completed
+            | ExecutionState::Expired
+            | ExecutionState::Failed => {
+                return false;
+            }
+            ExecutionState::Paused => {
+                tokio::select! {
+                    changed = execution.control.changed() => {
+                        if changed.is_err() {
+                            // The handle is gone, nobody can resume the execution.
+                            return false;
+                        }
+                    }
+                    Some(update) = execution.updates.recv() => execution.apply(&update),
+                }
+                execution.refresh();
+            }
+            ExecutionState::Running => {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(deadline) => return true,
+                    changed = execution.control.changed() => {
+                        if changed.is_err() {
+                            // The handle is gone, nobody can pause or cancel the
+                            // execution any more.
+                            return false;
+                        }
+                        execution.refresh();
+                    }
+                    Some(update) = execution.updates.recv() => execution.apply(&update),
+                }
+            }
+        }
+    }
+}
+
+/// Sums the quantity traded on `symbol` during the last `window`, paging
+/// through the trade history, newest first, until it reaches an older trade.
+/// At most [`VOLUME_MAX_PAGES`] pages are read, so on very active markets the
+/// volume is undercounted and the participation cap errs on the low side.
+async fn market_volume(client: &BpxClient, symbol: &str, window: Duration) -> Result<Decimal> {
+    let since = now_millis().saturating_sub(window.as_millis() as u64) as i64;
+    let trades = client
+        .historical_trades_paginator(symbol)
+        .with_page_size(VOLUME_PAGE_SIZE)
+        .with_max_items(VOLUME_PAGE_SIZE * VOLUME_MAX_PAGES)
+        .into_stream();
+    let mut trades = std::pin::pin!(trades);
+    let mut volume = Decimal::ZERO;
+    while let Some(trade) = trades.try_next().await? {
+        if trade.timestamp < since {
+            break;
+        }
+        volume += trade.quantity;
+    }
+    Ok(volume)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::round_to_step;
+    use rust_decimal_macros::dec;
+
+    fn filter() -> QuantityFilter {
+        QuantityFilter {
+            min_quantity: dec!(0.1),
+            max_quantity: None,
+            step_size: dec!(0.1),
+        }
+    }
+
+    fn kline(volume: Decimal) -> Kline {
+        serde_json::from_value(serde_json::json!({
+            "start": "2025-01-01 00:00:00",
+            "open": null,
+            "high": null,
+            "low": null,
+            "close": null,
+            "volume": volume,
+            "trades": "0",
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pausing_shifts_the_remaining_schedule() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let begin = Instant::now();
+        let handle = Execution::spawn(
+            "SOL_USDC".to_string(),
+            Side::Bid,
+            dec!(1),
+            None,
+            move |mut execution| async move {
+                let mut start = Instant::now();
+                for index in 0..4 {
+                    let offset = Duration::from_secs(10) * index;
+                    if !wait_until(&mut execution, &mut start, offset).await {
+                        return;
+                    }
+                    let _ = tx.send(begin.elapsed());
+                }
+            },
+        );
+        assert_eq!(rx.recv().await, Some(Duration::ZERO));
+
+        // Pause across the next three slice times.
+        handle.pause();
+        tokio::time::sleep(Duration::from_secs(35)).await;
+        handle.resume();
+
+        let mut fired = Vec::new();
+        while let Some(elapsed) = rx.recv().await {
+            fired.push(elapsed.as_secs());
+        }
+        assert_eq!(fired, [45, 55, 65]);
+    }
+
+    #[test]
+    fn twap_targets_are_even_and_end_at_the_parent_quantity() {
+        let targets = cumulative_targets(dec!(10), &[Decimal::ONE; 3]);
+        assert_eq!(targets.len(), 3);
+        assert_eq!(round_to_step(targets[0], &filter()), dec!(3.3));
+        assert_eq!(round_to_step(targets[1], &filter()), dec!(6.6));
+        assert_eq!(targets[2], dec!(10));
+    }
+
+    #[test]
+    fn vwap_weights_follow_historical_volume() {
+        let klines = [dec!(1), dec!(1), dec!(6), dec!(2)].map(kline);
+        let weights = volume_weights(&klines, 2);
+        assert_eq!(weights, [dec!(2), dec!(8)]);
+
+        let targets = cumulative_targets(dec!(5), &weights);
+        assert_eq!(targets, [dec!(1), dec!(5)]);
+
+        // Without any volume the schedule falls back to TWAP.
+        let targets = cumulative_targets(dec!(4), &[Decimal::ZERO, Decimal::ZERO]);
+        assert_eq!(targets, [dec!(2), dec!(4)]);
+    }
+
+    #[test]
+    fn child_quantities_respect_the_quantity_filter() {
+        assert_eq!(tradable_quantity(dec!(1.27), &filter()), Some(dec!(1.2)));
+        assert_eq!(tradable_quantity(dec!(0.09), &filter()), None);
+        assert_eq!(tradable_quantity(dec!(-1), &filter()), None);
+
+        let capped = QuantityFilter {
+            max_quantity: Some(dec!(5)),
+            ..filter()
+        };
+        assert_eq!(tradable_quantity(dec!(7), &capped), Some(dec!(5)));
+    }
+}
@@ -0,0 +1,76 @@
+//! CSV export.
+
+use std::{io::Write, marker::PhantomData};
+
+use chrono::SecondsFormat;
+
+use super::{ExportWriter, Exportable, Value};
+use crate::{Error, Result};
+
+/// Writes records of type `T` as CSV, starting with a header row of the
+/// column names.
+#[derive(Debug)]
+pub struct CsvExporter<W: Write, T> {
+    writer: ::csv::Writer<W>,
+    _record: PhantomData<fn(&T)>,
+}
+
+impl<W: Write, T: Exportable> CsvExporter<W, T> {
+    /// Creates an exporter writing to `writer` and writes the header row.
+    pub fn new(writer: W) -> Result<Self> {
+        let mut writer = ::csv::Writer::from_writer(writer);
+        writer.write_record(T::COLUMNS.iter().map(|column| column.name))?;
+        Ok(Self {
+            writer,
+            _record: PhantomData,
+        })
+    }
+
+    /// Flushes the remaining rows and returns the underlying writer.
+    pub fn finish(self) -> Result<W> {
+        self.writer
+            .into_inner()
+            .map_err(|err| Error::Io(err.into_error()))
+    }
+}
+
+impl<W: Write, T: Exportable> ExportWriter<T> for CsvExporter<W, T> {
+    fn write(&mut self, record: &T) -> Result<()> {
+        let row = record.values()?.into_iter().map(|value| match value {
+            Value::Null => String::new(),
+            Value::Text(text) => text,
+            Value::Decimal(decimal) => decimal.to_string(),
+            Value::Integer(integer) => integer.to_string(),
+            Value::Boolean(boolean) => boolean.to_string(),
+            Value::Timestamp(timestamp) => timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+        });
+        self.writer.write_record(row)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bpx_api_types::markets::FundingRate;
+    use chrono::DateTime;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn writes_header_and_exact_values() {
+        let rates = [FundingRate {
+            symbol: "SOL_USDC_PERP".to_string(),
+            interval_end_timestamp: DateTime::from_timestamp_millis(1735689600000).unwrap(),
+            funding_rate: dec!(0.000012340000000000000001),
+        }];
+        let mut exporter = CsvExporter::<_, FundingRate>::new(Vec::new()).unwrap();
+        assert_eq!(exporter.write_all(&rates).unwrap(), 1);
+        let csv = String::from_utf8(exporter.finish().unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "interval_end_timestamp,symbol,funding_rate\n\
+             2025-01-01T00:00:00.000Z,SOL_USDC_PERP,0.000012340000000000000001\n"
+        );
+    }
+}
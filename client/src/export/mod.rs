@@ -0,0 +1,444 @@
+//! CSV and Parquet export of account history.
+//!
+//! Records implementing [`Exportable`] (fills, deposits, withdrawals, funding
+//! rates, vault mints and vault redeems) are written with a fixed set of
+//! columns per type. Decimals are never converted to floating point, and every
+//! timestamp is normalized to UTC with millisecond precision.
+//!
+//! - [`csv::CsvExporter`]: decimals as written by the exchange and timestamps
+//!   as RFC 3339 strings, e.g. `2025-01-01T00:00:00.000Z`.
+//! - [`parquet::ParquetExporter`]: decimals as `DECIMAL(38, 18)` and
+//!   timestamps as UTC `TIMESTAMP(MILLIS)`.
+//!
+//! Exporters take records one by one, from an iterator with
+//! [`ExportWriter::write_all`] or from a stream such as a
+//! [paginator](crate::paginate) with [`ExportWriter::write_stream`].
+//!
+//! Requires the `export` feature.
+//!
+//! ## Example
+//! ```no_run
+//! use std::fs::File;
+//!
+//! use bpx_api_client::{
+//!     BpxClient,
+//!     export::{ExportWriter, parquet::ParquetExporter},
+//! };
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let mut exporter = ParquetExporter::new(File::create("deposits.parquet")?)?;
+//! let written = exporter
+//!     .write_stream(client.deposits_paginator().with_page_size(1000).into_stream())
+//!     .await?;
+//! exporter.finish()?;
+//! println!("exported {written} deposits");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{borrow::Borrow, future::Future};
+
+use bpx_api_types::{
+    capital::{Deposit, Withdrawal},
+    fill::Fill,
+    markets::FundingRate,
+    vault::{VaultMint, VaultRedeem},
+};
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt};
+use rust_decimal::Decimal;
+
+use crate::{Error, Result};
+
+pub mod csv;
+pub mod parquet;
+
+/// The type of the values of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Text,
+    Decimal,
+    Integer,
+    Boolean,
+    /// A UTC timestamp with millisecond precision.
+    Timestamp,
+}
+
+/// A column of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+    /// Whether the column may hold [`Value::Null`].
+    pub nullable: bool,
+}
+
+impl Column {
+    const fn required(name: &'static str, column_type: ColumnType) -> Self {
+        Self {
+            name,
+            column_type,
+            nullable: false,
+        }
+    }
+
+    const fn optional(name: &'static str, column_type: ColumnType) -> Self {
+        Self {
+            name,
+            column_type,
+            nullable: true,
+        }
+    }
+}
+
+/// A value of a row, matching the [`ColumnType`] of its column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Null,
+    Text(String),
+    Decimal(Decimal),
+    Integer(i64),
+    Boolean(bool),
+    Timestamp(DateTime<Utc>),
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<Decimal> for Value {
+    fn from(value: Decimal) -> Self {
+        Value::Decimal(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Integer(value.into())
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Value::Integer(value.into())
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<DateTime<Utc>> for Value {
+    fn from(value: DateTime<Utc>) -> Self {
+        Value::Timestamp(value)
+    }
+}
+
+/// A record that can be exported.
+pub trait Exportable {
+    /// The columns of the export, in order.
+    const COLUMNS: &'static [Column];
+
+    /// Returns the values of the record, one per column.
+    fn values(&self) -> Result<Vec<Value>>;
+}
+
+/// A writer of records of type `T`.
+pub trait ExportWriter<T: Exportable> {
+    /// Writes one record.
+    fn write(&mut self, record: &T) -> Result<()>;
+
+    /// Writes every record of `records` and returns their number.
+    fn write_all<I>(&mut self, records: I) -> Result<u64>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        let mut written = 0;
+        for record in records {
+            self.write(record.borrow())?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Writes every record of `records` and returns their number, stopping at
+    /// the first error.
+    fn write_stream<S>(&mut self, records: S) -> impl Future<Output = Result<u64>>
+    where
+        S: Stream<Item = Result<T>>,
+    {
+        async move {
+            let mut records = std::pin::pin!(records);
+            let mut written = 0;
+            while let Some(record) = records.next().await {
+                self.write(&record?)?;
+                written += 1;
+            }
+            Ok(written)
+        }
+    }
+}
+
+fn millis(timestamp: i64) -> Result<Value> {
+    DateTime::from_timestamp_millis(timestamp)
+        .map(Value::Timestamp)
+        .ok_or_else(|| Error::UnexpectedResponse(format!("invalid timestamp: {timestamp}").into()))
+}
+
+fn integer(value: Option<u64>) -> Result<Value> {
+    value
+        .map(|value| {
+            i64::try_from(value).map_err(|_| {
+                Error::UnexpectedResponse(format!("integer out of range: {value}").into())
+            })
+        })
+        .transpose()
+        .map(Value::from)
+}
+
+impl Exportable for Fill {
+    const COLUMNS: &'static [Column] = &[
+        Column::required("timestamp", ColumnType::Timestamp),
+        Column::optional("trade_id", ColumnType::Integer),
+        Column::required("order_id", ColumnType::Text),
+        Column::optional("client_id", ColumnType::Integer),
+        Column::required("symbol", ColumnType::Text),
+        Column::required("side", ColumnType::Text),
+        Column::required("price", ColumnType::Decimal),
+        Column::required("quantity", ColumnType::Decimal),
+        Column::required("fee", ColumnType::Decimal),
+        Column::required("fee_symbol", ColumnType::Text),
+        Column::required("is_maker", ColumnType::Boolean),
+        Column::optional("system_order_type", ColumnType::Text),
+    ];
+
+    fn values(&self) -> Result<Vec<Value>> {
+        Ok(vec![
+            self.timestamp.into(),
+            self.trade_id.into(),
+            self.order_id.as_str().into(),
+            self.client_id.into(),
+            self.symbol.as_str().into(),
+            self.side.to_string().into(),
+            self.price.into(),
+            self.quantity.into(),
+            self.fee.into(),
+            self.fee_symbol.as_str().into(),
+            self.is_maker.into(),
+            self.system_order_type.map(|kind| kind.to_string()).into(),
+        ])
+    }
+}
+
+impl Exportable for Deposit {
+    const COLUMNS: &'static [Column] = &[
+        Column::required("created_at", ColumnType::Timestamp),
+        Column::required("id", ColumnType::Integer),
+        Column::required("symbol", ColumnType::Text),
+        Column::required("quantity", ColumnType::Decimal),
+        Column::required("status", ColumnType::Text),
+        Column::required("source", ColumnType::Text),
+        Column::optional("transaction_hash", ColumnType::Text),
+        Column::optional("from_address", ColumnType::Text),
+        Column::optional("to_address", ColumnType::Text),
+        Column::optional("identifier", ColumnType::Text),
+        Column::optional("confirmation_block_number", ColumnType::Integer),
+        Column::optional("subaccount_id", ColumnType::Integer),
+    ];
+
+    fn values(&self) -> Result<Vec<Value>> {
+        Ok(vec![
+            self.created_at.and_utc().into(),
+            self.id.into(),
+            self.symbol.as_str().into(),
+            self.quantity.into(),
+            self.status.to_string().into(),
+            self.source.to_string().into(),
+            self.transaction_hash.clone().into(),
+            self.from_address.clone().into(),
+            self.to_address.clone().into(),
+            self.identifier.clone().into(),
+            self.confirmation_block_number.into(),
+            integer(self.subaccount_id)?,
+        ])
+    }
+}
+
+impl Exportable for Withdrawal {
+    const COLUMNS: &'static [Column] = &[
+        Column::required("created_at", ColumnType::Timestamp),
+        Column::required("id", ColumnType::Integer),
+        Column::required("symbol", ColumnType::Text),
+        Column::required("quantity", ColumnType::Decimal),
+        Column::required("fee", ColumnType::Decimal),
+        Column::required("status", ColumnType::Text),
+        Column::required("blockchain", ColumnType::Text),
+        Column::required("to_address", ColumnType::Text),
+        Column::optional("transaction_hash", ColumnType::Text),
+        Column::optional("client_id", ColumnType::Text),
+        Column::optional("identifier", ColumnType::Text),
+        Column::optional("subaccount_id", ColumnType::Integer),
+    ];
+
+    fn values(&self) -> Result<Vec<Value>> {
+        Ok(vec![
+            self.created_at.and_utc().into(),
+            self.id.into(),
+            self.symbol.as_str().into(),
+            self.quantity.into(),
+            self.fee.into(),
+            self.status.to_string().into(),
+            self.blockchain.to_string().into(),
+            self.to_address.as_str().into(),
+            self.transaction_hash.clone().into(),
+            self.client_id.clone().into(),
+            self.identifier.clone().into(),
+            integer(self.subaccount_id)?,
+        ])
+    }
+}
+
+impl Exportable for FundingRate {
+    const COLUMNS: &'static [Column] = &[
+        Column::required("interval_end_timestamp", ColumnType::Timestamp),
+        Column::required("symbol", ColumnType::Text),
+        Column::required("funding_rate", ColumnType::Decimal),
+    ];
+
+    fn values(&self) -> Result<Vec<Value>> {
+        Ok(vec![
+            self.interval_end_timestamp.into(),
+            self.symbol.as_str().into(),
+            self.funding_rate.into(),
+        ])
+    }
+}
+
+impl Exportable for VaultMint {
+    const COLUMNS: &'static [Column] = &[
+        Column::required("timestamp", ColumnType::Timestamp),
+        Column::required("id", ColumnType::Text),
+        Column::required("vault_id", ColumnType::Integer),
+        Column::required("vault_token", ColumnType::Text),
+        Column::required("symbol", ColumnType::Text),
+        Column::required("quantity", ColumnType::Decimal),
+        Column::required("vault_tokens_minted", ColumnType::Decimal),
+        Column::required("nav", ColumnType::Decimal),
+    ];
+
+    fn values(&self) -> Result<Vec<Value>> {
+        Ok(vec![
+            millis(self.timestamp)?,
+            self.id.as_str().into(),
+            self.vault_id.into(),
+            self.vault_token.as_str().into(),
+            self.symbol.as_str().into(),
+            self.quantity.into(),
+            self.vault_tokens_minted.into(),
+            self.nav.into(),
+        ])
+    }
+}
+
+impl Exportable for VaultRedeem {
+    const COLUMNS: &'static [Column] = &[
+        Column::required("timestamp", ColumnType::Timestamp),
+        Column::required("id", ColumnType::Text),
+        Column::required("vault_id", ColumnType::Integer),
+        Column::required("status", ColumnType::Text),
+        Column::required("vault_token_quantity", ColumnType::Decimal),
+        Column::optional("vault_token", ColumnType::Text),
+        Column::optional("symbol", ColumnType::Text),
+        Column::optional("quantity", ColumnType::Decimal),
+        Column::optional("nav", ColumnType::Decimal),
+        Column::optional("reason", ColumnType::Text),
+    ];
+
+    fn values(&self) -> Result<Vec<Value>> {
+        Ok(vec![
+            millis(self.timestamp)?,
+            self.id.as_str().into(),
+            self.vault_id.into(),
+            self.status.to_string().into(),
+            self.vault_token_quantity.into(),
+            self.vault_token.clone().into(),
+            self.symbol.clone().into(),
+            self.quantity.into(),
+            self.nav.into(),
+            self.reason.clone().into(),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_columns<T: Exportable>(record: &T) {
+        let values = record.values().unwrap();
+        assert_eq!(values.len(), T::COLUMNS.len());
+        for (column, value) in T::COLUMNS.iter().zip(&values) {
+            let matches = match value {
+                Value::Null => column.nullable,
+                Value::Text(_) => column.column_type == ColumnType::Text,
+                Value::Decimal(_) => column.column_type == ColumnType::Decimal,
+                Value::Integer(_) => column.column_type == ColumnType::Integer,
+                Value::Boolean(_) => column.column_type == ColumnType::Boolean,
+                Value::Timestamp(_) => column.column_type == ColumnType::Timestamp,
+            };
+            assert!(matches, "{} holds {value:?}", column.name);
+        }
+    }
+
+    #[test]
+    fn values_match_columns() {
+        let fill: Fill = serde_json::from_str(
+            r#"{"tradeId":1,"orderId":"2","symbol":"SOL_USDC","feeSymbol":"USDC",
+                "price":"100.25","quantity":"1.5","fee":"0.01","side":"Bid",
+                "timestamp":"2025-01-01T00:00:00.123","isMaker":true}"#,
+        )
+        .unwrap();
+        check_columns(&fill);
+        assert_eq!(
+            fill.values().unwrap()[0],
+            Value::Timestamp(DateTime::from_timestamp_millis(1735689600123).unwrap())
+        );
+
+        let deposit: Deposit = serde_json::from_str(
+            r#"{"id":1,"source":"solana","status":"confirmed","symbol":"USDC",
+                "quantity":"10","createdAt":"2025-01-01T00:00:00","subaccountId":3}"#,
+        )
+        .unwrap();
+        check_columns(&deposit);
+
+        let redeem: VaultRedeem = serde_json::from_str(
+            r#"{"status":"Requested","id":"r","vaultId":1,"vaultTokenQuantity":"2",
+                "timestamp":1735689600000}"#,
+        )
+        .unwrap();
+        check_columns(&redeem);
+    }
+}
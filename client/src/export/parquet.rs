@@ -0,0 +1,361 @@
+//! Parquet export.
+//!
+//! Decimals are stored as `DECIMAL(38, 18)`: values with more than 18
+//! fractional digits or more than 20 integer digits are rejected rather than
+//! rounded. Timestamps are UTC `TIMESTAMP(MILLIS)`. Pages are not compressed.
+//!
+//! Values are checked and encoded when a record is written, so a record that
+//! cannot be stored is rejected on its own and the buffered rows are kept.
+
+use std::{io::Write, marker::PhantomData, sync::Arc};
+
+use ::parquet::{
+    basic::{LogicalType, Repetition, TimeUnit, Type as PhysicalType},
+    data_type::{
+        BoolType, ByteArray, ByteArrayType, FixedLenByteArray, FixedLenByteArrayType, Int64Type,
+    },
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    format::MilliSeconds,
+    schema::types::Type,
+};
+use rust_decimal::Decimal;
+
+use super::{Column, ColumnType, ExportWriter, Exportable, Value};
+use crate::{Error, Result};
+
+/// Precision of the decimal columns.
+const DECIMAL_PRECISION: i32 = 38;
+/// Scale of the decimal columns.
+const DECIMAL_SCALE: u32 = 18;
+/// Default number of rows per row group.
+const DEFAULT_ROW_GROUP_SIZE: usize = 65_536;
+
+/// Writes records of type `T` as a Parquet file. Rows are buffered and written
+/// one row group at a time; [`ParquetExporter::finish`] writes the last row
+/// group and the file footer.
+pub struct ParquetExporter<W: Write + Send, T> {
+    writer: SerializedFileWriter<W>,
+    rows: Vec<Vec<Cell>>,
+    row_group_size: usize,
+    _record: PhantomData<fn(&T)>,
+}
+
+impl<W: Write + Send, T: Exportable> ParquetExporter<W, T> {
+    /// Creates an exporter writing to `writer`.
+    pub fn new(writer: W) -> Result<Self> {
+        let fields = T::COLUMNS
+            .iter()
+            .map(|column| field(column).map(Arc::new))
+            .collect::<Result<Vec<_>>>()?;
+        let schema = Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()?;
+        let properties = WriterProperties::builder().build();
+        Ok(Self {
+            writer: SerializedFileWriter::new(writer, Arc::new(schema), Arc::new(properties))?,
+            rows: Vec::new(),
+            row_group_size: DEFAULT_ROW_GROUP_SIZE,
+            _record: PhantomData,
+        })
+    }
+
+    /// Sets the number of rows per row group. Defaults to 65536.
+    pub fn with_row_group_size(mut self, row_group_size: usize) -> Self {
+        self.row_group_size = row_group_size.max(1);
+        self
+    }
+
+    /// Writes the buffered rows and the file footer, and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.writer.into_inner()?)
+    }
+
+    /// Writes the buffered rows as a row group.
+    fn flush(&mut self) -> Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let rows = std::mem::take(&mut self.rows);
+        let mut row_group = self.writer.next_row_group()?;
+        let mut index = 0;
+        while let Some(mut column_writer) = row_group.next_column()? {
+            let column = &T::COLUMNS[index];
+            let values = rows.iter().map(|row| &row[index]);
+            let mut levels = Vec::with_capacity(rows.len());
+            match column.column_type {
+                ColumnType::Text => {
+                    let data = present(values, &mut levels, |cell| match cell {
+                        Cell::Text(text) => Some(text.clone()),
+                        _ => None,
+                    })?;
+                    column_writer.typed::<ByteArrayType>().write_batch(
+                        &data,
+                        def_levels(column, &levels),
+                        None,
+                    )?;
+                }
+                ColumnType::Decimal => {
+                    let data = present(values, &mut levels, |cell| match cell {
+                        Cell::Decimal(decimal) => Some(decimal.clone()),
+                        _ => None,
+                    })?;
+                    column_writer.typed::<FixedLenByteArrayType>().write_batch(
+                        &data,
+                        def_levels(column, &levels),
+                        None,
+                    )?;
+                }
+                ColumnType::Integer | ColumnType::Timestamp => {
+                    let data = present(values, &mut levels, |cell| match cell {
+                        Cell::Integer(integer) => Some(*integer),
+                        _ => None,
+                    })?;
+                    column_writer.typed::<Int64Type>().write_batch(
+                        &data,
+                        def_levels(column, &levels),
+                        None,
+                    )?;
+                }
+                ColumnType::Boolean => {
+                    let data = present(values, &mut levels, |cell| match cell {
+                        Cell::Boolean(boolean) => Some(*boolean),
+                        _ => None,
+                    })?;
+                    column_writer.typed::<BoolType>().write_batch(
+                        &data,
+                        def_levels(column, &levels),
+                        None,
+                    )?;
+                }
+            }
+            column_writer.close()?;
+            index += 1;
+        }
+        row_group.close()?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Send, T: Exportable> ExportWriter<T> for ParquetExporter<W, T> {
+    fn write(&mut self, record: &T) -> Result<()> {
+        let row = T::COLUMNS
+            .iter()
+            .zip(record.values()?)
+            .map(|(column, value)| Cell::encode(column, value))
+            .collect::<Result<Vec<_>>>()?;
+        self.rows.push(row);
+        if self.rows.len() >= self.row_group_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// A value checked against its column and encoded as stored.
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Null,
+    Text(ByteArray),
+    Decimal(FixedLenByteArray),
+    /// Integers, and timestamps in milliseconds.
+    Integer(i64),
+    Boolean(bool),
+}
+
+impl Cell {
+    fn encode(column: &Column, value: Value) -> Result<Self> {
+        let cell = match (column.column_type, value) {
+            (_, Value::Null) if column.nullable => Self::Null,
+            (_, Value::Null) => {
+                return Err(Error::InvalidRequest(
+                    format!("column {} is not nullable", column.name).into(),
+                ));
+            }
+            (ColumnType::Text, Value::Text(text)) => Self::Text(ByteArray::from(text.as_str())),
+            (ColumnType::Decimal, Value::Decimal(decimal)) => {
+                Self::Decimal(decimal_bytes(decimal)?)
+            }
+            (ColumnType::Integer, Value::Integer(integer)) => Self::Integer(integer),
+            (ColumnType::Boolean, Value::Boolean(boolean)) => Self::Boolean(boolean),
+            (ColumnType::Timestamp, Value::Timestamp(timestamp)) => {
+                Self::Integer(timestamp.timestamp_millis())
+            }
+            (_, value) => {
+                return Err(Error::InvalidRequest(
+                    format!("value {value:?} does not match column {}", column.name).into(),
+                ));
+            }
+        };
+        Ok(cell)
+    }
+}
+
+/// Returns the schema field of a column.
+fn field(column: &Column) -> Result<Type> {
+    let repetition = if column.nullable {
+        Repetition::OPTIONAL
+    } else {
+        Repetition::REQUIRED
+    };
+    let builder = match column.column_type {
+        ColumnType::Text => Type::primitive_type_builder(column.name, PhysicalType::BYTE_ARRAY)
+            .with_logical_type(Some(LogicalType::String)),
+        ColumnType::Decimal => {
+            Type::primitive_type_builder(column.name, PhysicalType::FIXED_LEN_BYTE_ARRAY)
+                .with_length(16)
+                .with_precision(DECIMAL_PRECISION)
+                .with_scale(DECIMAL_SCALE as i32)
+                .with_logical_type(Some(LogicalType::Decimal {
+                    scale: DECIMAL_SCALE as i32,
+                    precision: DECIMAL_PRECISION,
+                }))
+        }
+        ColumnType::Integer => Type::primitive_type_builder(column.name, PhysicalType::INT64),
+        ColumnType::Boolean => Type::primitive_type_builder(column.name, PhysicalType::BOOLEAN),
+        ColumnType::Timestamp => Type::primitive_type_builder(column.name, PhysicalType::INT64)
+            .with_logical_type(Some(LogicalType::Timestamp {
+                is_adjusted_to_u_t_c: true,
+                unit: TimeUnit::MILLIS(MilliSeconds {}),
+            })),
+    };
+    Ok(builder.with_repetition(repetition).build()?)
+}
+
+/// Collects the non-null values of a column and records their definition
+/// levels: 1 for a value, 0 for a null.
+fn present<'a, V>(
+    values: impl Iterator<Item = &'a Cell>,
+    levels: &mut Vec<i16>,
+    extract: impl Fn(&Cell) -> Option<V>,
+) -> Result<Vec<V>> {
+    let mut data = Vec::new();
+    for value in values {
+        if *value == Cell::Null {
+            levels.push(0);
+            continue;
+        }
+        let value = extract(value).ok_or_else(|| {
+            Error::InvalidRequest(format!("value {value:?} does not match its column").into())
+        })?;
+        levels.push(1);
+        data.push(value);
+    }
+    Ok(data)
+}
+
+fn def_levels<'a>(column: &Column, levels: &'a [i16]) -> Option<&'a [i16]> {
+    column.nullable.then_some(levels)
+}
+
+/// Encodes a decimal as the big-endian two's complement of its unscaled value
+/// at [`DECIMAL_SCALE`].
+fn decimal_bytes(decimal: Decimal) -> Result<FixedLenByteArray> {
+    let decimal = decimal.normalize();
+    let unscaled = DECIMAL_SCALE
+        .checked_sub(decimal.scale())
+        .and_then(|shift| decimal.mantissa().checked_mul(10i128.checked_pow(shift)?))
+        .filter(|unscaled| unscaled.unsigned_abs() < 10u128.pow(DECIMAL_PRECISION as u32))
+        .ok_or_else(|| {
+            Error::InvalidRequest(
+                format!("{decimal} does not fit DECIMAL({DECIMAL_PRECISION}, {DECIMAL_SCALE})")
+                    .into(),
+            )
+        })?;
+    Ok(FixedLenByteArray::from(unscaled.to_be_bytes().to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use ::parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::RowAccessor,
+    };
+    use bpx_api_types::vault::VaultRedeem;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn encodes_decimals_without_rounding() {
+        let bytes = decimal_bytes(dec!(-1.5)).unwrap();
+        assert_eq!(
+            i128::from_be_bytes(bytes.data().try_into().unwrap()),
+            -1_500_000_000_000_000_000
+        );
+        assert!(decimal_bytes(dec!(0.0000000000000000001)).is_err());
+        assert!(decimal_bytes(Decimal::MAX).is_err());
+    }
+
+    #[test]
+    fn writes_readable_row_groups() {
+        let redeems: Vec<VaultRedeem> = serde_json::from_str(
+            r#"[{"status":"Redeemed","id":"a","vaultId":1,"vaultTokenQuantity":"2.5",
+                 "quantity":"100.000000000000000001","timestamp":1735689600000},
+                {"status":"Requested","id":"b","vaultId":1,"vaultTokenQuantity":"1",
+                 "timestamp":1735689601000}]"#,
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join(format!("bpx-export-{}.parquet", std::process::id()));
+        let file = std::fs::File::create(&path).unwrap();
+        let mut exporter = ParquetExporter::<_, VaultRedeem>::new(file)
+            .unwrap()
+            .with_row_group_size(1);
+        assert_eq!(exporter.write_all(&redeems).unwrap(), 2);
+        exporter.finish().unwrap();
+
+        let reader = SerializedFileReader::try_from(path.as_path()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows[0].get_timestamp_millis(0).unwrap(), 1735689600000);
+        assert_eq!(rows[0].get_string(3).unwrap(), "Redeemed");
+        let quantity = rows[0].get_decimal(7).unwrap();
+        assert_eq!(
+            i128::from_be_bytes(quantity.data().try_into().unwrap()),
+            100_000_000_000_000_000_001
+        );
+        assert!(rows[1].get_decimal(7).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_unstorable_records_without_losing_buffered_rows() {
+        let redeems: Vec<VaultRedeem> = serde_json::from_str(
+            r#"[{"status":"Redeemed","id":"a","vaultId":1,"vaultTokenQuantity":"1",
+                 "timestamp":1735689600000},
+                {"status":"Redeemed","id":"b","vaultId":1,
+                 "vaultTokenQuantity":"100000000000000000000000","timestamp":1735689601000},
+                {"status":"Redeemed","id":"c","vaultId":1,"vaultTokenQuantity":"3",
+                 "timestamp":1735689602000}]"#,
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "bpx-export-rejected-{}.parquet",
+            std::process::id()
+        ));
+        let file = std::fs::File::create(&path).unwrap();
+        let mut exporter = ParquetExporter::<_, VaultRedeem>::new(file)
+            .unwrap()
+            .with_row_group_size(2);
+        exporter.write(&redeems[0]).unwrap();
+        assert!(exporter.write(&redeems[1]).is_err());
+        exporter.write(&redeems[2]).unwrap();
+        exporter.finish().unwrap();
+
+        let reader = SerializedFileReader::try_from(path.as_path()).unwrap();
+        let ids = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().get_string(1).unwrap().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["a", "c"]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+//! Funding rate history and analytics for perpetual markets.
+//!
+//! [`BpxClient::get_funding_rates_between`] pages through the funding rate
+//! history of a market, and the functions in this module annualize funding
+//! rates with the market's funding interval, sum funding over a window and
+//! check rates against the market's funding rate bounds.
+//!
+//! Funding rates are fractions of the position notional paid per interval,
+//! e.g. `0.0001` is 1 bps. Positive rates are paid by longs to shorts.
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::{BpxClient, funding};
+//! use chrono::{TimeZone, Utc};
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+//! let end = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap();
+//! let rates = client
+//!     .get_funding_rates_between("SOL_USDC_PERP", start, end)
+//!     .await?;
+//! let cumulative = funding::cumulative_funding(&rates, start, end);
+//!
+//! let markets = client.get_markets().await?;
+//! let market = markets.iter().find(|m| m.symbol == "SOL_USDC_PERP").unwrap();
+//! if let Some(last) = rates.last() {
+//!     println!(
+//!         "January funding {cumulative}, annualized {:?}, bound {:?}",
+//!         funding::annualized_funding_rate(market, last.funding_rate),
+//!         funding::funding_bound(market, last.funding_rate),
+//!     );
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use bpx_api_types::{
+    markets::{FundingRate, Market},
+    symbol::Symbol,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use crate::{BpxClient, Result, routes::market_symbol};
+
+/// Number of funding rates requested per page.
+const PAGE_SIZE: u64 = 1000;
+
+const BPS: Decimal = Decimal::from_parts(10_000, 0, 0, false, 0);
+
+/// Milliseconds in a 365 day year.
+const YEAR_MILLIS: u64 = 365 * 24 * 60 * 60 * 1000;
+
+/// Where a funding rate lies relative to the bounds of its market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FundingBound {
+    /// The rate is strictly within the bounds, or the market has no bounds.
+    Within,
+    /// The rate is at or above the upper bound and is clamped to it.
+    Upper,
+    /// The rate is at or below the lower bound and is clamped to it.
+    Lower,
+}
+
+impl BpxClient {
+    /// Fetches the funding rates of `symbol` for intervals ending in
+    /// `[start, end)`, oldest first.
+    ///
+    /// The endpoint has no time filter, so the history is paged through from the
+    /// most recent interval. It returns intervals newest first, so paging stops
+    /// at the first interval ending before `start`; windows far in the past
+    /// still page through every newer interval.
+    pub async fn get_funding_rates_between(
+        &self,
+        symbol: impl Into<Symbol>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<FundingRate>> {
+        let symbol = market_symbol(symbol)?;
+        let mut rates = Vec::new();
+        let mut offset = 0;
+        'pages: loop {
+            let page = self
+                .get_funding_interval_rates(&symbol, Some(PAGE_SIZE), Some(offset))
+                .await?;
+            let last_page = (page.len() as u64) < PAGE_SIZE;
+            offset += page.len() as u64;
+            for rate in page {
+                if rate.interval_end_timestamp < start {
+                    break 'pages;
+                }
+                // Intervals can shift onto the next page while paging.
+                let duplicate = rates.last().is_some_and(|last: &FundingRate| {
+                    last.interval_end_timestamp <= rate.interval_end_timestamp
+                });
+                if rate.interval_end_timestamp < end && !duplicate {
+                    rates.push(rate);
+                }
+            }
+            if last_page {
+                break;
+            }
+        }
+        rates.reverse();
+        Ok(rates)
+    }
+}
+
+/// Scales a per-interval funding rate to a 365 day year using the market's
+/// funding interval. Returns `None` if the market has no funding interval.
+pub fn annualized_funding_rate(market: &Market, rate: Decimal) -> Option<Decimal> {
+    let interval = market.funding_interval.filter(|interval| *interval > 0)?;
+    Some(rate * Decimal::from(YEAR_MILLIS) / Decimal::from(interval))
+}
+
+/// Sums the funding rates of intervals ending in `[start, end)`, i.e. the
+/// funding paid by a constant long position of unit notional.
+pub fn cumulative_funding(
+    rates: &[FundingRate],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Decimal {
+    rates
+        .iter()
+        .filter(|rate| rate.interval_end_timestamp >= start && rate.interval_end_timestamp < end)
+        .map(|rate| rate.funding_rate)
+        .sum()
+}
+
+/// Checks a funding rate against the market's funding rate bounds.
+pub fn funding_bound(market: &Market, rate: Decimal) -> FundingBound {
+    let (lower, upper) = bounds(market);
+    match (lower, upper) {
+        (_, Some(upper)) if rate >= upper => FundingBound::Upper,
+        (Some(lower), _) if rate <= lower => FundingBound::Lower,
+        _ => FundingBound::Within,
+    }
+}
+
+/// Clamps a funding rate to the market's funding rate bounds.
+pub fn clamp_funding_rate(market: &Market, rate: Decimal) -> Decimal {
+    let (lower, upper) = bounds(market);
+    let rate = upper.map_or(rate, |upper| rate.min(upper));
+    lower.map_or(rate, |lower| rate.max(lower))
+}
+
+/// Returns the funding rate bounds of the market as rates, converted from basis
+/// points.
+fn bounds(market: &Market) -> (Option<Decimal>, Option<Decimal>) {
+    (
+        market.funding_rate_lower_bound.map(|bound| bound / BPS),
+        market.funding_rate_upper_bound.map(|bound| bound / BPS),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+
+    fn market() -> Market {
+        serde_json::from_value(json!({
+            "symbol": "SOL_USDC_PERP",
+            "baseSymbol": "SOL",
+            "quoteSymbol": "USDC",
+            "marketType": "PERP",
+            "filters": {
+                "price": { "minPrice": "0.01", "tickSize": "0.01" },
+                "quantity": { "minQuantity": "0.01", "stepSize": "0.01" }
+            },
+            "fundingInterval": 28800000,
+            "fundingRateUpperBound": "10",
+            "fundingRateLowerBound": "-10",
+            "orderBookState": "Open",
+            "createdAt": "2025-01-21T06:34:54.691858",
+            "visible": true
+        }))
+        .unwrap()
+    }
+
+    fn rate(hours: i64, funding_rate: Decimal) -> FundingRate {
+        FundingRate {
+            symbol: "SOL_USDC_PERP".to_string(),
+            interval_end_timestamp: DateTime::from_timestamp(hours * 3600, 0).unwrap(),
+            funding_rate,
+        }
+    }
+
+    #[test]
+    fn annualizes_with_funding_interval() {
+        // Three 8 hour intervals a day.
+        assert_eq!(
+            annualized_funding_rate(&market(), dec!(0.0001)),
+            Some(dec!(0.1095))
+        );
+    }
+
+    #[test]
+    fn sums_funding_within_window() {
+        let rates = [
+            rate(8, dec!(0.0001)),
+            rate(16, dec!(-0.0002)),
+            rate(24, dec!(0.0004)),
+        ];
+        let at = |hours: i64| DateTime::from_timestamp(hours * 3600, 0).unwrap();
+        assert_eq!(cumulative_funding(&rates, at(8), at(24)), dec!(-0.0001));
+        assert_eq!(cumulative_funding(&rates, at(0), at(25)), dec!(0.0003));
+    }
+
+    #[test]
+    fn checks_and_clamps_bounds() {
+        let market = market();
+        assert_eq!(funding_bound(&market, dec!(0.0005)), FundingBound::Within);
+        assert_eq!(funding_bound(&market, dec!(0.001)), FundingBound::Upper);
+        assert_eq!(funding_bound(&market, dec!(-0.002)), FundingBound::Lower);
+        assert_eq!(clamp_funding_rate(&market, dec!(0.002)), dec!(0.001));
+        assert_eq!(clamp_funding_rate(&market, dec!(-0.002)), dec!(-0.001));
+        assert_eq!(clamp_funding_rate(&market, dec!(0.0002)), dec!(0.0002));
+    }
+}
@@ -0,0 +1,343 @@
+//! History stored as JSON Lines files.
+//!
+//! Records of each account and history kind are appended to
+//! `<root>/<account>/<kind>.jsonl`, one [`SyncRecord`] per line. The cursor is
+//! the last complete line of the file, so records and cursor cannot disagree.
+//! A line left incomplete by a crash is removed before the next append.
+//! Updates rewrite the file to a temporary file that then replaces it.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+use super::{Cursor, HistoryKind, HistoryStore, SyncRecord};
+use crate::{Error, Result};
+
+/// Size of the blocks read backwards when looking for the last line.
+const BLOCK_SIZE: u64 = 8 * 1024;
+
+/// A [`HistoryStore`] writing JSON Lines files under a root directory.
+#[derive(Debug, Clone)]
+pub struct JsonlStore {
+    root: PathBuf,
+}
+
+impl JsonlStore {
+    /// Creates a store under `root`. Directories are created on first write.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the file holding the records of `kind` for `account`.
+    pub fn path(&self, account: &str, kind: HistoryKind) -> Result<PathBuf> {
+        if account.is_empty() || account.starts_with('.') || account.contains(['/', '\\']) {
+            return Err(Error::InvalidRequest(
+                format!("invalid account name: {account:?}").into(),
+            ));
+        }
+        Ok(self.root.join(account).join(format!("{kind}.jsonl")))
+    }
+}
+
+impl HistoryStore for JsonlStore {
+    fn cursor(&mut self, account: &str, kind: HistoryKind) -> Result<Option<Cursor>> {
+        let path = self.path(account, kind)?;
+        let mut file = match OpenOptions::new().read(true).write(true).open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let Some(line) = recover_last_line(&mut file)? else {
+            return Ok(None);
+        };
+        let record: SyncRecord = serde_json::from_slice(&line)?;
+        Ok(Some(record.cursor()))
+    }
+
+    fn append(&mut self, account: &str, kind: HistoryKind, records: &[SyncRecord]) -> Result<()> {
+        let path = self.path(account, kind)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        recover_last_line(&mut file)?;
+        file.seek(SeekFrom::End(0))?;
+
+        let mut buf = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut buf, record)?;
+            buf.push(b'\n');
+        }
+        file.write_all(&buf)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    fn update(&mut self, account: &str, kind: HistoryKind, records: &[SyncRecord]) -> Result<()> {
+        let mut updates: HashMap<_, _> = records
+            .iter()
+            .map(|record| (record.id.as_str(), record))
+            .collect();
+        let path = self.path(account, kind)?;
+        let mut buf = Vec::new();
+        for record in self.records(account, kind)? {
+            let record = updates.remove(record.id.as_str()).unwrap_or(&record);
+            serde_json::to_writer(&mut buf, record)?;
+            buf.push(b'\n');
+        }
+
+        let tmp = path.with_extension("jsonl.tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(&buf)?;
+        file.sync_data()?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn records(&mut self, account: &str, kind: HistoryKind) -> Result<Vec<SyncRecord>> {
+        let path = self.path(account, kind)?;
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut records = Vec::new();
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 {
+            // A trailing line without a newline was not completely written.
+            if !line.ends_with('\n') {
+                break;
+            }
+            records.push(serde_json::from_str(&line)?);
+            line.clear();
+        }
+        Ok(records)
+    }
+
+    fn records_since(
+        &mut self,
+        account: &str,
+        kind: HistoryKind,
+        timestamp: i64,
+    ) -> Result<Vec<SyncRecord>> {
+        let path = self.path(account, kind)?;
+        let mut file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut start = file.seek(SeekFrom::End(0))?;
+        let mut tail = Vec::new();
+        // Read backwards until the first complete line in the tail is older
+        // than `timestamp`.
+        loop {
+            if start > 0 {
+                let read = BLOCK_SIZE.min(start);
+                start -= read;
+                file.seek(SeekFrom::Start(start))?;
+                let mut block = vec![0; read as usize];
+                file.read_exact(&mut block)?;
+                block.extend_from_slice(&tail);
+                tail = block;
+            }
+            // The first line may be cut by the block boundary, and a trailing
+            // line without a newline was not completely written.
+            let first = match start {
+                0 => 0,
+                _ => tail
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map_or(tail.len(), |i| i + 1),
+            };
+            let end = tail.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+            let lines = || tail[first..end.max(first)].split(|b| *b == b'\n');
+            let reached = start == 0
+                || lines()
+                    .find(|line| !line.is_empty())
+                    .map(serde_json::from_slice::<SyncRecord>)
+                    .transpose()?
+                    .is_some_and(|record| record.timestamp < timestamp);
+            if reached {
+                let mut records = Vec::new();
+                for line in lines().filter(|line| !line.is_empty()) {
+                    let record: SyncRecord = serde_json::from_slice(line)?;
+                    if record.timestamp >= timestamp {
+                        records.push(record);
+                    }
+                }
+                return Ok(records);
+            }
+        }
+    }
+}
+
+/// Truncates an incomplete trailing line and returns the last complete line,
+/// without its newline.
+fn recover_last_line(file: &mut File) -> Result<Option<Vec<u8>>> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let mut tail = Vec::new();
+    let mut start = len;
+    // Read backwards until the tail holds a newline that precedes the last one.
+    loop {
+        let complete_end = tail.iter().rposition(|b| *b == b'\n');
+        if let Some(end) = complete_end
+            && let Some(line_start) = tail[..end].iter().rposition(|b| *b == b'\n')
+        {
+            return finish(file, start, &tail, Some(line_start + 1), end);
+        }
+        if start == 0 {
+            return match complete_end {
+                Some(end) => finish(file, start, &tail, Some(0), end),
+                None => finish(file, start, &tail, None, 0),
+            };
+        }
+        let read = BLOCK_SIZE.min(start);
+        start -= read;
+        file.seek(SeekFrom::Start(start))?;
+        let mut block = vec![0; read as usize];
+        file.read_exact(&mut block)?;
+        block.extend_from_slice(&tail);
+        tail = block;
+    }
+}
+
+/// Drops anything after the newline at `tail[end]` and returns the line
+/// starting at `tail[line_start]`. `tail` starts at offset `start` of the file.
+fn finish(
+    file: &mut File,
+    start: u64,
+    tail: &[u8],
+    line_start: Option<usize>,
+    end: usize,
+) -> Result<Option<Vec<u8>>> {
+    let valid_len = match line_start {
+        Some(_) => start + end as u64 + 1,
+        None => start,
+    };
+    if valid_len < start + tail.len() as u64 {
+        file.set_len(valid_len)?;
+        file.sync_data()?;
+    }
+    Ok(line_start.map(|line_start| tail[line_start..end].to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(id: u64) -> SyncRecord {
+        SyncRecord {
+            id: id.to_string(),
+            timestamp: id as i64 * 1000,
+            record: json!({ "id": id, "padding": "x".repeat(3000) }),
+        }
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("bpx-jsonl-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+
+    #[test]
+    fn appends_and_resumes_from_last_line() {
+        let root = temp_root("resume");
+        let mut store = JsonlStore::new(&root);
+        assert_eq!(store.cursor("main", HistoryKind::Fills).unwrap(), None);
+
+        store
+            .append(
+                "main",
+                HistoryKind::Fills,
+                &(1..=5).map(record).collect::<Vec<_>>(),
+            )
+            .unwrap();
+        store
+            .append("main", HistoryKind::Fills, &[record(6)])
+            .unwrap();
+        assert_eq!(
+            store.cursor("main", HistoryKind::Fills).unwrap(),
+            Some(record(6).cursor())
+        );
+        assert_eq!(store.records("main", HistoryKind::Fills).unwrap().len(), 6);
+        assert_eq!(store.cursor("main", HistoryKind::Deposits).unwrap(), None);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn drops_incomplete_trailing_line() {
+        let root = temp_root("crash");
+        let mut store = JsonlStore::new(&root);
+        store
+            .append("main", HistoryKind::Fills, &[record(1), record(2)])
+            .unwrap();
+
+        // Simulate a crash halfway through writing a line.
+        let path = store.path("main", HistoryKind::Fills).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"id":"3","timestamp":30"#).unwrap();
+        drop(file);
+
+        assert_eq!(
+            store.cursor("main", HistoryKind::Fills).unwrap(),
+            Some(record(2).cursor())
+        );
+        store
+            .append("main", HistoryKind::Fills, &[record(3)])
+            .unwrap();
+        let records = store.records("main", HistoryKind::Fills).unwrap();
+        assert_eq!(records, vec![record(1), record(2), record(3)]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn reads_records_since_and_updates_in_place() {
+        let root = temp_root("since");
+        let mut store = JsonlStore::new(&root);
+        store
+            .append(
+                "main",
+                HistoryKind::Deposits,
+                &(1..=10).map(record).collect::<Vec<_>>(),
+            )
+            .unwrap();
+        let since = store
+            .records_since("main", HistoryKind::Deposits, 8000)
+            .unwrap();
+        assert_eq!(since, vec![record(8), record(9), record(10)]);
+
+        let mut updated = record(4);
+        updated.record = json!({ "id": 4, "status": "confirmed" });
+        store
+            .update("main", HistoryKind::Deposits, &[updated.clone()])
+            .unwrap();
+        let records = store.records("main", HistoryKind::Deposits).unwrap();
+        assert_eq!(records.len(), 10);
+        assert_eq!(records[3], updated);
+        assert_eq!(
+            store.cursor("main", HistoryKind::Deposits).unwrap(),
+            Some(record(10).cursor())
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rejects_account_names_escaping_the_root() {
+        let store = JsonlStore::new("history");
+        assert!(store.path("../other", HistoryKind::Fills).is_err());
+        assert!(store.path("", HistoryKind::Fills).is_err());
+    }
+}
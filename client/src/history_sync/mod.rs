@@ -0,0 +1,446 @@
+//! Incremental synchronization of account history to local storage.
+//!
+//! [`HistorySync`] copies the fill, deposit and withdrawal history of an
+//! account into a [`HistoryStore`], fetching only records newer than the
+//! store's cursor for that account and history kind. A cursor is the timestamp
+//! and id of the last stored record.
+//!
+//! Fills are requested from the cursor's timestamp onwards in ascending order
+//! and stored page by page. The API orders fills by timestamp only, so fills
+//! sharing the cursor's timestamp are fetched again and only those already
+//! stored are skipped. Deposits and withdrawals are only listed most recent
+//! first, so their new records are collected first and then stored oldest
+//! first. Stored deposits and withdrawals that have not reached a final status
+//! yet are fetched again on every sync and updated when their status changes.
+//!
+//! Stores write records and advance the cursor atomically, so a sync
+//! interrupted at any point resumes after the last stored record.
+//!
+//! - [`jsonl`]: one JSON Lines file per account and history kind.
+//! - `sqlite`: an SQLite database, behind the `sqlite` feature.
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::{
+//!     BpxClient,
+//!     history_sync::{HistoryKind, HistorySync, jsonl::JsonlStore},
+//! };
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let store = JsonlStore::new("history");
+//! let mut sync = HistorySync::new(client, store, "main");
+//! for report in sync.sync_all().await? {
+//!     println!("{}: {} new records", report.kind, report.written);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use bpx_api_types::{
+    capital::{Deposit, DepositStatus, Withdrawal, WithdrawalStatus},
+    fill::{Fill, FillsHistoryParams},
+    history::SortDirection,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{BpxClient, Result};
+
+pub mod jsonl;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// Default number of records requested per page.
+const DEFAULT_PAGE_SIZE: u64 = 1000;
+
+/// A kind of account history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryKind {
+    Fills,
+    Deposits,
+    Withdrawals,
+}
+
+impl HistoryKind {
+    /// Every history kind.
+    pub const ALL: [Self; 3] = [Self::Fills, Self::Deposits, Self::Withdrawals];
+
+    /// Returns the lower case name of the kind, e.g. `fills`.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fills => "fills",
+            Self::Deposits => "deposits",
+            Self::Withdrawals => "withdrawals",
+        }
+    }
+}
+
+impl fmt::Display for HistoryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The position of a record in a history: its timestamp in milliseconds and
+/// its id.
+///
+/// Positions are ordered by timestamp, then by id, comparing ids numerically
+/// when both are numbers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub timestamp: i64,
+    pub id: String,
+}
+
+impl Ord for Cursor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let numeric = |cursor: &Self| cursor.id.parse::<i64>().ok();
+        self.timestamp
+            .cmp(&other.timestamp)
+            .then_with(|| numeric(self).cmp(&numeric(other)))
+            .then_with(|| self.id.cmp(&other.id))
+    }
+}
+
+impl PartialOrd for Cursor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A stored history record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    /// Id of the record, unique within its history kind.
+    pub id: String,
+    /// Timestamp of the record in milliseconds.
+    pub timestamp: i64,
+    /// The record as returned by the API.
+    pub record: serde_json::Value,
+}
+
+impl SyncRecord {
+    /// Returns the position of the record.
+    pub fn cursor(&self) -> Cursor {
+        Cursor {
+            timestamp: self.timestamp,
+            id: self.id.clone(),
+        }
+    }
+
+    fn new(id: String, timestamp: i64, record: &impl Serialize) -> Result<Self> {
+        Ok(Self {
+            id,
+            timestamp,
+            record: serde_json::to_value(record)?,
+        })
+    }
+
+    /// Trade ids are only unique within a market, and both sides of a self
+    /// trade share one, so fill ids also hold the symbol and side.
+    fn from_fill(fill: &Fill) -> Result<Self> {
+        let timestamp = fill.timestamp.timestamp_millis();
+        let trade = match fill.trade_id {
+            Some(trade_id) => trade_id.to_string(),
+            None => format!("{}-{}", fill.order_id, timestamp),
+        };
+        let id = format!("{}-{}-{}", fill.symbol, trade, fill.side);
+        Self::new(id, timestamp, fill)
+    }
+
+    fn from_deposit(deposit: &Deposit) -> Result<Self> {
+        let timestamp = deposit.created_at.and_utc().timestamp_millis();
+        Self::new(deposit.id.to_string(), timestamp, deposit)
+    }
+
+    fn from_withdrawal(withdrawal: &Withdrawal) -> Result<Self> {
+        let timestamp = withdrawal.created_at.and_utc().timestamp_millis();
+        Self::new(withdrawal.id.to_string(), timestamp, withdrawal)
+    }
+
+    /// Whether a deposit or withdrawal record can still change status.
+    fn is_pending(&self, kind: HistoryKind) -> bool {
+        let status = self.record.get("status").cloned().unwrap_or_default();
+        match kind {
+            HistoryKind::Fills => false,
+            HistoryKind::Deposits => serde_json::from_value::<DepositStatus>(status)
+                .is_ok_and(|status| !status.is_final()),
+            HistoryKind::Withdrawals => serde_json::from_value::<WithdrawalStatus>(status)
+                .is_ok_and(|status| !status.is_final()),
+        }
+    }
+}
+
+/// Storage for synchronized history.
+pub trait HistoryStore {
+    /// Returns the position of the last stored record of `kind` for `account`.
+    fn cursor(&mut self, account: &str, kind: HistoryKind) -> Result<Option<Cursor>>;
+
+    /// Stores `records`, which are new, ordered and none older than the
+    /// current cursor's timestamp, and advances the cursor to the last of them.
+    /// Either all records are stored or, if the process stops midway, the
+    /// cursor reflects exactly the records that were.
+    fn append(&mut self, account: &str, kind: HistoryKind, records: &[SyncRecord]) -> Result<()>;
+
+    /// Replaces the stored records with the ids of `records`, keeping their
+    /// position and the cursor.
+    fn update(&mut self, account: &str, kind: HistoryKind, records: &[SyncRecord]) -> Result<()>;
+
+    /// Returns every stored record of `kind` for `account`, oldest first.
+    fn records(&mut self, account: &str, kind: HistoryKind) -> Result<Vec<SyncRecord>>;
+
+    /// Returns the stored records of `kind` for `account` whose timestamp is
+    /// `timestamp` or later, oldest first.
+    fn records_since(
+        &mut self,
+        account: &str,
+        kind: HistoryKind,
+        timestamp: i64,
+    ) -> Result<Vec<SyncRecord>>;
+}
+
+/// The result of synchronizing one history kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    pub kind: HistoryKind,
+    /// Number of records stored by this sync.
+    pub written: u64,
+    /// Number of stored records updated by this sync because their status
+    /// changed.
+    pub updated: u64,
+    /// Position of the last stored record.
+    pub cursor: Option<Cursor>,
+}
+
+/// Synchronizes the history of one account into a store. See the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct HistorySync<S> {
+    client: BpxClient,
+    store: S,
+    account: String,
+    page_size: u64,
+}
+
+impl<S: HistoryStore> HistorySync<S> {
+    /// Creates a sync of the account `client` is authenticated as into `store`.
+    /// `account` names the account in the store.
+    pub fn new(client: BpxClient, store: S, account: impl Into<String>) -> Self {
+        Self {
+            client,
+            store,
+            account: account.into(),
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// Sets the number of records requested per page. Defaults to 1000.
+    pub fn with_page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Returns the store.
+    pub fn store(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    /// Returns the store, consuming the sync.
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// Synchronizes fills, deposits and withdrawals.
+    pub async fn sync_all(&mut self) -> Result<Vec<SyncReport>> {
+        let mut reports = Vec::new();
+        for kind in HistoryKind::ALL {
+            reports.push(self.sync(kind).await?);
+        }
+        Ok(reports)
+    }
+
+    /// Stores the records of `kind` added since the last sync.
+    pub async fn sync(&mut self, kind: HistoryKind) -> Result<SyncReport> {
+        let cursor = self.store.cursor(&self.account, kind)?;
+        let mut report = SyncReport {
+            kind,
+            written: 0,
+            updated: 0,
+            cursor: cursor.clone(),
+        };
+        match kind {
+            HistoryKind::Fills => self.sync_fills(&mut report).await?,
+            HistoryKind::Deposits | HistoryKind::Withdrawals => {
+                self.sync_descending(&mut report).await?
+            }
+        }
+        Ok(report)
+    }
+
+    /// Requests fills from the cursor's timestamp onwards in ascending order,
+    /// storing each page as it arrives.
+    async fn sync_fills(&mut self, report: &mut SyncReport) -> Result<()> {
+        let from = report.cursor.as_ref().map(|cursor| cursor.timestamp);
+        // Ids of the stored fills at the cursor's timestamp, which are returned
+        // again.
+        let mut seen = match from {
+            Some(from) => self
+                .store
+                .records_since(&self.account, report.kind, from)?
+                .into_iter()
+                .filter(|record| record.timestamp == from)
+                .map(|record| record.id)
+                .collect(),
+            None => HashSet::new(),
+        };
+        let mut offset = 0;
+        loop {
+            let mut params = FillsHistoryParams::default()
+                .with_sort_direction(SortDirection::Asc)
+                .with_limit(self.page_size)
+                .with_offset(offset);
+            if let Some(from) = from {
+                params = params.with_from(from);
+            }
+            let page = self.client.get_historical_fills(params).await?;
+            offset += page.len() as u64;
+
+            let records = page
+                .iter()
+                .map(SyncRecord::from_fill)
+                .collect::<Result<Vec<_>>>()?;
+            self.store_fills(report, &mut seen, records)?;
+            if (page.len() as u64) < self.page_size {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Stores the fills not stored yet, in order. `seen` holds the ids of the
+    /// stored fills at the cursor's timestamp.
+    fn store_fills(
+        &mut self,
+        report: &mut SyncReport,
+        seen: &mut HashSet<String>,
+        mut records: Vec<SyncRecord>,
+    ) -> Result<()> {
+        records.sort_by_key(SyncRecord::cursor);
+        records.dedup_by(|a, b| a.id == b.id);
+        if let Some(cursor) = &report.cursor {
+            records.retain(|record| {
+                record.timestamp > cursor.timestamp
+                    || (record.timestamp == cursor.timestamp && !seen.contains(&record.id))
+            });
+        }
+        let Some(last) = records.last() else {
+            return Ok(());
+        };
+        self.store.append(&self.account, report.kind, &records)?;
+        if report
+            .cursor
+            .as_ref()
+            .is_none_or(|cursor| cursor.timestamp < last.timestamp)
+        {
+            seen.clear();
+        }
+        seen.extend(
+            records
+                .iter()
+                .filter(|record| record.timestamp == last.timestamp)
+                .map(|record| record.id.clone()),
+        );
+        report.written += records.len() as u64;
+        report.cursor = Some(last.cursor());
+        Ok(())
+    }
+
+    /// Walks a most recent first history back to the cursor and to the oldest
+    /// stored record that can still change status. Stores the new records
+    /// oldest first and updates the stored ones whose status changed.
+    async fn sync_descending(&mut self, report: &mut SyncReport) -> Result<()> {
+        let pending: HashMap<String, SyncRecord> = match &report.cursor {
+            Some(_) => self
+                .store
+                .records(&self.account, report.kind)?
+                .into_iter()
+                .filter(|record| record.is_pending(report.kind))
+                .map(|record| (record.id.clone(), record))
+                .collect(),
+            None => HashMap::new(),
+        };
+        let oldest = pending
+            .values()
+            .map(SyncRecord::cursor)
+            .chain(report.cursor.clone())
+            .min();
+
+        let limit = self.page_size as i64;
+        let mut records = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = match report.kind {
+                HistoryKind::Deposits => self
+                    .client
+                    .get_deposits(Some(limit), Some(offset))
+                    .await?
+                    .iter()
+                    .map(SyncRecord::from_deposit)
+                    .collect::<Result<Vec<_>>>()?,
+                HistoryKind::Withdrawals => self
+                    .client
+                    .get_withdrawals(Some(limit), Some(offset))
+                    .await?
+                    .iter()
+                    .map(SyncRecord::from_withdrawal)
+                    .collect::<Result<Vec<_>>>()?,
+                HistoryKind::Fills => unreachable!("fills are synced in ascending order"),
+            };
+            offset += page.len() as i64;
+            let short = (page.len() as i64) < limit;
+            let reached_oldest = oldest
+                .as_ref()
+                .is_some_and(|oldest| page.iter().any(|record| record.cursor() <= *oldest));
+            records.extend(page);
+            if short || reached_oldest {
+                break;
+            }
+        }
+
+        let changed: Vec<_> = records
+            .iter()
+            .filter(|record| {
+                pending
+                    .get(&record.id)
+                    .is_some_and(|stored| stored.record != record.record)
+            })
+            .cloned()
+            .collect();
+        if !changed.is_empty() {
+            self.store.update(&self.account, report.kind, &changed)?;
+            report.updated += changed.len() as u64;
+        }
+        self.store_new(report, records)
+    }
+
+    /// Stores the records after the cursor, in order.
+    fn store_new(&mut self, report: &mut SyncReport, mut records: Vec<SyncRecord>) -> Result<()> {
+        records.sort_by_key(SyncRecord::cursor);
+        records.dedup_by(|a, b| a.id == b.id);
+        if let Some(cursor) = &report.cursor {
+            records.retain(|record| record.cursor() > *cursor);
+        }
+        let Some(last) = records.last() else {
+            return Ok(());
+        };
+        self.store.append(&self.account, report.kind, &records)?;
+        report.written += records.len() as u64;
+        report.cursor = Some(last.cursor());
+        Ok(())
+    }
+}
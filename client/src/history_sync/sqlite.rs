@@ -0,0 +1,200 @@
+//! History stored in an SQLite database.
+//!
+//! Records are kept in a `history_records` table keyed by account, kind and id,
+//! and cursors in a `history_cursors` table. Each append is one transaction.
+
+use std::path::Path;
+
+use rusqlite::{Connection, OptionalExtension, params};
+
+use super::{Cursor, HistoryKind, HistoryStore, SyncRecord};
+use crate::Result;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS history_records (
+    account TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    record TEXT NOT NULL,
+    PRIMARY KEY (account, kind, id)
+);
+CREATE INDEX IF NOT EXISTS history_records_timestamp
+    ON history_records (account, kind, timestamp);
+CREATE TABLE IF NOT EXISTS history_cursors (
+    account TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    id TEXT NOT NULL,
+    PRIMARY KEY (account, kind)
+);
+";
+
+/// A [`HistoryStore`] backed by an SQLite database.
+#[derive(Debug)]
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    /// Opens or creates the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Opens a database held in memory.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    /// Uses an open connection, creating the tables if needed.
+    pub fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Returns the underlying connection, e.g. to query the records.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl HistoryStore for SqliteStore {
+    fn cursor(&mut self, account: &str, kind: HistoryKind) -> Result<Option<Cursor>> {
+        let cursor = self
+            .conn
+            .query_row(
+                "SELECT timestamp, id FROM history_cursors WHERE account = ?1 AND kind = ?2",
+                params![account, kind.as_str()],
+                |row| {
+                    Ok(Cursor {
+                        timestamp: row.get(0)?,
+                        id: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(cursor)
+    }
+
+    fn append(&mut self, account: &str, kind: HistoryKind, records: &[SyncRecord]) -> Result<()> {
+        let Some(last) = records.last() else {
+            return Ok(());
+        };
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR REPLACE INTO history_records (account, kind, id, timestamp, record)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for record in records {
+                insert.execute(params![
+                    account,
+                    kind.as_str(),
+                    record.id,
+                    record.timestamp,
+                    record.record.to_string(),
+                ])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO history_cursors (account, kind, timestamp, id)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![account, kind.as_str(), last.timestamp, last.id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn update(&mut self, account: &str, kind: HistoryKind, records: &[SyncRecord]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut update = tx.prepare(
+                "UPDATE history_records SET timestamp = ?4, record = ?5
+                 WHERE account = ?1 AND kind = ?2 AND id = ?3",
+            )?;
+            for record in records {
+                update.execute(params![
+                    account,
+                    kind.as_str(),
+                    record.id,
+                    record.timestamp,
+                    record.record.to_string(),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn records(&mut self, account: &str, kind: HistoryKind) -> Result<Vec<SyncRecord>> {
+        self.records_since(account, kind, i64::MIN)
+    }
+
+    fn records_since(
+        &mut self,
+        account: &str,
+        kind: HistoryKind,
+        timestamp: i64,
+    ) -> Result<Vec<SyncRecord>> {
+        let mut query = self.conn.prepare(
+            "SELECT id, timestamp, record FROM history_records
+             WHERE account = ?1 AND kind = ?2 AND timestamp >= ?3",
+        )?;
+        let rows = query.query_map(params![account, kind.as_str(), timestamp], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        let mut records = Vec::new();
+        for row in rows {
+            let (id, timestamp, record) = row?;
+            records.push(SyncRecord {
+                id,
+                timestamp,
+                record: serde_json::from_str(&record)?,
+            });
+        }
+        records.sort_by_key(SyncRecord::cursor);
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(id: u64) -> SyncRecord {
+        SyncRecord {
+            id: id.to_string(),
+            timestamp: 1000,
+            record: json!({ "id": id }),
+        }
+    }
+
+    #[test]
+    fn stores_records_and_cursor_together() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        assert_eq!(store.cursor("main", HistoryKind::Deposits).unwrap(), None);
+
+        store
+            .append("main", HistoryKind::Deposits, &[record(9), record(10)])
+            .unwrap();
+        assert_eq!(
+            store.cursor("main", HistoryKind::Deposits).unwrap(),
+            Some(record(10).cursor())
+        );
+        // Ids are ordered numerically within a timestamp.
+        let records = store.records("main", HistoryKind::Deposits).unwrap();
+        assert_eq!(records, vec![record(9), record(10)]);
+        assert!(
+            store
+                .records("other", HistoryKind::Deposits)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
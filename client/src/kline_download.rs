@@ -0,0 +1,218 @@
+//! Downloading long ranges of K-lines.
+//!
+//! [`KlineDownloader`] splits a `[start, end)` range into windows small enough
+//! for one `get_k_lines` request, fetches them with bounded concurrency (and
+//! under the client's [`RateLimiter`], if one is configured), removes duplicate
+//! K-lines at window edges and reports K-lines missing from the result.
+//!
+//! Intervals without trades either come back without prices or are missing
+//! altogether. With forward filling enabled, both are filled in with the close
+//! of the previous K-line and zero volume.
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::{BpxClient, types::markets::KlineInterval};
+//! use chrono::{TimeZone, Utc};
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+//! let end = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+//!
+//! let download = client
+//!     .kline_downloader("SOL_USDC", KlineInterval::OneMinute)
+//!     .with_forward_fill(true)
+//!     .download(start, end)
+//!     .await?;
+//! println!("{} K-lines, {} gaps", download.klines.len(), download.gaps.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`RateLimiter`]: crate::rate_limit::RateLimiter
+
+use bpx_api_types::{
+    markets::{Kline, KlineInterval},
+    symbol::Symbol,
+};
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use rust_decimal::Decimal;
+
+use crate::{BpxClient, Error, Result, now_millis};
+
+/// Default number of K-lines requested per window.
+const DEFAULT_WINDOW: u32 = 1000;
+
+/// Default number of windows fetched concurrently.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// A run of consecutive K-lines missing from a download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KlineGap {
+    /// Start of the first missing K-line.
+    pub start: DateTime<Utc>,
+    /// End of the last missing K-line.
+    pub end: DateTime<Utc>,
+    /// Number of missing K-lines.
+    pub count: u64,
+}
+
+/// The result of a K-line download.
+#[derive(Debug, Clone)]
+pub struct KlineDownload {
+    /// K-lines ordered by start time, without duplicates.
+    pub klines: Vec<Kline>,
+    /// K-lines that are missing and were not filled.
+    pub gaps: Vec<KlineGap>,
+    /// Number of K-lines that were forward filled.
+    pub filled: u64,
+}
+
+/// Downloads K-lines for one symbol and interval. See the
+/// [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct KlineDownloader {
+    client: BpxClient,
+    symbol: Symbol,
+    interval: KlineInterval,
+    window: u32,
+    concurrency: usize,
+    forward_fill: bool,
+}
+
+impl BpxClient {
+    /// Creates a downloader for the K-lines of `symbol` at `interval`.
+    pub fn kline_downloader(
+        &self,
+        symbol: impl Into<Symbol>,
+        interval: KlineInterval,
+    ) -> KlineDownloader {
+        KlineDownloader {
+            client: self.clone(),
+            symbol: symbol.into(),
+            interval,
+            window: DEFAULT_WINDOW,
+            concurrency: DEFAULT_CONCURRENCY,
+            forward_fill: false,
+        }
+    }
+}
+
+impl KlineDownloader {
+    /// Sets the number of K-lines requested at once. Defaults to 1000.
+    pub fn with_window(mut self, window: u32) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Sets the number of windows fetched concurrently. Defaults to 4.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Fills K-lines without trades with the close of the previous K-line.
+    pub fn with_forward_fill(mut self, forward_fill: bool) -> Self {
+        self.forward_fill = forward_fill;
+        self
+    }
+
+    /// Downloads the K-lines starting in `[start, end)`, where `start` is rounded
+    /// down to the start of its K-line. K-lines that have not started yet are
+    /// not expected.
+    pub async fn download(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<KlineDownload> {
+        let now = DateTime::from_timestamp_millis(now_millis() as i64)
+            .ok_or_else(|| Error::InvalidRequest("system time out of range".into()))?;
+        let start = self.interval.floor(start);
+        let end = end.min(now);
+
+        let mut windows = Vec::new();
+        let mut window_start = start;
+        while window_start < end {
+            let mut window_end = window_start;
+            for _ in 0..self.window {
+                window_end = self.interval.next(window_end);
+                if window_end >= end {
+                    break;
+                }
+            }
+            windows.push((window_start, window_end.min(end)));
+            window_start = window_end;
+        }
+
+        let mut klines = stream::iter(windows)
+            .map(|(start, end)| {
+                self.client.get_k_lines(
+                    &self.symbol,
+                    self.interval,
+                    start.timestamp(),
+                    Some(end.timestamp()),
+                )
+            })
+            .buffered(self.concurrency)
+            .try_concat()
+            .await?;
+
+        klines.retain(|kline| kline.start >= start && kline.start < end);
+        klines.sort_by_key(|kline| kline.start);
+        klines.dedup_by_key(|kline| kline.start);
+
+        Ok(self.fill(klines, start, end))
+    }
+
+    /// Walks the expected K-line starts, filling or reporting missing K-lines.
+    fn fill(&self, klines: Vec<Kline>, start: DateTime<Utc>, end: DateTime<Utc>) -> KlineDownload {
+        let mut download = KlineDownload {
+            klines: Vec::with_capacity(klines.len()),
+            gaps: Vec::new(),
+            filled: 0,
+        };
+        let mut received = klines.into_iter().peekable();
+        let mut expected = start;
+        while expected < end {
+            let next = self.interval.next(expected);
+            let kline = received.next_if(|kline| kline.start == expected);
+            let without_trades = kline.as_ref().is_none_or(|kline| kline.close.is_none());
+            let previous_close = download.klines.last().and_then(|kline| kline.close);
+
+            match (kline, previous_close) {
+                (_, Some(close)) if without_trades && self.forward_fill => {
+                    download.klines.push(Kline {
+                        start: expected,
+                        open: Some(close),
+                        high: Some(close),
+                        low: Some(close),
+                        close: Some(close),
+                        end: Some(next),
+                        volume: Decimal::ZERO,
+                        trades: 0,
+                    });
+                    download.filled += 1;
+                }
+                (Some(kline), _) => download.klines.push(kline),
+                (None, _) => match download.gaps.last_mut() {
+                    Some(gap) if gap.end == expected => {
+                        gap.end = next;
+                        gap.count += 1;
+                    }
+                    _ => download.gaps.push(KlineGap {
+                        start: expected,
+                        end: next,
+                        count: 1,
+                    }),
+                },
+            }
+
+            // K-lines off the expected grid are kept as they are.
+            while let Some(kline) = received.next_if(|kline| kline.start < next) {
+                download.klines.push(kline);
+            }
+            expected = next;
+        }
+        download
+    }
+}
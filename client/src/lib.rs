@@ -9,6 +9,16 @@
 //! - Supports both REST and WebSocket endpoints.
 //! - Includes modules for managing capital, orders, trades, and user data.
 //!
+//! ## Subaccounts
+//! API keys belong to a single account, so each subaccount that trades needs its
+//! own key and client. A client can also be scoped to a subaccount with
+//! [`BpxClientBuilder::subaccount_id`] or [`BpxClient::with_subaccount`], which
+//! filters the endpoints that accept a `subaccountId`.
+//!
+//! Listing subaccounts and transferring funds between the main account and its
+//! subaccounts are not supported: the public API does not offer endpoints for
+//! either, so these remain open until it does.
+//!
 //! ## Example
 //! ```no_run
 //! # // We depend on tokio only when the `ws` feature is enabled.
@@ -35,7 +45,10 @@
 
 use base64ct::{Base64, Encoding};
 use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
-use reqwest::{IntoUrl, Method, Request, Response, StatusCode, Url, header::CONTENT_TYPE};
+use reqwest::{
+    IntoUrl, Method, Request, Response, StatusCode, Url,
+    header::{CONTENT_TYPE, HeaderValue, USER_AGENT},
+};
 use routes::{
     account::{
         API_ACCOUNT, API_ACCOUNT_CONVERT_DUST, API_ACCOUNT_MAX_BORROW, API_ACCOUNT_MAX_ORDER,
@@ -44,7 +57,10 @@ use routes::{
     borrow_lend::API_BORROW_LEND_POSITIONS,
     capital::{API_CAPITAL, API_COLLATERAL, API_DEPOSIT_ADDRESS, API_DEPOSITS, API_WITHDRAWALS},
     futures::API_FUTURES_POSITION,
-    history::API_FILLS_HISTORY,
+    history::{
+        API_BORROW_LEND_HISTORY, API_FILLS_HISTORY, API_FUNDING_HISTORY, API_INTEREST_HISTORY,
+        API_SETTLEMENT_HISTORY,
+    },
     order::{API_ORDER, API_ORDERS},
     rfq::{API_RFQ, API_RFQ_QUOTE},
     user::API_USER_2FA,
@@ -58,7 +74,30 @@ use std::{
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+pub mod batch;
+pub mod candles;
+pub mod client_id;
+#[cfg(feature = "runtime")]
+pub mod dead_mans_switch;
 pub mod error;
+#[cfg(feature = "runtime")]
+pub mod execution;
+#[cfg(feature = "export")]
+pub mod export;
+pub mod funding;
+pub mod history_sync;
+pub mod kline_download;
+pub mod mark_price;
+pub mod order_book;
+pub mod order_tracker;
+pub mod paginate;
+pub mod pnl;
+pub mod pool;
+pub mod rate_limit;
+pub mod registry;
+#[cfg(feature = "runtime")]
+pub mod transfer_watcher;
+pub mod withdrawal;
 
 mod routes;
 
@@ -71,6 +110,8 @@ pub use bpx_api_types as types;
 /// Re-export of the custom `Error` type and `Result` alias for error handling.
 pub use error::{Error, Result};
 
+use crate::rate_limit::RateLimiter;
+
 use crate::routes::rfq::{API_RFQ_ACCEPT, API_RFQ_CANCEL, API_RFQ_REFRESH};
 
 const API_USER_AGENT: &str = "bpx-rust-client";
@@ -102,6 +143,12 @@ pub struct BpxClient {
     #[cfg_attr(not(feature = "ws"), allow(dead_code))]
     ws_url: Url,
     client: reqwest::Client,
+    rate_limiter: Option<RateLimiter>,
+    subaccount_id: Option<u64>,
+    /// Headers and timeout set on each request, used instead of the defaults
+    /// of the HTTP client when it is shared with other accounts.
+    request_headers: BpxHeaders,
+    request_timeout: Option<Duration>,
 }
 
 impl std::ops::Deref for BpxClient {
@@ -179,35 +226,44 @@ impl BpxClient {
 
     /// Sends a GET request to the specified URL and signs it before execution.
     pub async fn get<U: IntoUrl>(&self, url: U) -> Result<Response> {
+        self.wait_for_rate_limit().await;
         let req = self.build_and_maybe_sign_request::<(), _>(url, Method::GET, None)?;
         tracing::debug!(?req, "GET request");
-        self.execute(req).await
+        self.send(req).await
     }
 
     /// Sends a POST request with a JSON payload to the specified URL and signs it.
     pub async fn post<P: Serialize, U: IntoUrl>(&self, url: U, payload: P) -> Result<Response> {
+        self.wait_for_rate_limit().await;
         let req = self.build_and_maybe_sign_request(url, Method::POST, Some(&payload))?;
         tracing::debug!(?req, "POST request");
-        self.execute(req).await
+        self.send(req).await
     }
 
     /// Sends a DELETE request with a JSON payload to the specified URL and signs it.
     pub async fn delete<P: Serialize, U: IntoUrl>(&self, url: U, payload: P) -> Result<Response> {
+        self.wait_for_rate_limit().await;
         let req = self.build_and_maybe_sign_request(url, Method::DELETE, Some(&payload))?;
         tracing::debug!(?req, "DELETE request");
-        self.execute(req).await
+        self.send(req).await
     }
 
     /// Sends a PATCH request with a JSON payload to the specified URL and signs it.
     pub async fn patch<P: Serialize, U: IntoUrl>(&self, url: U, payload: P) -> Result<Response> {
+        self.wait_for_rate_limit().await;
         let req = self.build_and_maybe_sign_request(url, Method::PATCH, Some(&payload))?;
         tracing::debug!(?req, "PATCH request");
-        self.execute(req).await
+        self.send(req).await
     }
 
+    /// Sends a prepared request once the rate limiter admits it.
+    ///
+    /// A signed request's window starts when it is signed, so a request signed
+    /// before waiting here can expire in the queue. [`Self::get`], [`Self::post`],
+    /// [`Self::delete`] and [`Self::patch`] wait before signing.
     pub async fn execute(&self, request: Request) -> Result<Response> {
-        let res = self.client.execute(request).await?;
-        Self::process_response(res).await
+        self.wait_for_rate_limit().await;
+        self.send(request).await
     }
 
     /// Returns a reference to the [`VerifyingKey`] used for request verification.
@@ -224,10 +280,53 @@ impl BpxClient {
     pub fn base_url(&self) -> &Url {
         &self.base_url
     }
+
+    /// Returns the rate limiter requests wait on, if one was configured.
+    pub const fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Returns the subaccount this client is scoped to, if any.
+    pub const fn subaccount_id(&self) -> Option<u64> {
+        self.subaccount_id
+    }
+
+    /// Returns a copy of this client scoped to `subaccount_id`, or to the main
+    /// account if `None`. The copy shares the HTTP client and rate limiter.
+    ///
+    /// The scope is sent as `subaccountId` on the endpoints that accept it:
+    /// collateral, funding and interest history, and vault mint and redeem
+    /// history. A `subaccount_id` set on the call's parameters takes
+    /// precedence. Other endpoints, including orders and the websocket
+    /// streams, act on the account of the API key, so trading on a subaccount
+    /// requires a key created for it.
+    ///
+    /// The scope is not applied to [`BpxClient::get_deposits`] and
+    /// [`BpxClient::get_withdrawals`], which do not accept a `subaccountId`.
+    /// Filter their records on the `subaccount_id` field instead.
+    pub fn with_subaccount(&self, subaccount_id: Option<u64>) -> Self {
+        Self {
+            subaccount_id,
+            ..self.clone()
+        }
+    }
 }
 
 // Private functions.
 impl BpxClient {
+    /// Waits until the rate limiter, if any, admits a request. Signed requests
+    /// must be signed afterwards so that the wait does not eat into their window.
+    async fn wait_for_rate_limit(&self) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+    }
+
+    async fn send(&self, request: Request) -> Result<Response> {
+        let res = self.client.execute(request).await?;
+        Self::process_response(res).await
+    }
+
     /// Signs a request by generating a signature from the request details
     /// and appending necessary headers for authentication.
     ///
@@ -268,13 +367,17 @@ impl BpxClient {
             API_ACCOUNT if method == Method::PATCH => "accountUpdate",
             API_ACCOUNT_CONVERT_DUST if method == Method::POST => "convertDust",
             API_FILLS_HISTORY if method == Method::GET => "fillHistoryQueryAll",
+            API_FUNDING_HISTORY if method == Method::GET => "fundingHistoryQueryAll",
+            API_INTEREST_HISTORY if method == Method::GET => "interestHistoryQueryAll",
+            API_SETTLEMENT_HISTORY if method == Method::GET => "settlementHistoryQueryAll",
+            API_BORROW_LEND_HISTORY if method == Method::GET => "borrowHistoryQueryAll",
             API_VAULT_MINT if method == Method::POST => "vaultMint",
             API_VAULT_REDEEM if method == Method::POST => "vaultRedeemRequest",
             API_VAULT_REDEEM if method == Method::DELETE => "vaultRedeemCancel",
             API_VAULT_MINTS_HISTORY if method == Method::GET => "vaultMintHistoryQueryAll",
             API_VAULT_REDEEMS_HISTORY if method == Method::GET => "vaultRedeemHistoryQueryAll",
             _ => {
-                let req = self.client().request(method, url);
+                let req = self.request(method, url);
                 if let Some(payload) = payload {
                     return Ok(req.json(payload).build()?);
                 } else {
@@ -286,6 +389,29 @@ impl BpxClient {
         self.build_signed_request(url, method, instruction, payload)
     }
 
+    /// Starts a request with the per-request headers and timeout, if any.
+    fn request(&self, method: Method, url: Url) -> reqwest::RequestBuilder {
+        let req = self
+            .client
+            .request(method, url)
+            .headers(self.request_headers.clone());
+        match self.request_timeout {
+            Some(timeout) => req.timeout(timeout),
+            None => req,
+        }
+    }
+
+    /// Adds the client's subaccount to the query of `url`, unless the call
+    /// already filters by one. The parameter is part of the signed query.
+    fn apply_subaccount(&self, url: &mut Url) {
+        if let Some(subaccount_id) = self.subaccount_id
+            && !url.query_pairs().any(|(key, _)| key == "subaccountId")
+        {
+            url.query_pairs_mut()
+                .append_pair("subaccountId", &subaccount_id.to_string());
+        }
+    }
+
     /// Builds an authenticated request with signing headers.
     ///
     /// Use this to create signed requests for custom endpoints. The `instruction`
@@ -319,7 +445,7 @@ impl BpxClient {
         let signature: Signature = signing_key.sign(signee.as_bytes());
         let signature = Base64::encode_string(&signature.to_bytes());
 
-        let mut req = self.client().request(method, url);
+        let mut req = self.request(method, url);
         if let Some(payload) = payload {
             req = req.json(payload);
         }
@@ -385,6 +511,9 @@ pub struct BpxClientBuilder {
     secret: Option<String>,
     headers: Option<BpxHeaders>,
     timeout: Option<u64>,
+    rate_limiter: Option<RateLimiter>,
+    subaccount_id: Option<u64>,
+    http_client: Option<reqwest::Client>,
 }
 
 impl BpxClientBuilder {
@@ -458,6 +587,47 @@ impl BpxClientBuilder {
         self
     }
 
+    /// Sets a rate limiter every request waits on before it is sent.
+    /// If not set, requests are not rate limited.
+    ///
+    /// # Arguments
+    /// * `rate_limiter` - The rate limiter, possibly shared with other clients
+    ///
+    /// # Returns
+    /// * `Self` - The updated builder instance
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Sends requests through an existing HTTP client, sharing its connection
+    /// pool. The API key, custom headers and timeout are then set on each
+    /// request rather than as defaults of the HTTP client.
+    /// If not set, the client builds its own HTTP client.
+    ///
+    /// # Arguments
+    /// * `http_client` - The HTTP client, possibly shared with other clients
+    ///
+    /// # Returns
+    /// * `Self` - The updated builder instance
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Scopes the client to a subaccount. See [`BpxClient::with_subaccount`].
+    /// If not set, the client acts on the main account.
+    ///
+    /// # Arguments
+    /// * `subaccount_id` - The subaccount id
+    ///
+    /// # Returns
+    /// * `Self` - The updated builder instance
+    pub fn subaccount_id(mut self, subaccount_id: u64) -> Self {
+        self.subaccount_id = Some(subaccount_id);
+        self
+    }
+
     /// Builds the `BpxClient` instance with the configured parameters.
     ///
     /// # Returns
@@ -495,16 +665,35 @@ impl BpxClientBuilder {
             );
         }
 
+        let timeout = Duration::from_secs(self.timeout.unwrap_or(30));
+        let (client, request_headers, request_timeout) = match self.http_client {
+            Some(http_client) => {
+                if !header_map.contains_key(USER_AGENT) {
+                    header_map.insert(USER_AGENT, HeaderValue::from_static(API_USER_AGENT));
+                }
+                (http_client, header_map, Some(timeout))
+            }
+            None => (
+                reqwest::Client::builder()
+                    .user_agent(API_USER_AGENT)
+                    .default_headers(header_map)
+                    .timeout(timeout)
+                    .build()?,
+                BpxHeaders::new(),
+                None,
+            ),
+        };
+
         let client = BpxClient {
             signing_key,
             verifying_key,
             base_url,
             ws_url,
-            client: reqwest::Client::builder()
-                .user_agent(API_USER_AGENT)
-                .default_headers(header_map)
-                .timeout(Duration::from_secs(self.timeout.unwrap_or(30)))
-                .build()?,
+            client,
+            rate_limiter: self.rate_limiter,
+            subaccount_id: self.subaccount_id,
+            request_headers,
+            request_timeout,
         };
 
         Ok(client)
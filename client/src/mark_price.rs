@@ -0,0 +1,207 @@
+//! Cached mark prices.
+//!
+//! [`MarkPriceCache`] is seeded with the mark prices of every perpetual market
+//! over REST and kept current from `markPrice` websocket events
+//! ([`MarkPriceUpdate`]). Reads are hash map lookups, so the cache can be read
+//! on every tick. Clones share the same prices.
+//!
+//! Every entry records when it was received, see [`MarkPriceEntry::age`]. A
+//! REST refresh keeps websocket prices unless they are older than the cache's
+//! maximum websocket age, so refreshing periodically repairs the prices of
+//! markets whose stream stopped.
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::{BpxClient, mark_price::MarkPriceCache, types::markets::MarkPriceUpdate};
+//! use tokio::sync::mpsc::Receiver;
+//!
+//! # async fn run(client: BpxClient, rx: Receiver<MarkPriceUpdate>) -> bpx_api_client::Result<()> {
+//! // `rx` receives the `markPrice.<symbol>` streams of the markets of interest.
+//! let cache = MarkPriceCache::seed(&client).await?;
+//! tokio::spawn(cache.clone().run(rx));
+//!
+//! if let Some(sol) = cache.get("SOL_USDC_PERP") {
+//!     println!("mark {} index {} funding {}", sol.mark_price, sol.index_price, sol.funding_rate);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use bpx_api_types::{
+    markets::{MarkPrice, MarkPriceUpdate},
+    symbol::Symbol,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use tokio::sync::mpsc::Receiver;
+
+use crate::{BpxClient, Result};
+
+/// Default age after which a REST refresh replaces websocket prices.
+const DEFAULT_MAX_WEBSOCKET_AGE: Duration = Duration::from_secs(30);
+
+/// The latest prices of one market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkPriceEntry {
+    pub mark_price: Decimal,
+    pub index_price: Decimal,
+    /// Estimated funding rate for the current interval.
+    pub funding_rate: Decimal,
+    /// Time of the next funding payment.
+    pub next_funding_time: Option<DateTime<Utc>>,
+    /// Engine timestamp of the last websocket update in microseconds, or `None`
+    /// if the entry was loaded over REST.
+    pub engine_timestamp: Option<i64>,
+    /// Local time at which the entry was received.
+    pub received_at: Instant,
+}
+
+impl MarkPriceEntry {
+    /// Returns the time elapsed since the entry was received.
+    pub fn age(&self) -> Duration {
+        self.received_at.elapsed()
+    }
+}
+
+impl From<&MarkPrice> for MarkPriceEntry {
+    fn from(price: &MarkPrice) -> Self {
+        Self {
+            mark_price: price.mark_price,
+            index_price: price.index_price,
+            funding_rate: price.funding_rate,
+            next_funding_time: DateTime::from_timestamp_millis(price.next_funding_timestamp as i64),
+            engine_timestamp: None,
+            received_at: Instant::now(),
+        }
+    }
+}
+
+impl From<&MarkPriceUpdate> for MarkPriceEntry {
+    fn from(update: &MarkPriceUpdate) -> Self {
+        Self {
+            mark_price: update.mark_price,
+            index_price: update.index_price,
+            funding_rate: update.funding_rate,
+            next_funding_time: DateTime::from_timestamp_micros(update.funding_timestamp as i64),
+            engine_timestamp: Some(update.engine_timestamp),
+            received_at: Instant::now(),
+        }
+    }
+}
+
+/// Mark, index and funding prices by symbol. See the
+/// [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct MarkPriceCache {
+    prices: Arc<RwLock<HashMap<String, MarkPriceEntry>>>,
+    max_websocket_age: Duration,
+}
+
+impl Default for MarkPriceCache {
+    fn default() -> Self {
+        Self {
+            prices: Arc::default(),
+            max_websocket_age: DEFAULT_MAX_WEBSOCKET_AGE,
+        }
+    }
+}
+
+impl MarkPriceCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the age after which [`Self::refresh`] replaces prices received
+    /// over the websocket. Defaults to 30 seconds.
+    pub fn with_max_websocket_age(mut self, max_age: Duration) -> Self {
+        self.max_websocket_age = max_age;
+        self
+    }
+
+    /// Creates a cache holding the current mark prices of every market.
+    pub async fn seed(client: &BpxClient) -> Result<Self> {
+        let cache = Self::new();
+        cache.refresh(client).await?;
+        Ok(cache)
+    }
+
+    /// Reloads the mark prices of every market over REST. Prices received over
+    /// the websocket are kept until they are older than the maximum websocket
+    /// age, since REST prices carry no timestamp to tell whether they are more
+    /// recent.
+    pub async fn refresh(&self, client: &BpxClient) -> Result<()> {
+        let prices = client.get_all_mark_prices(None::<Symbol>).await?;
+        let mut cached = self.prices.write().expect("mark price cache poisoned");
+        for price in &prices {
+            match cached.get_mut(&price.symbol) {
+                Some(entry)
+                    if entry.engine_timestamp.is_some()
+                        && entry.age() <= self.max_websocket_age => {}
+                Some(entry) => *entry = price.into(),
+                None => {
+                    cached.insert(price.symbol.clone(), price.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a websocket update. Updates older than the cached prices of the
+    /// market are ignored.
+    pub fn apply(&self, update: &MarkPriceUpdate) {
+        let mut cached = self.prices.write().expect("mark price cache poisoned");
+        match cached.get_mut(&update.symbol) {
+            Some(entry)
+                if entry
+                    .engine_timestamp
+                    .is_some_and(|timestamp| timestamp > update.engine_timestamp) => {}
+            Some(entry) => *entry = update.into(),
+            None => {
+                cached.insert(update.symbol.clone(), update.into());
+            }
+        }
+    }
+
+    /// Applies updates from `updates` until the channel is closed.
+    pub async fn run(self, mut updates: Receiver<MarkPriceUpdate>) {
+        while let Some(update) = updates.recv().await {
+            self.apply(&update);
+        }
+    }
+
+    /// Returns the prices of a market.
+    pub fn get(&self, symbol: &str) -> Option<MarkPriceEntry> {
+        let cached = self.prices.read().expect("mark price cache poisoned");
+        cached.get(symbol).copied()
+    }
+
+    /// Returns the mark price of a market.
+    pub fn mark_price(&self, symbol: &str) -> Option<Decimal> {
+        self.get(symbol).map(|entry| entry.mark_price)
+    }
+
+    /// Returns the index price of a market.
+    pub fn index_price(&self, symbol: &str) -> Option<Decimal> {
+        self.get(symbol).map(|entry| entry.index_price)
+    }
+
+    /// Returns the estimated funding rate of a market.
+    pub fn funding_rate(&self, symbol: &str) -> Option<Decimal> {
+        self.get(symbol).map(|entry| entry.funding_rate)
+    }
+
+    /// Returns a copy of every cached entry.
+    pub fn snapshot(&self) -> HashMap<String, MarkPriceEntry> {
+        self.prices
+            .read()
+            .expect("mark price cache poisoned")
+            .clone()
+    }
+}
@@ -0,0 +1,535 @@
+//! Order book analytics and local order books.
+//!
+//! [`OrderBookAnalytics`] estimates what a market order would cost before it is
+//! sent: the average fill price for a base or quote quantity, the slippage
+//! versus the mid price, the liquidity within a distance from the mid price,
+//! the order imbalance and the microprice. [`OrderBookAnalytics::impact_check`]
+//! predicts whether the exchange would reject or cap an order because of the
+//! impact limits in its [`PriceFilter`].
+//!
+//! The analytics are implemented for [`OrderBookDepth`] snapshots and for
+//! [`OrderBook`], which is kept up to date from `depth` websocket events.
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::{
+//!     BpxClient,
+//!     order_book::{FillAmount, OrderBookAnalytics},
+//!     types::{markets::OrderBookDepthLimit, order::Side},
+//! };
+//! use rust_decimal_macros::dec;
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let depth = client
+//!     .get_order_book_depth("SOL_USDC", Some(OrderBookDepthLimit::OneHundred))
+//!     .await?;
+//! if let Some(estimate) = depth.estimate_fill(Side::Bid, FillAmount::Base(dec!(250))) {
+//!     println!(
+//!         "average {} worst {} slippage {:?} bps",
+//!         estimate.average_price, estimate.worst_price, estimate.slippage_bps
+//!     );
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use bpx_api_types::{
+    markets::{OrderBookDepth, OrderBookDepthUpdate, PriceFilter},
+    order::{ExecuteOrderPayload, Side, SlippageToleranceType},
+};
+use rust_decimal::Decimal;
+
+use crate::{Error, Result};
+
+const BPS: Decimal = Decimal::from_parts(10_000, 0, 0, false, 0);
+
+/// The size of a hypothetical order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillAmount {
+    /// A quantity of the base asset.
+    Base(Decimal),
+    /// A quantity of the quote asset to spend or receive.
+    Quote(Decimal),
+}
+
+/// The expected result of sweeping the book with a market order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillEstimate {
+    /// Base quantity filled.
+    pub quantity: Decimal,
+    /// Quote quantity filled.
+    pub quote_quantity: Decimal,
+    /// Volume weighted average fill price.
+    pub average_price: Decimal,
+    /// Price of the last level the order reaches.
+    pub worst_price: Decimal,
+    /// Number of price levels the order reaches.
+    pub levels: usize,
+    /// Whether the book holds enough liquidity to fill the whole amount.
+    pub complete: bool,
+    /// Distance of the average fill price from the mid price, in basis points,
+    /// positive when the fill is worse than the mid price.
+    pub slippage_bps: Option<Decimal>,
+}
+
+/// The predicted outcome of a market order against the impact limits of a
+/// market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImpactOutcome {
+    /// The whole order fills within the impact limit.
+    Fills,
+    /// Only `quantity` of the base asset fills, limited by the impact limit or
+    /// by the liquidity in the book, and the rest of the order would not be
+    /// filled.
+    Capped { quantity: Decimal },
+    /// No liquidity is within the impact limit, so the order would be rejected.
+    Rejected,
+}
+
+/// Analytics over the price levels of an order book. See the
+/// [module documentation](self).
+pub trait OrderBookAnalytics {
+    /// Bid levels as price-quantity pairs, best (highest) first.
+    fn bid_levels(&self) -> impl Iterator<Item = (Decimal, Decimal)> + '_;
+
+    /// Ask levels as price-quantity pairs, best (lowest) first.
+    fn ask_levels(&self) -> impl Iterator<Item = (Decimal, Decimal)> + '_;
+
+    /// Returns the best bid as a price-quantity pair.
+    fn best_bid(&self) -> Option<(Decimal, Decimal)> {
+        self.bid_levels().next()
+    }
+
+    /// Returns the best ask as a price-quantity pair.
+    fn best_ask(&self) -> Option<(Decimal, Decimal)> {
+        self.ask_levels().next()
+    }
+
+    /// Returns the mid price.
+    fn mid_price(&self) -> Option<Decimal> {
+        let ((bid, _), (ask, _)) = (self.best_bid()?, self.best_ask()?);
+        Some((bid + ask) / Decimal::TWO)
+    }
+
+    /// Returns the bid-ask spread in basis points of the mid price.
+    fn spread_bps(&self) -> Option<Decimal> {
+        let ((bid, _), (ask, _)) = (self.best_bid()?, self.best_ask()?);
+        let mid = self.mid_price()?;
+        (!mid.is_zero()).then(|| (ask - bid) / mid * BPS)
+    }
+
+    /// Returns the mid price weighted by the size on the opposite side of the
+    /// book, which leans towards the side more likely to be traded through.
+    fn microprice(&self) -> Option<Decimal> {
+        let ((bid, bid_quantity), (ask, ask_quantity)) = (self.best_bid()?, self.best_ask()?);
+        let total = bid_quantity + ask_quantity;
+        (!total.is_zero()).then(|| (bid * ask_quantity + ask * bid_quantity) / total)
+    }
+
+    /// Returns the order imbalance over the best `levels` levels of each side,
+    /// from -1 (only asks) to 1 (only bids).
+    fn imbalance(&self, levels: usize) -> Option<Decimal> {
+        let bids: Decimal = self.bid_levels().take(levels).map(|(_, q)| q).sum();
+        let asks: Decimal = self.ask_levels().take(levels).map(|(_, q)| q).sum();
+        let total = bids + asks;
+        (!total.is_zero()).then(|| (bids - asks) / total)
+    }
+
+    /// Returns the base quantity resting on `side` within `bps` basis points of
+    /// the mid price.
+    fn depth_within_bps(&self, side: Side, bps: Decimal) -> Decimal {
+        let Some(mid) = self.mid_price() else {
+            return Decimal::ZERO;
+        };
+        let distance = mid * bps / BPS;
+        match side {
+            Side::Bid => self
+                .bid_levels()
+                .take_while(|(price, _)| *price >= mid - distance)
+                .map(|(_, quantity)| quantity)
+                .sum(),
+            Side::Ask => self
+                .ask_levels()
+                .take_while(|(price, _)| *price <= mid + distance)
+                .map(|(_, quantity)| quantity)
+                .sum(),
+        }
+    }
+
+    /// Estimates the fill of a market order on `side` for `amount`. Returns
+    /// `None` if the opposite side of the book is empty or `amount` is not
+    /// positive.
+    fn estimate_fill(&self, side: Side, amount: FillAmount) -> Option<FillEstimate> {
+        let mut estimate = sweep(self, side, amount, None)?;
+        estimate.slippage_bps = self.mid_price().and_then(|mid| {
+            (!mid.is_zero()).then(|| match side {
+                Side::Bid => (estimate.average_price - mid) / mid * BPS,
+                Side::Ask => (mid - estimate.average_price) / mid * BPS,
+            })
+        });
+        Some(estimate)
+    }
+
+    /// Predicts how the exchange treats a market order on `side` for `amount`,
+    /// given the `max_impact_multiplier` and `min_impact_multiplier` of `filter`
+    /// applied to `reference_price`, the last active price of the market.
+    /// Orders are assumed to fill if the filter has no impact limit.
+    fn impact_check(
+        &self,
+        side: Side,
+        amount: FillAmount,
+        filter: &PriceFilter,
+        reference_price: Decimal,
+    ) -> ImpactOutcome {
+        let multiplier = match side {
+            Side::Bid => filter.max_impact_multiplier,
+            Side::Ask => filter.min_impact_multiplier,
+        };
+        let limit = multiplier.map(|multiplier| reference_price * multiplier);
+        let Some(unbounded) = sweep(self, side, amount, None) else {
+            return ImpactOutcome::Rejected;
+        };
+        match sweep(self, side, amount, limit) {
+            None => ImpactOutcome::Rejected,
+            Some(bounded) if bounded.quantity < unbounded.quantity || !unbounded.complete => {
+                ImpactOutcome::Capped {
+                    quantity: bounded.quantity,
+                }
+            }
+            Some(_) => ImpactOutcome::Fills,
+        }
+    }
+
+    /// Sets the slippage tolerance of a market order to the distance between
+    /// the mid price and the worst expected fill price, plus `buffer_bps`, as a
+    /// percentage. Returns the tolerance, or `None` (leaving `payload`
+    /// untouched) if the payload has no quantity or the book cannot fill it.
+    fn fill_slippage_tolerance(
+        &self,
+        payload: &mut ExecuteOrderPayload,
+        buffer_bps: Decimal,
+    ) -> Option<Decimal> {
+        let amount = match (payload.quantity, payload.quote_quantity) {
+            (Some(quantity), _) => FillAmount::Base(quantity),
+            (None, Some(quote_quantity)) => FillAmount::Quote(quote_quantity),
+            (None, None) => return None,
+        };
+        let estimate = self.estimate_fill(payload.side, amount)?;
+        let mid = self.mid_price().filter(|mid| !mid.is_zero())?;
+        if !estimate.complete {
+            return None;
+        }
+        let distance = match payload.side {
+            Side::Bid => estimate.worst_price - mid,
+            Side::Ask => mid - estimate.worst_price,
+        };
+        let tolerance =
+            (distance.max(Decimal::ZERO) / mid * BPS + buffer_bps) / Decimal::ONE_HUNDRED;
+        payload.slippage_tolerance = Some(tolerance);
+        payload.slippage_tolerance_type = Some(SlippageToleranceType::Percent);
+        Some(tolerance)
+    }
+}
+
+/// Walks the side of the book a market order on `side` takes from, stopping at
+/// `amount` or at the first level beyond `limit`.
+fn sweep<B: OrderBookAnalytics + ?Sized>(
+    book: &B,
+    side: Side,
+    amount: FillAmount,
+    limit: Option<Decimal>,
+) -> Option<FillEstimate> {
+    let remaining = match amount {
+        FillAmount::Base(quantity) | FillAmount::Quote(quantity) => quantity,
+    };
+    if remaining <= Decimal::ZERO {
+        return None;
+    }
+    let levels: Box<dyn Iterator<Item = (Decimal, Decimal)> + '_> = match side {
+        Side::Bid => Box::new(book.ask_levels()),
+        Side::Ask => Box::new(book.bid_levels()),
+    };
+    let within_limit = |price: Decimal| match (side, limit) {
+        (_, None) => true,
+        (Side::Bid, Some(limit)) => price <= limit,
+        (Side::Ask, Some(limit)) => price >= limit,
+    };
+
+    let mut estimate = FillEstimate {
+        quantity: Decimal::ZERO,
+        quote_quantity: Decimal::ZERO,
+        average_price: Decimal::ZERO,
+        worst_price: Decimal::ZERO,
+        levels: 0,
+        complete: false,
+        slippage_bps: None,
+    };
+    let mut remaining = remaining;
+    for (price, quantity) in levels.take_while(|(price, _)| within_limit(*price)) {
+        if quantity <= Decimal::ZERO {
+            continue;
+        }
+        let take = match amount {
+            FillAmount::Base(_) => quantity.min(remaining),
+            FillAmount::Quote(_) => quantity.min(remaining / price),
+        };
+        estimate.quantity += take;
+        estimate.quote_quantity += take * price;
+        estimate.worst_price = price;
+        estimate.levels += 1;
+        remaining -= match amount {
+            FillAmount::Base(_) => take,
+            FillAmount::Quote(_) => take * price,
+        };
+        if remaining <= Decimal::ZERO {
+            estimate.complete = true;
+            break;
+        }
+    }
+    if estimate.quantity.is_zero() {
+        return None;
+    }
+    estimate.average_price = estimate.quote_quantity / estimate.quantity;
+    Some(estimate)
+}
+
+/// Both sides of a depth response are sorted by ascending price.
+impl OrderBookAnalytics for OrderBookDepth {
+    fn bid_levels(&self) -> impl Iterator<Item = (Decimal, Decimal)> + '_ {
+        self.bids.iter().rev().copied()
+    }
+
+    fn ask_levels(&self) -> impl Iterator<Item = (Decimal, Decimal)> + '_ {
+        self.asks.iter().copied()
+    }
+}
+
+/// An order book maintained locally from a depth snapshot and `depth` websocket
+/// updates.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+    last_update_id: i64,
+    timestamp: i64,
+}
+
+impl OrderBook {
+    /// Returns the id of the last update applied to the book.
+    pub fn last_update_id(&self) -> i64 {
+        self.last_update_id
+    }
+
+    /// Returns the engine timestamp of the last update, in microseconds.
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// Applies a depth update. Updates already contained in the book are
+    /// ignored. Returns an error if updates were missed, in which case the book
+    /// must be rebuilt from a new snapshot.
+    pub fn apply(&mut self, update: &OrderBookDepthUpdate) -> Result<()> {
+        if update.last_update_id <= self.last_update_id {
+            return Ok(());
+        }
+        if update.first_update_id > self.last_update_id + 1 {
+            return Err(Error::UnexpectedResponse(
+                format!(
+                    "order book update {} does not follow {}",
+                    update.first_update_id, self.last_update_id
+                )
+                .into(),
+            ));
+        }
+        for &(price, quantity) in &update.bids {
+            set_level(&mut self.bids, price, quantity);
+        }
+        for &(price, quantity) in &update.asks {
+            set_level(&mut self.asks, price, quantity);
+        }
+        self.last_update_id = update.last_update_id;
+        self.timestamp = update.timestamp;
+        Ok(())
+    }
+}
+
+fn set_level(levels: &mut BTreeMap<Decimal, Decimal>, price: Decimal, quantity: Decimal) {
+    if quantity.is_zero() {
+        levels.remove(&price);
+    } else {
+        levels.insert(price, quantity);
+    }
+}
+
+impl From<&OrderBookDepth> for OrderBook {
+    fn from(depth: &OrderBookDepth) -> Self {
+        Self {
+            bids: depth.bids.iter().copied().collect(),
+            asks: depth.asks.iter().copied().collect(),
+            last_update_id: depth.last_update_id,
+            timestamp: depth.timestamp,
+        }
+    }
+}
+
+impl From<OrderBookDepth> for OrderBook {
+    fn from(depth: OrderBookDepth) -> Self {
+        Self::from(&depth)
+    }
+}
+
+impl OrderBookAnalytics for OrderBook {
+    fn bid_levels(&self) -> impl Iterator<Item = (Decimal, Decimal)> + '_ {
+        self.bids
+            .iter()
+            .rev()
+            .map(|(price, quantity)| (*price, *quantity))
+    }
+
+    fn ask_levels(&self) -> impl Iterator<Item = (Decimal, Decimal)> + '_ {
+        self.asks
+            .iter()
+            .map(|(price, quantity)| (*price, *quantity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn depth() -> OrderBookDepth {
+        OrderBookDepth {
+            asks: vec![
+                (dec!(101), dec!(2)),
+                (dec!(102), dec!(5)),
+                (dec!(105), dec!(10)),
+            ],
+            bids: vec![(dec!(98), dec!(4)), (dec!(99), dec!(6))],
+            last_update_id: 10,
+            timestamp: 0,
+        }
+    }
+
+    fn price_filter(max_impact: Option<Decimal>, min_impact: Option<Decimal>) -> PriceFilter {
+        PriceFilter {
+            min_price: dec!(0.01),
+            max_price: None,
+            tick_size: dec!(0.01),
+            max_multiplier: None,
+            min_multiplier: None,
+            max_impact_multiplier: max_impact,
+            min_impact_multiplier: min_impact,
+            mean_mark_price_band: None,
+            mean_premium_band: None,
+            discovery_bound_band: None,
+            borrow_entry_fee_max_multiplier: None,
+            borrow_entry_fee_min_multiplier: None,
+            max_price_update_multiplier: None,
+            min_price_update_multiplier: None,
+        }
+    }
+
+    #[test]
+    fn computes_top_of_book_metrics() {
+        let book = depth();
+        assert_eq!(book.best_bid(), Some((dec!(99), dec!(6))));
+        assert_eq!(book.best_ask(), Some((dec!(101), dec!(2))));
+        assert_eq!(book.mid_price(), Some(dec!(100)));
+        assert_eq!(book.spread_bps(), Some(dec!(200)));
+        assert_eq!(book.microprice(), Some(dec!(100.5)));
+        assert_eq!(book.imbalance(1), Some(dec!(0.5)));
+        assert_eq!(book.depth_within_bps(Side::Ask, dec!(200)), dec!(7));
+        assert_eq!(book.depth_within_bps(Side::Bid, dec!(200)), dec!(10));
+    }
+
+    #[test]
+    fn estimates_fills_by_base_and_quote_quantity() {
+        let book = depth();
+        let buy = book
+            .estimate_fill(Side::Bid, FillAmount::Base(dec!(4)))
+            .unwrap();
+        assert_eq!(buy.quote_quantity, dec!(406));
+        assert_eq!(buy.average_price, dec!(101.5));
+        assert_eq!(buy.worst_price, dec!(102));
+        assert_eq!(buy.levels, 2);
+        assert!(buy.complete);
+        assert_eq!(buy.slippage_bps, Some(dec!(150)));
+
+        let sell = book
+            .estimate_fill(Side::Ask, FillAmount::Quote(dec!(986)))
+            .unwrap();
+        assert_eq!(sell.quantity, dec!(10));
+        assert_eq!(sell.worst_price, dec!(98));
+
+        let too_big = book
+            .estimate_fill(Side::Bid, FillAmount::Base(dec!(100)))
+            .unwrap();
+        assert_eq!(too_big.quantity, dec!(17));
+        assert!(!too_big.complete);
+    }
+
+    #[test]
+    fn predicts_impact_limits() {
+        let book = depth();
+        let filter = price_filter(Some(dec!(1.03)), Some(dec!(0.995)));
+        let buy =
+            |quantity| book.impact_check(Side::Bid, FillAmount::Base(quantity), &filter, dec!(100));
+        assert_eq!(buy(dec!(5)), ImpactOutcome::Fills);
+        assert_eq!(buy(dec!(10)), ImpactOutcome::Capped { quantity: dec!(7) });
+        assert_eq!(
+            book.impact_check(Side::Ask, FillAmount::Base(dec!(1)), &filter, dec!(100)),
+            ImpactOutcome::Rejected
+        );
+    }
+
+    #[test]
+    fn fills_slippage_tolerance() {
+        let mut payload = ExecuteOrderPayload {
+            side: Side::Bid,
+            quantity: Some(dec!(4)),
+            ..Default::default()
+        };
+        let tolerance = depth().fill_slippage_tolerance(&mut payload, dec!(10));
+        assert_eq!(tolerance, Some(dec!(2.1)));
+        assert_eq!(payload.slippage_tolerance, Some(dec!(2.1)));
+        assert_eq!(
+            payload.slippage_tolerance_type,
+            Some(SlippageToleranceType::Percent)
+        );
+    }
+
+    #[test]
+    fn maintains_local_book() {
+        let mut book = OrderBook::from(depth());
+        let update = |first, last, bids, asks| OrderBookDepthUpdate {
+            event_type: "depth".to_string(),
+            event_time: 0,
+            symbol: "SOL_USDC".to_string(),
+            timestamp: last,
+            first_update_id: first,
+            last_update_id: last,
+            asks,
+            bids,
+        };
+
+        book.apply(&update(
+            11,
+            12,
+            vec![(dec!(99), dec!(0))],
+            vec![(dec!(100), dec!(1))],
+        ))
+        .unwrap();
+        assert_eq!(book.best_bid(), Some((dec!(98), dec!(4))));
+        assert_eq!(book.best_ask(), Some((dec!(100), dec!(1))));
+        assert_eq!(book.last_update_id(), 12);
+
+        // Stale updates are ignored, missed updates are reported.
+        book.apply(&update(5, 9, vec![(dec!(120), dec!(1))], vec![]))
+            .unwrap();
+        assert_eq!(book.best_bid(), Some((dec!(98), dec!(4))));
+        assert!(book.apply(&update(14, 15, vec![], vec![])).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+//! Local order state maintained from REST responses and `account.orderUpdate`
+//! websocket events.
+//!
+//! [`OrderTracker`] keeps one [`TrackedOrder`] per order id, indexes orders by
+//! client id and records each fill once, even if the same update is delivered
+//! twice.
+
+use std::collections::{HashMap, HashSet};
+
+use bpx_api_types::order::{Order, OrderStatus, OrderUpdate, OrderUpdateType, Side};
+use rust_decimal::Decimal;
+
+/// A single fill of a tracked order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedFill {
+    pub trade_id: Option<u64>,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub fee: Option<Decimal>,
+    pub fee_symbol: Option<String>,
+    pub was_maker: Option<bool>,
+    /// Engine timestamp in microseconds.
+    pub timestamp: i64,
+}
+
+/// The locally known state of an order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedOrder {
+    pub order_id: String,
+    pub client_id: Option<u32>,
+    pub symbol: String,
+    pub side: Side,
+    pub price: Option<Decimal>,
+    /// Order quantity, if the order was placed with a base quantity.
+    pub quantity: Option<Decimal>,
+    pub executed_quantity: Decimal,
+    pub executed_quote_quantity: Decimal,
+    pub status: OrderStatus,
+    pub fills: Vec<TrackedFill>,
+}
+
+impl TrackedOrder {
+    /// Returns `true` while the order can still trade.
+    pub const fn is_open(&self) -> bool {
+        is_open_status(self.status)
+    }
+
+    /// Merges the executed quantities and status of a REST response or an
+    /// update. The status is taken when the executed quantity increases, or
+    /// when it is unchanged and the incoming status is terminal, e.g. in the
+    /// response to a cancel request. A terminal status never reverts to an
+    /// open one, so stale updates cannot reopen a closed order.
+    fn merge(
+        &mut self,
+        executed_quantity: Decimal,
+        executed_quote_quantity: Decimal,
+        status: OrderStatus,
+    ) {
+        let take_status = if executed_quantity > self.executed_quantity {
+            self.executed_quantity = executed_quantity;
+            self.executed_quote_quantity = executed_quote_quantity;
+            true
+        } else {
+            executed_quantity == self.executed_quantity && !is_open_status(status)
+        };
+        if take_status && (self.is_open() || !is_open_status(status)) {
+            self.status = status;
+        }
+    }
+
+    /// Quantity left to fill, if the order was placed with a base quantity.
+    pub fn remaining_quantity(&self) -> Option<Decimal> {
+        self.quantity
+            .map(|quantity| (quantity - self.executed_quantity).max(Decimal::ZERO))
+    }
+
+    /// Volume weighted average fill price.
+    pub fn average_price(&self) -> Option<Decimal> {
+        (!self.executed_quantity.is_zero())
+            .then(|| self.executed_quote_quantity / self.executed_quantity)
+    }
+}
+
+impl From<&Order> for TrackedOrder {
+    fn from(order: &Order) -> Self {
+        match order {
+            Order::Market(order) => Self {
+                order_id: order.id.clone(),
+                client_id: order.client_id,
+                symbol: order.symbol.clone(),
+                side: order.side,
+                price: None,
+                quantity: order.quantity,
+                executed_quantity: order.executed_quantity,
+                executed_quote_quantity: order.executed_quote_quantity,
+                status: order.status,
+                fills: Vec::new(),
+            },
+            Order::Limit(order) => Self {
+                order_id: order.id.clone(),
+                client_id: order.client_id,
+                symbol: order.symbol.clone(),
+                side: order.side,
+                price: Some(order.price),
+                quantity: Some(order.quantity),
+                executed_quantity: order.executed_quantity,
+                executed_quote_quantity: order.executed_quote_quantity,
+                status: order.status,
+                fills: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Returns `true` while an order with `status` can still trade.
+const fn is_open_status(status: OrderStatus) -> bool {
+    matches!(
+        status,
+        OrderStatus::New
+            | OrderStatus::PartiallyFilled
+            | OrderStatus::Triggered
+            | OrderStatus::TriggerPending
+    )
+}
+
+/// Tracks orders from REST responses and websocket order updates.
+#[derive(Debug, Clone, Default)]
+pub struct OrderTracker {
+    orders: HashMap<String, TrackedOrder>,
+    client_ids: HashMap<u32, String>,
+    trade_ids: HashSet<(String, u64)>,
+}
+
+impl OrderTracker {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Starts tracking an order returned by the REST API, e.g. from `execute_order`
+    /// or `cancel_order`. Executed quantities never move backwards if updates
+    /// arrived first.
+    pub fn track(&mut self, order: &Order) -> &TrackedOrder {
+        let incoming = TrackedOrder::from(order);
+        if let Some(client_id) = incoming.client_id {
+            self.client_ids.insert(client_id, incoming.order_id.clone());
+        }
+        let tracked = self
+            .orders
+            .entry(incoming.order_id.clone())
+            .or_insert_with(|| incoming.clone());
+        tracked.merge(
+            incoming.executed_quantity,
+            incoming.executed_quote_quantity,
+            incoming.status,
+        );
+        tracked
+    }
+
+    /// Applies an order update, returning the updated order. Updates for orders
+    /// that are not tracked yet start tracking them.
+    pub fn apply(&mut self, update: &OrderUpdate) -> &TrackedOrder {
+        let client_id = update
+            .client_order_id
+            .and_then(|client_id| u32::try_from(client_id).ok());
+        if let Some(client_id) = client_id {
+            self.client_ids.insert(client_id, update.order_id.clone());
+        }
+
+        let tracked = self
+            .orders
+            .entry(update.order_id.clone())
+            .or_insert_with(|| TrackedOrder {
+                order_id: update.order_id.clone(),
+                client_id,
+                symbol: update.symbol.clone(),
+                side: update.side,
+                price: update.price,
+                quantity: (!update.quantity.is_zero()).then_some(update.quantity),
+                executed_quantity: Decimal::ZERO,
+                executed_quote_quantity: Decimal::ZERO,
+                status: update.order_status,
+                fills: Vec::new(),
+            });
+
+        tracked.merge(
+            update.executed_quantity,
+            update.executed_quantity_in_quote,
+            update.order_status,
+        );
+        if tracked.price.is_none() {
+            tracked.price = update.price;
+        }
+
+        if matches!(update.event_type, OrderUpdateType::OrderFill)
+            && let (Some(price), Some(quantity)) = (update.fill_price, update.fill_quantity)
+        {
+            let is_new = update
+                .trade_id
+                .is_none_or(|trade_id| self.trade_ids.insert((update.order_id.clone(), trade_id)));
+            if is_new {
+                tracked.fills.push(TrackedFill {
+                    trade_id: update.trade_id,
+                    price,
+                    quantity,
+                    fee: update.fee,
+                    fee_symbol: update.fee_symbol.clone(),
+                    was_maker: update.was_maker,
+                    timestamp: update.timestamp,
+                });
+            }
+        }
+        tracked
+    }
+
+    /// Returns a tracked order by order id.
+    pub fn get(&self, order_id: &str) -> Option<&TrackedOrder> {
+        self.orders.get(order_id)
+    }
+
+    /// Returns a tracked order by client id.
+    pub fn get_by_client_id(&self, client_id: u32) -> Option<&TrackedOrder> {
+        self.orders.get(self.client_ids.get(&client_id)?)
+    }
+
+    /// Iterates over orders that can still trade.
+    pub fn open_orders(&self) -> impl Iterator<Item = &TrackedOrder> {
+        self.orders.values().filter(|order| order.is_open())
+    }
+
+    /// Iterates over every tracked order.
+    pub fn orders(&self) -> impl Iterator<Item = &TrackedOrder> {
+        self.orders.values()
+    }
+
+    /// Stops tracking an order.
+    pub fn remove(&mut self, order_id: &str) -> Option<TrackedOrder> {
+        let order = self.orders.remove(order_id)?;
+        if let Some(client_id) = order.client_id {
+            self.client_ids.remove(&client_id);
+        }
+        self.trade_ids.retain(|(id, _)| id != order_id);
+        Some(order)
+    }
+
+    /// Stops tracking every order that can no longer trade.
+    pub fn remove_closed(&mut self) {
+        let closed = self
+            .orders
+            .values()
+            .filter(|order| !order.is_open())
+            .map(|order| order.order_id.clone())
+            .collect::<Vec<_>>();
+        for order_id in closed {
+            self.remove(&order_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn update(event: &str, status: &str, executed: &str, fill: Option<(&str, u64)>) -> OrderUpdate {
+        let fill = fill
+            .map(|(quantity, trade_id)| {
+                format!(
+                    r#","l":"{quantity}","L":"10","t":{trade_id},"m":true,"n":"0.01","N":"USDC""#
+                )
+            })
+            .unwrap_or_default();
+        serde_json::from_str(&format!(
+            r#"{{"E":1,"O":"USER","S":"Bid","T":1,"V":"RejectTaker","X":"{status}","Z":"0","c":7,"e":"{event}","f":"GTC","i":"42","o":"LIMIT","p":"10","q":"3","s":"SOL_USDC","z":"{executed}"{fill}}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn tracks_fills_from_order_updates() {
+        let mut tracker = OrderTracker::new();
+        tracker.apply(&update("orderAccepted", "New", "0", None));
+        tracker.apply(&update(
+            "orderFill",
+            "PartiallyFilled",
+            "1",
+            Some(("1", 100)),
+        ));
+        // Duplicate delivery of the same fill.
+        tracker.apply(&update(
+            "orderFill",
+            "PartiallyFilled",
+            "1",
+            Some(("1", 100)),
+        ));
+        let order = tracker.apply(&update("orderFill", "Filled", "3", Some(("2", 101))));
+
+        assert_eq!(order.fills.len(), 2);
+        assert_eq!(order.executed_quantity, dec!(3));
+        assert_eq!(order.remaining_quantity(), Some(dec!(0)));
+        assert!(!order.is_open());
+        assert_eq!(tracker.get_by_client_id(7).unwrap().order_id, "42");
+
+        tracker.remove_closed();
+        assert!(tracker.get("42").is_none());
+        assert!(tracker.get_by_client_id(7).is_none());
+    }
+
+    #[test]
+    fn stale_updates_do_not_move_executed_quantity_backwards() {
+        let mut tracker = OrderTracker::new();
+        tracker.apply(&update("orderFill", "PartiallyFilled", "2", Some(("2", 1))));
+        let order = tracker.apply(&update("orderAccepted", "New", "0", None));
+        assert_eq!(order.executed_quantity, dec!(2));
+        assert_eq!(order.status, OrderStatus::PartiallyFilled);
+    }
+
+    #[test]
+    fn stale_accept_does_not_reopen_a_cancelled_order() {
+        let mut tracker = OrderTracker::new();
+        // The response to a cancel request arrives before the accept update.
+        let cancelled: Order = serde_json::from_str(
+            r#"{"orderType":"Limit","id":"42","clientId":7,"symbol":"SOL_USDC","side":"Bid",
+                "quantity":"3","executedQuantity":"0","executedQuoteQuantity":"0","price":"10",
+                "timeInForce":"GTC","selfTradePrevention":"RejectTaker","postOnly":false,
+                "status":"Cancelled","createdAt":0}"#,
+        )
+        .unwrap();
+        tracker.track(&cancelled);
+        let order = tracker.apply(&update("orderAccepted", "New", "0", None));
+        assert_eq!(order.status, OrderStatus::Cancelled);
+        assert!(!order.is_open());
+        assert_eq!(tracker.open_orders().count(), 0);
+    }
+}
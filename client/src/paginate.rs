@@ -0,0 +1,369 @@
+//! Streams over offset-paginated endpoints.
+//!
+//! A [`Paginator`] requests pages with increasing offsets and yields their
+//! items one by one, stopping at the first short page or after an optional
+//! maximum number of items. Items are identified by a key, and items already
+//! yielded are skipped, since items added while walking the history shift the
+//! following pages and would otherwise be returned twice.
+//!
+//! The history endpoints that take a limit and an offset have ready-made
+//! paginators, e.g. [`BpxClient::deposits_paginator`].
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::BpxClient;
+//! use futures_util::TryStreamExt;
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let deposits = client
+//!     .deposits_paginator()
+//!     .with_page_size(1000)
+//!     .with_max_items(5000)
+//!     .into_stream();
+//! let mut deposits = std::pin::pin!(deposits);
+//! while let Some(deposit) = deposits.try_next().await? {
+//!     println!("{} {} {}", deposit.id, deposit.quantity, deposit.symbol);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashSet, future::Future, hash::Hash};
+
+use bpx_api_types::{
+    capital::{Deposit, Withdrawal},
+    fill::{Fill, FillsHistoryParams},
+    symbol::Symbol,
+    trade::Trade,
+    vault::{VaultMint, VaultMintHistoryParams, VaultRedeem, VaultRedeemHistoryParams},
+};
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt, future::BoxFuture, stream};
+
+use crate::{BpxClient, Result};
+
+/// Default number of items requested per page.
+const DEFAULT_PAGE_SIZE: u64 = 100;
+
+/// The future returned by the page fetchers of the ready-made paginators.
+pub type PageFuture<T> = BoxFuture<'static, Result<Vec<T>>>;
+
+/// Identifies a fill by its order id, trade id and timestamp.
+pub type FillKey = (String, Option<i64>, DateTime<Utc>);
+
+/// Walks an offset-paginated endpoint. See the [module documentation](self).
+///
+/// `fetch` is called with a limit and an offset and returns one page. `key`
+/// identifies items to skip duplicates.
+#[derive(Debug, Clone)]
+pub struct Paginator<F, K> {
+    fetch: F,
+    key: K,
+    page_size: u64,
+    offset: u64,
+    max_items: Option<u64>,
+}
+
+impl<F, K> Paginator<F, K> {
+    /// Creates a paginator starting at offset zero.
+    pub fn new(fetch: F, key: K) -> Self {
+        Self {
+            fetch,
+            key,
+            page_size: DEFAULT_PAGE_SIZE,
+            offset: 0,
+            max_items: None,
+        }
+    }
+
+    /// Sets the number of items requested per page. Defaults to 100.
+    pub fn with_page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Sets the offset of the first page.
+    pub fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Stops after yielding `max_items` items.
+    pub fn with_max_items(mut self, max_items: u64) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Returns a stream of the items of every page. The stream ends after the
+    /// first error.
+    pub fn into_stream<T, Fut, Id>(self) -> impl Stream<Item = Result<T>>
+    where
+        F: FnMut(u64, u64) -> Fut,
+        Fut: Future<Output = Result<Vec<T>>>,
+        K: Fn(&T) -> Id,
+        Id: Hash + Eq,
+    {
+        let state = Walk {
+            remaining: self.max_items.unwrap_or(u64::MAX),
+            offset: self.offset,
+            seen: HashSet::new(),
+            done: false,
+            paginator: self,
+        };
+        stream::unfold(state, |mut walk| async move {
+            let items = walk.next_page().await?;
+            Some((stream::iter(items), walk))
+        })
+        .flatten()
+    }
+}
+
+/// The state of a walk over the pages.
+struct Walk<F, K, Id> {
+    paginator: Paginator<F, K>,
+    offset: u64,
+    remaining: u64,
+    seen: HashSet<Id>,
+    done: bool,
+}
+
+impl<F, K, Id> Walk<F, K, Id>
+where
+    Id: Hash + Eq,
+{
+    /// Fetches pages until one holds new items, returning `None` once the walk
+    /// is over.
+    async fn next_page<T, Fut>(&mut self) -> Option<Vec<Result<T>>>
+    where
+        F: FnMut(u64, u64) -> Fut,
+        Fut: Future<Output = Result<Vec<T>>>,
+        K: Fn(&T) -> Id,
+    {
+        while !self.done && self.remaining > 0 {
+            let page_size = self.paginator.page_size;
+            let page = match (self.paginator.fetch)(page_size, self.offset).await {
+                Ok(page) => page,
+                Err(err) => {
+                    self.done = true;
+                    return Some(vec![Err(err)]);
+                }
+            };
+            self.done = (page.len() as u64) < page_size;
+            self.offset += page.len() as u64;
+
+            let key = &self.paginator.key;
+            let seen = &mut self.seen;
+            let items: Vec<_> = page
+                .into_iter()
+                .filter(|item| seen.insert(key(item)))
+                .take(self.remaining.try_into().unwrap_or(usize::MAX))
+                .map(Ok)
+                .collect();
+            self.remaining -= items.len() as u64;
+            if !items.is_empty() {
+                return Some(items);
+            }
+        }
+        None
+    }
+}
+
+impl BpxClient {
+    /// Pages through the deposit history.
+    pub fn deposits_paginator(
+        &self,
+    ) -> Paginator<impl FnMut(u64, u64) -> PageFuture<Deposit>, impl Fn(&Deposit) -> i32> {
+        let client = self.clone();
+        Paginator::new(
+            move |limit, offset| -> PageFuture<Deposit> {
+                let client = client.clone();
+                Box::pin(async move {
+                    client
+                        .get_deposits(Some(limit as i64), Some(offset as i64))
+                        .await
+                })
+            },
+            |deposit: &Deposit| deposit.id,
+        )
+    }
+
+    /// Pages through the withdrawal history.
+    pub fn withdrawals_paginator(
+        &self,
+    ) -> Paginator<impl FnMut(u64, u64) -> PageFuture<Withdrawal>, impl Fn(&Withdrawal) -> i32>
+    {
+        let client = self.clone();
+        Paginator::new(
+            move |limit, offset| -> PageFuture<Withdrawal> {
+                let client = client.clone();
+                Box::pin(async move {
+                    client
+                        .get_withdrawals(Some(limit as i64), Some(offset as i64))
+                        .await
+                })
+            },
+            |withdrawal: &Withdrawal| withdrawal.id,
+        )
+    }
+
+    /// Pages through the public trade history of a market.
+    pub fn historical_trades_paginator(
+        &self,
+        symbol: impl Into<Symbol>,
+    ) -> Paginator<impl FnMut(u64, u64) -> PageFuture<Trade>, impl Fn(&Trade) -> i64> {
+        let client = self.clone();
+        let symbol = symbol.into();
+        Paginator::new(
+            move |limit, offset| -> PageFuture<Trade> {
+                let client = client.clone();
+                let symbol = symbol.clone();
+                Box::pin(async move {
+                    client
+                        .get_historical_trades(symbol, Some(limit as i64), Some(offset as i64))
+                        .await
+                })
+            },
+            |trade: &Trade| trade.id,
+        )
+    }
+
+    /// Pages through the fill history matching `params`. The limit and offset
+    /// of `params` are replaced by those of the paginator.
+    pub fn historical_fills_paginator(
+        &self,
+        params: FillsHistoryParams,
+    ) -> Paginator<impl FnMut(u64, u64) -> PageFuture<Fill>, impl Fn(&Fill) -> FillKey> {
+        let client = self.clone();
+        Paginator::new(
+            move |limit, offset| -> PageFuture<Fill> {
+                let client = client.clone();
+                let params = params.clone().with_limit(limit).with_offset(offset);
+                Box::pin(async move { client.get_historical_fills(params).await })
+            },
+            |fill: &Fill| (fill.order_id.clone(), fill.trade_id, fill.timestamp),
+        )
+    }
+
+    /// Pages through the vault mint history matching `params`. The limit and
+    /// offset of `params` are replaced by those of the paginator.
+    pub fn vault_mints_paginator(
+        &self,
+        params: VaultMintHistoryParams,
+    ) -> Paginator<impl FnMut(u64, u64) -> PageFuture<VaultMint>, impl Fn(&VaultMint) -> String>
+    {
+        let client = self.clone();
+        Paginator::new(
+            move |limit, offset| -> PageFuture<VaultMint> {
+                let client = client.clone();
+                let params = VaultMintHistoryParams {
+                    limit: Some(limit),
+                    offset: Some(offset),
+                    ..params.clone()
+                };
+                Box::pin(async move { client.get_vault_mints(params).await })
+            },
+            |mint: &VaultMint| mint.id.clone(),
+        )
+    }
+
+    /// Pages through the vault redeem history matching `params`. The limit and
+    /// offset of `params` are replaced by those of the paginator.
+    pub fn vault_redeems_paginator(
+        &self,
+        params: VaultRedeemHistoryParams,
+    ) -> Paginator<impl FnMut(u64, u64) -> PageFuture<VaultRedeem>, impl Fn(&VaultRedeem) -> String>
+    {
+        let client = self.clone();
+        Paginator::new(
+            move |limit, offset| -> PageFuture<VaultRedeem> {
+                let client = client.clone();
+                let params = VaultRedeemHistoryParams {
+                    limit: Some(limit),
+                    offset: Some(offset),
+                    ..params.clone()
+                };
+                Box::pin(async move { client.get_vault_redeems(params).await })
+            },
+            |redeem: &VaultRedeem| redeem.id.clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures_util::TryStreamExt;
+
+    use super::*;
+    use crate::Error;
+
+    /// Serves pages of `history` (most recent first), inserting `inserted` new
+    /// items at the front after the first request.
+    fn source(
+        history: Vec<u64>,
+        inserted: Vec<u64>,
+    ) -> impl FnMut(u64, u64) -> std::future::Ready<Result<Vec<u64>>> {
+        let history = Arc::new(Mutex::new(history));
+        let mut requests = 0;
+        move |limit, offset| {
+            let mut history = history.lock().unwrap();
+            if requests == 1 {
+                for item in inserted.iter().rev() {
+                    history.insert(0, *item);
+                }
+            }
+            requests += 1;
+            let page = history
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .copied()
+                .collect();
+            std::future::ready(Ok(page))
+        }
+    }
+
+    #[tokio::test]
+    async fn skips_items_shifted_by_new_items() {
+        let items: Vec<u64> =
+            Paginator::new(source((1..=7).rev().collect(), vec![9, 8]), |i: &u64| *i)
+                .with_page_size(3)
+                .into_stream()
+                .try_collect()
+                .await
+                .unwrap();
+        assert_eq!(items, vec![7, 6, 5, 4, 3, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn stops_at_max_items() {
+        let items: Vec<u64> = Paginator::new(source((1..=7).rev().collect(), vec![]), |i: &u64| *i)
+            .with_page_size(3)
+            .with_max_items(4)
+            .into_stream()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items, vec![7, 6, 5, 4]);
+    }
+
+    #[tokio::test]
+    async fn ends_after_an_error() {
+        let mut calls = 0;
+        let fetch = move |_, _| {
+            calls += 1;
+            std::future::ready(match calls {
+                1 => Ok(vec![1, 2]),
+                _ => Err(Error::InvalidRequest("boom".into())),
+            })
+        };
+        let results: Vec<Result<u64>> = Paginator::new(fetch, |i: &u64| *i)
+            .with_page_size(2)
+            .into_stream()
+            .collect()
+            .await;
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+    }
+}
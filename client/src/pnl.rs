@@ -0,0 +1,560 @@
+//! Realized PnL and cost basis from fill history.
+//!
+//! [`PnlLedger`] consumes fills in timestamp order and keeps, per symbol, the
+//! open lots of the position, the realized PnL, the fees paid and a maker/taker
+//! breakdown. Lots are matched first in first out, last in first out or at
+//! their average cost ([`CostBasis`]). Positions may be long or short; a fill
+//! that crosses zero closes the position and opens one on the other side.
+//!
+//! Realized PnL is gross of fees and in the quote asset of the market. Fees are
+//! converted to the quote asset:
+//! - fees in the quote asset are taken as they are;
+//! - fees in the base asset are valued at the fill price. They reduce the
+//!   quantity bought, or add to the quantity sold;
+//! - fees in other assets are valued with a [`PriceSource`].
+//!
+//! With a reporting currency, realized PnL and fees are also converted to it at
+//! the time of each fill, e.g. with [`KlinePrices`] loaded from `get_k_lines`.
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::{
+//!     BpxClient,
+//!     pnl::{CostBasis, KlinePrices, PnlLedger},
+//!     types::{fill::FillsHistoryParams, markets::KlineInterval},
+//! };
+//! use chrono::{TimeZone, Utc};
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+//! let end = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap();
+//! let fills = client
+//!     .get_historical_fills(FillsHistoryParams::default().with_from(start.timestamp_millis()))
+//!     .await?;
+//! let prices =
+//!     KlinePrices::load(&client, ["USDT_USDC"], KlineInterval::OneHour, start, end).await?;
+//!
+//! let mut ledger = PnlLedger::new(CostBasis::Fifo)
+//!     .with_prices(prices)
+//!     .with_reporting_currency("USDC");
+//! ledger.apply_all(fills)?;
+//! for pnl in ledger.symbols() {
+//!     println!("{}: realized {} fees {}", pnl.symbol, pnl.realized_pnl, pnl.fees);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use bpx_api_types::{
+    fill::Fill,
+    markets::{Kline, KlineInterval},
+    order::Side,
+    symbol::Symbol,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use crate::{BpxClient, Error, Result};
+
+/// How closing fills are matched against open lots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CostBasis {
+    /// Close the oldest lots first.
+    #[default]
+    Fifo,
+    /// Close the most recent lots first.
+    Lifo,
+    /// Keep a single lot at the average cost of the position.
+    AverageCost,
+}
+
+/// An open part of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lot {
+    /// Open quantity, always positive. The side is that of the position.
+    pub quantity: Decimal,
+    /// Cost per unit in the quote asset.
+    pub price: Decimal,
+    /// Time of the fill that opened the lot.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Totals of the maker or the taker fills of a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FillBreakdown {
+    pub fills: u64,
+    /// Traded quantity in the base asset.
+    pub quantity: Decimal,
+    /// Traded value in the quote asset.
+    pub notional: Decimal,
+    /// Fees in the quote asset.
+    pub fees: Decimal,
+    /// Realized PnL in the quote asset, gross of fees.
+    pub realized_pnl: Decimal,
+}
+
+/// The PnL of one symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolPnl {
+    pub symbol: String,
+    pub base: String,
+    pub quote: String,
+    /// Open position in the base asset, negative when short.
+    pub position: Decimal,
+    /// Open lots, oldest first.
+    pub lots: VecDeque<Lot>,
+    /// Realized PnL in the quote asset, gross of fees.
+    pub realized_pnl: Decimal,
+    /// Fees in the quote asset.
+    pub fees: Decimal,
+    /// Fees in the asset they were paid in.
+    pub fees_by_asset: BTreeMap<String, Decimal>,
+    /// Realized PnL in the reporting currency, or the quote asset if none is
+    /// set.
+    pub reported_realized_pnl: Decimal,
+    /// Fees in the reporting currency, or the quote asset if none is set.
+    pub reported_fees: Decimal,
+    pub maker: FillBreakdown,
+    pub taker: FillBreakdown,
+}
+
+impl SymbolPnl {
+    fn new(symbol: &Symbol, base: &str, quote: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            base: base.to_string(),
+            quote: quote.to_string(),
+            position: Decimal::ZERO,
+            lots: VecDeque::new(),
+            realized_pnl: Decimal::ZERO,
+            fees: Decimal::ZERO,
+            fees_by_asset: BTreeMap::new(),
+            reported_realized_pnl: Decimal::ZERO,
+            reported_fees: Decimal::ZERO,
+            maker: FillBreakdown::default(),
+            taker: FillBreakdown::default(),
+        }
+    }
+
+    /// Realized PnL net of fees, in the quote asset.
+    pub fn net_pnl(&self) -> Decimal {
+        self.realized_pnl - self.fees
+    }
+
+    /// Average cost of the open position.
+    pub fn average_cost(&self) -> Option<Decimal> {
+        let quantity: Decimal = self.lots.iter().map(|lot| lot.quantity).sum();
+        let cost: Decimal = self.lots.iter().map(|lot| lot.quantity * lot.price).sum();
+        (!quantity.is_zero()).then(|| cost / quantity)
+    }
+
+    /// Trades `quantity` (negative to sell) at `price` and returns the realized
+    /// PnL.
+    fn trade(
+        &mut self,
+        quantity: Decimal,
+        price: Decimal,
+        timestamp: DateTime<Utc>,
+        method: CostBasis,
+    ) -> Decimal {
+        let mut realized = Decimal::ZERO;
+        let mut remaining = quantity.abs();
+        let closing = !self.position.is_zero()
+            && self.position.is_sign_positive() != quantity.is_sign_positive();
+        if closing {
+            let direction = sign(self.position);
+            while remaining > Decimal::ZERO {
+                let lot = match method {
+                    CostBasis::Fifo | CostBasis::AverageCost => self.lots.front_mut(),
+                    CostBasis::Lifo => self.lots.back_mut(),
+                };
+                let Some(lot) = lot else {
+                    break;
+                };
+                let closed = remaining.min(lot.quantity);
+                realized += closed * (price - lot.price) * direction;
+                lot.quantity -= closed;
+                remaining -= closed;
+                self.position -= closed * direction;
+                if lot.quantity.is_zero() {
+                    match method {
+                        CostBasis::Fifo | CostBasis::AverageCost => self.lots.pop_front(),
+                        CostBasis::Lifo => self.lots.pop_back(),
+                    };
+                }
+            }
+        }
+        if remaining > Decimal::ZERO {
+            self.position += remaining * sign(quantity);
+            let lot = Lot {
+                quantity: remaining,
+                price,
+                timestamp,
+            };
+            match (method, self.lots.front_mut()) {
+                (CostBasis::AverageCost, Some(average)) => {
+                    let quantity = average.quantity + lot.quantity;
+                    average.price =
+                        (average.quantity * average.price + lot.quantity * lot.price) / quantity;
+                    average.quantity = quantity;
+                }
+                _ => self.lots.push_back(lot),
+            }
+        }
+        realized
+    }
+}
+
+/// Historical prices used to value fees and convert to a reporting currency.
+pub trait PriceSource {
+    /// Returns the price of one unit of `asset` in `quote` at `at`.
+    fn price(&self, asset: &str, quote: &str, at: DateTime<Utc>) -> Option<Decimal>;
+}
+
+/// No prices: only fees in the base or quote asset and a reporting currency
+/// equal to the quote asset are supported.
+impl PriceSource for () {
+    fn price(&self, _asset: &str, _quote: &str, _at: DateTime<Utc>) -> Option<Decimal> {
+        None
+    }
+}
+
+/// Prices from K-lines, by market symbol.
+///
+/// The price at a time is the open of the K-line containing it, or the close
+/// of the last K-line before it. Markets are used in both directions: a price
+/// of `USDT` in `USDC` is looked up in `USDT_USDC` and, inverted, in
+/// `USDC_USDT`.
+#[derive(Debug, Clone, Default)]
+pub struct KlinePrices {
+    klines: HashMap<String, Vec<Kline>>,
+}
+
+impl KlinePrices {
+    /// Creates an empty set of prices.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Downloads the K-lines of `symbols` between `start` and `end`, forward
+    /// filling intervals without trades.
+    pub async fn load<S: Into<Symbol>>(
+        client: &BpxClient,
+        symbols: impl IntoIterator<Item = S>,
+        interval: KlineInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Self> {
+        let mut prices = Self::new();
+        for symbol in symbols {
+            let symbol = symbol.into();
+            let download = client
+                .kline_downloader(symbol.clone(), interval)
+                .with_forward_fill(true)
+                .download(start, end)
+                .await?;
+            prices.insert(symbol, download.klines);
+        }
+        Ok(prices)
+    }
+
+    /// Adds the K-lines of a market, replacing those already present.
+    pub fn insert(&mut self, symbol: impl Into<Symbol>, mut klines: Vec<Kline>) {
+        klines.sort_by_key(|kline| kline.start);
+        self.klines.insert(symbol.into().to_string(), klines);
+    }
+
+    fn market_price(&self, symbol: &str, at: DateTime<Utc>) -> Option<Decimal> {
+        let klines = self.klines.get(symbol)?;
+        let index = klines.partition_point(|kline| kline.start <= at);
+        let kline = klines.get(index.checked_sub(1)?)?;
+        if kline.end.is_none_or(|end| at < end) {
+            kline.open.or(kline.close)
+        } else {
+            kline.close
+        }
+    }
+}
+
+impl PriceSource for KlinePrices {
+    fn price(&self, asset: &str, quote: &str, at: DateTime<Utc>) -> Option<Decimal> {
+        if let Some(price) = self.market_price(&format!("{asset}_{quote}"), at) {
+            return Some(price);
+        }
+        let inverse = self.market_price(&format!("{quote}_{asset}"), at)?;
+        (!inverse.is_zero()).then(|| Decimal::ONE / inverse)
+    }
+}
+
+/// Realized PnL per symbol. See the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct PnlLedger<P = ()> {
+    method: CostBasis,
+    prices: P,
+    reporting_currency: Option<String>,
+    symbols: BTreeMap<String, SymbolPnl>,
+    last_timestamp: Option<DateTime<Utc>>,
+}
+
+impl PnlLedger {
+    /// Creates an empty ledger matching lots with `method`.
+    pub fn new(method: CostBasis) -> Self {
+        Self {
+            method,
+            prices: (),
+            reporting_currency: None,
+            symbols: BTreeMap::new(),
+            last_timestamp: None,
+        }
+    }
+}
+
+impl<P: PriceSource> PnlLedger<P> {
+    /// Sets the prices used to value fees paid in other assets and to convert
+    /// to the reporting currency.
+    pub fn with_prices<Q: PriceSource>(self, prices: Q) -> PnlLedger<Q> {
+        PnlLedger {
+            method: self.method,
+            prices,
+            reporting_currency: self.reporting_currency,
+            symbols: self.symbols,
+            last_timestamp: self.last_timestamp,
+        }
+    }
+
+    /// Converts realized PnL and fees to `currency`. Without one, they are
+    /// reported in the quote asset of each market.
+    pub fn with_reporting_currency(mut self, currency: impl Into<String>) -> Self {
+        self.reporting_currency = Some(currency.into());
+        self
+    }
+
+    /// Applies a fill. Fills must be applied in timestamp order.
+    pub fn apply(&mut self, fill: &Fill) -> Result<()> {
+        let timestamp = fill.timestamp;
+        if self.last_timestamp.is_some_and(|last| timestamp < last) {
+            return Err(Error::InvalidRequest(
+                format!("fill at {timestamp} applied after a later fill").into(),
+            ));
+        }
+
+        let symbol = Symbol::from(fill.symbol.as_str());
+        let (Some(base), Some(quote)) = (symbol.base(), symbol.quote()) else {
+            return Err(Error::InvalidRequest(
+                format!("invalid symbol: {}", fill.symbol).into(),
+            ));
+        };
+        let fee = if fill.fee_symbol == quote {
+            fill.fee
+        } else if fill.fee_symbol == base {
+            fill.fee * fill.price
+        } else {
+            self.convert(fill.fee, &fill.fee_symbol, quote, timestamp)?
+        };
+        let rate = match &self.reporting_currency {
+            Some(currency) => self.convert(Decimal::ONE, quote, currency, timestamp)?,
+            None => Decimal::ONE,
+        };
+
+        let base_fee = if fill.fee_symbol == base {
+            fill.fee
+        } else {
+            Decimal::ZERO
+        };
+        let quantity = match fill.side {
+            Side::Bid => fill.quantity - base_fee,
+            Side::Ask => -(fill.quantity + base_fee),
+        };
+        let pnl = self
+            .symbols
+            .entry(symbol.to_string())
+            .or_insert_with(|| SymbolPnl::new(&symbol, base, quote));
+        let realized = pnl.trade(quantity, fill.price, timestamp, self.method);
+
+        pnl.realized_pnl += realized;
+        pnl.fees += fee;
+        *pnl.fees_by_asset
+            .entry(fill.fee_symbol.clone())
+            .or_default() += fill.fee;
+        pnl.reported_realized_pnl += realized * rate;
+        pnl.reported_fees += fee * rate;
+        let breakdown = if fill.is_maker {
+            &mut pnl.maker
+        } else {
+            &mut pnl.taker
+        };
+        breakdown.fills += 1;
+        breakdown.quantity += fill.quantity;
+        breakdown.notional += fill.quantity * fill.price;
+        breakdown.fees += fee;
+        breakdown.realized_pnl += realized;
+
+        self.last_timestamp = Some(timestamp);
+        Ok(())
+    }
+
+    /// Sorts `fills` by timestamp and trade id and applies them.
+    pub fn apply_all(&mut self, fills: impl IntoIterator<Item = Fill>) -> Result<()> {
+        let mut fills: Vec<_> = fills.into_iter().collect();
+        fills.sort_by_key(|fill| (fill.timestamp, fill.trade_id));
+        fills.iter().try_for_each(|fill| self.apply(fill))
+    }
+
+    /// Returns the PnL of a symbol.
+    pub fn symbol(&self, symbol: &str) -> Option<&SymbolPnl> {
+        self.symbols.get(symbol)
+    }
+
+    /// Returns the PnL of every traded symbol, ordered by symbol.
+    pub fn symbols(&self) -> impl Iterator<Item = &SymbolPnl> {
+        self.symbols.values()
+    }
+
+    /// Returns the realized PnL and fees of every symbol in the reporting
+    /// currency, or `None` if no reporting currency is set.
+    pub fn totals(&self) -> Option<(Decimal, Decimal)> {
+        self.reporting_currency.as_ref()?;
+        Some(
+            self.symbols()
+                .fold((Decimal::ZERO, Decimal::ZERO), |(realized, fees), pnl| {
+                    (
+                        realized + pnl.reported_realized_pnl,
+                        fees + pnl.reported_fees,
+                    )
+                }),
+        )
+    }
+
+    fn convert(&self, amount: Decimal, from: &str, to: &str, at: DateTime<Utc>) -> Result<Decimal> {
+        if from == to {
+            return Ok(amount);
+        }
+        let price = self
+            .prices
+            .price(from, to, at)
+            .ok_or_else(|| Error::InvalidRequest(format!("no {from}/{to} price at {at}").into()))?;
+        Ok(amount * price)
+    }
+}
+
+fn sign(value: Decimal) -> Decimal {
+    if value.is_sign_negative() {
+        Decimal::NEGATIVE_ONE
+    } else {
+        Decimal::ONE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn fill(second: u32, side: Side, quantity: Decimal, price: Decimal) -> Fill {
+        Fill {
+            trade_id: Some(second.into()),
+            client_id: None,
+            order_id: second.to_string(),
+            symbol: "SOL_USDC".to_string(),
+            fee_symbol: "USDC".to_string(),
+            price,
+            quantity,
+            fee: Decimal::ZERO,
+            side,
+            timestamp: DateTime::from_timestamp(1735689600 + i64::from(second), 0).unwrap(),
+            is_maker: false,
+            system_order_type: None,
+        }
+    }
+
+    fn trades() -> Vec<Fill> {
+        vec![
+            fill(1, Side::Bid, dec!(1), dec!(100)),
+            fill(2, Side::Bid, dec!(1), dec!(120)),
+            fill(3, Side::Ask, dec!(1), dec!(130)),
+        ]
+    }
+
+    #[test]
+    fn matches_lots_by_method() {
+        for (method, realized, remaining) in [
+            (CostBasis::Fifo, dec!(30), dec!(120)),
+            (CostBasis::Lifo, dec!(10), dec!(100)),
+            (CostBasis::AverageCost, dec!(20), dec!(110)),
+        ] {
+            let mut ledger = PnlLedger::new(method);
+            ledger.apply_all(trades()).unwrap();
+            let pnl = ledger.symbol("SOL_USDC").unwrap();
+            assert_eq!(pnl.realized_pnl, realized, "{method:?}");
+            assert_eq!(pnl.position, dec!(1));
+            assert_eq!(pnl.average_cost(), Some(remaining), "{method:?}");
+        }
+    }
+
+    #[test]
+    fn crosses_from_long_to_short() {
+        let mut ledger = PnlLedger::new(CostBasis::Fifo);
+        ledger
+            .apply_all([
+                fill(1, Side::Bid, dec!(1), dec!(100)),
+                fill(2, Side::Ask, dec!(3), dec!(110)),
+                fill(3, Side::Bid, dec!(1), dec!(90)),
+            ])
+            .unwrap();
+        let pnl = ledger.symbol("SOL_USDC").unwrap();
+        assert_eq!(pnl.realized_pnl, dec!(30));
+        assert_eq!(pnl.position, dec!(-1));
+        assert_eq!(pnl.lots[0].price, dec!(110));
+    }
+
+    #[test]
+    fn values_fees_and_splits_maker_and_taker() {
+        let mut buy = fill(1, Side::Bid, dec!(2), dec!(100));
+        buy.fee_symbol = "SOL".to_string();
+        buy.fee = dec!(0.01);
+        let mut sell = fill(2, Side::Ask, dec!(1), dec!(110));
+        sell.fee_symbol = "USDT".to_string();
+        sell.fee = dec!(0.5);
+        sell.is_maker = true;
+
+        let mut prices = KlinePrices::new();
+        let kline: Kline = serde_json::from_str(
+            r#"{"start":"2025-01-01 00:00:00","open":"1.002","high":"1.002","low":"1.002",
+                "close":"1.002","volume":"1","trades":"1"}"#,
+        )
+        .unwrap();
+        prices.insert("USDC_USDT", vec![kline]);
+        let mut ledger = PnlLedger::new(CostBasis::Fifo)
+            .with_prices(prices)
+            .with_reporting_currency("USDT");
+        ledger.apply_all([buy, sell]).unwrap();
+
+        let pnl = ledger.symbol("SOL_USDC").unwrap();
+        // The base asset fee reduces the quantity bought.
+        assert_eq!(pnl.position, dec!(0.99));
+        assert_eq!(pnl.realized_pnl, dec!(10));
+        assert_eq!(pnl.taker.fees, dec!(1));
+        assert_eq!(pnl.maker.fees, dec!(0.5) / dec!(1.002));
+        assert_eq!(pnl.maker.realized_pnl, dec!(10));
+        assert_eq!(pnl.fees_by_asset["SOL"], dec!(0.01));
+        assert_eq!(pnl.reported_realized_pnl, dec!(10.02));
+        assert_eq!(ledger.totals().unwrap().0, dec!(10.02));
+    }
+
+    #[test]
+    fn rejects_fills_out_of_order() {
+        let mut ledger = PnlLedger::new(CostBasis::Fifo);
+        ledger
+            .apply(&fill(2, Side::Bid, dec!(1), dec!(100)))
+            .unwrap();
+        assert!(
+            ledger
+                .apply(&fill(1, Side::Bid, dec!(1), dec!(100)))
+                .is_err()
+        );
+        assert!(ledger.totals().is_none());
+    }
+}
@@ -0,0 +1,209 @@
+//! Clients for many accounts sharing one connection pool.
+//!
+//! A [`BpxClientPool`] holds one [`BpxClient`] per account label. Every client
+//! sends its requests through the same `reqwest::Client`, but signs them with
+//! its own key and, with [`BpxClientPool::with_rate_limit`], waits on its own
+//! [`RateLimiter`]. The fan-out helpers query every account concurrently and
+//! return one result per account, so a failing account does not hide the
+//! others.
+//!
+//! ## Example
+//! ```no_run
+//! use std::time::Duration;
+//! use bpx_api_client::pool::{BpxClientPool, total_balances};
+//!
+//! # async fn run(secrets: Vec<(String, String)>) -> bpx_api_client::Result<()> {
+//! let mut pool = BpxClientPool::new()?.with_rate_limit(20, Duration::from_secs(1));
+//! for (label, secret) in secrets {
+//!     pool.add_account(label, secret)?;
+//! }
+//!
+//! let balances = pool.get_balances().await;
+//! for (label, result) in &balances {
+//!     if let Err(error) = result {
+//!         eprintln!("{label}: {error}");
+//!     }
+//! }
+//! println!("{:?}", total_balances(&balances));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    time::Duration,
+};
+
+use bpx_api_types::{
+    capital::{Balance, Collateral},
+    futures::FuturePosition,
+    order::Order,
+    symbol::Symbol,
+};
+use futures_util::future::join_all;
+use rust_decimal::Decimal;
+
+use crate::{API_USER_AGENT, BpxClient, BpxClientBuilder, Result, rate_limit::RateLimiter};
+
+/// One result per account label.
+pub type AccountResults<T> = BTreeMap<String, Result<T>>;
+
+/// A set of clients keyed by account label. See the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct BpxClientPool {
+    http_client: reqwest::Client,
+    base_url: Option<String>,
+    rate_limit: Option<(u32, Duration)>,
+    accounts: BTreeMap<String, BpxClient>,
+}
+
+impl BpxClientPool {
+    /// Creates an empty pool with its own HTTP client.
+    pub fn new() -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .user_agent(API_USER_AGENT)
+            .build()?;
+        Ok(Self::with_http_client(http_client))
+    }
+
+    /// Creates an empty pool sending requests through `http_client`.
+    pub fn with_http_client(http_client: reqwest::Client) -> Self {
+        Self {
+            http_client,
+            base_url: None,
+            rate_limit: None,
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the base URL of the accounts added with [`Self::add_account`].
+    /// Defaults to `BACKPACK_API_BASE_URL`.
+    pub fn with_base_url(mut self, base_url: impl ToString) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Gives each account added afterwards its own budget of `requests` per
+    /// `period`, unless its builder already has a rate limiter.
+    ///
+    /// # Panics
+    /// If `requests` is zero.
+    pub fn with_rate_limit(mut self, requests: u32, period: Duration) -> Self {
+        assert!(requests > 0, "requests must be positive");
+        self.rate_limit = Some((requests, period));
+        self
+    }
+
+    /// Adds an account signing with `secret`, replacing any account with the
+    /// same label.
+    pub fn add_account(&mut self, label: impl Into<String>, secret: impl ToString) -> Result<()> {
+        let mut builder = BpxClient::builder().secret(secret);
+        if let Some(base_url) = &self.base_url {
+            builder = builder.base_url(base_url);
+        }
+        self.insert(label, builder)
+    }
+
+    /// Builds a client from `builder` on the pool's HTTP client and adds it,
+    /// replacing any account with the same label.
+    pub fn insert(
+        &mut self,
+        label: impl Into<String>,
+        mut builder: BpxClientBuilder,
+    ) -> Result<()> {
+        if builder.rate_limiter.is_none()
+            && let Some((requests, period)) = self.rate_limit
+        {
+            builder = builder.rate_limiter(RateLimiter::new(requests, period));
+        }
+        let client = builder.http_client(self.http_client.clone()).build()?;
+        self.accounts.insert(label.into(), client);
+        Ok(())
+    }
+
+    /// Removes an account and returns its client.
+    pub fn remove(&mut self, label: &str) -> Option<BpxClient> {
+        self.accounts.remove(label)
+    }
+
+    /// Returns the client of an account.
+    pub fn get(&self, label: &str) -> Option<&BpxClient> {
+        self.accounts.get(label)
+    }
+
+    /// Returns the account labels in order.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.accounts.keys().map(String::as_str)
+    }
+
+    /// Returns the accounts in label order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &BpxClient)> {
+        self.accounts
+            .iter()
+            .map(|(label, client)| (label.as_str(), client))
+    }
+
+    /// Returns the number of accounts.
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Returns `true` if the pool has no accounts.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Runs `call` on every account concurrently.
+    pub async fn fan_out<'a, T, F, Fut>(&'a self, call: F) -> AccountResults<T>
+    where
+        F: Fn(&'a BpxClient) -> Fut,
+        Fut: Future<Output = Result<T>> + 'a,
+    {
+        let results = join_all(self.accounts.values().map(call)).await;
+        self.accounts.keys().cloned().zip(results).collect()
+    }
+
+    /// Fetches the balances of every account.
+    pub async fn get_balances(&self) -> AccountResults<HashMap<String, Balance>> {
+        self.fan_out(BpxClient::get_balances).await
+    }
+
+    /// Fetches the collateral of every account.
+    pub async fn get_collateral(&self) -> AccountResults<Collateral> {
+        self.fan_out(BpxClient::get_collateral).await
+    }
+
+    /// Fetches the open futures positions of every account.
+    pub async fn get_open_future_positions(&self) -> AccountResults<Vec<FuturePosition>> {
+        self.fan_out(BpxClient::get_open_future_positions).await
+    }
+
+    /// Fetches the open orders of every account, optionally on one symbol.
+    pub async fn get_open_orders(
+        &self,
+        symbol: Option<impl Into<Symbol>>,
+    ) -> AccountResults<Vec<Order>> {
+        let symbol = symbol.map(Into::into);
+        self.fan_out(|client| client.get_open_orders(symbol.clone()))
+            .await
+    }
+}
+
+/// Sums the balances of the accounts that answered, per asset.
+pub fn total_balances(
+    balances: &AccountResults<HashMap<String, Balance>>,
+) -> HashMap<String, Balance> {
+    let mut totals: HashMap<String, Balance> = HashMap::new();
+    for (asset, balance) in balances.values().flatten().flatten() {
+        let total = totals.entry(asset.clone()).or_insert(Balance {
+            available: Decimal::ZERO,
+            locked: Decimal::ZERO,
+            staked: Decimal::ZERO,
+        });
+        total.available += balance.available;
+        total.locked += balance.locked;
+        total.staked += balance.staked;
+    }
+    totals
+}
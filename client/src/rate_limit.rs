@@ -0,0 +1,83 @@
+//! Client-side request rate limiting.
+//!
+//! A [`RateLimiter`] attached with [`BpxClientBuilder::rate_limiter`] delays
+//! requests so that no more than `requests` are sent in any `period`, allowing
+//! bursts of up to `requests`. Clones share the same budget, so one limiter can
+//! be attached to several clients that share an API key or IP address.
+//!
+//! [`BpxClientBuilder::rate_limiter`]: crate::BpxClientBuilder::rate_limiter
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+/// Limits the rate of requests. See the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    interval: Duration,
+    burst: Duration,
+    /// Time at which the budget is fully used up by the requests admitted so far.
+    next: Arc<Mutex<Instant>>,
+}
+
+impl RateLimiter {
+    /// Allows `requests` requests per `period`.
+    ///
+    /// # Panics
+    /// If `requests` is zero.
+    pub fn new(requests: u32, period: Duration) -> Self {
+        assert!(requests > 0, "requests must be positive");
+        let interval = period / requests;
+        Self {
+            interval,
+            burst: period - interval,
+            next: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Waits until a request may be sent. Requests are admitted in the order they
+    /// call this method.
+    pub async fn acquire(&self) {
+        let admitted_at = {
+            let mut next = self.next.lock().expect("rate limiter poisoned");
+            let now = Instant::now();
+            let scheduled = (*next).max(now);
+            *next = scheduled + self.interval;
+            scheduled
+                .checked_sub(self.burst)
+                .map_or(now, |at| at.max(now))
+        };
+        tokio::time::sleep_until(admitted_at).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn allows_bursts_then_spaces_requests() {
+        let limiter = RateLimiter::new(5, Duration::from_secs(1));
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::from_millis(400));
+
+        // The budget refills while idle.
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let resumed = Instant::now();
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+        assert_eq!(resumed.elapsed(), Duration::ZERO);
+    }
+}
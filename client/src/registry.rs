@@ -0,0 +1,251 @@
+//! Cached market metadata.
+//!
+//! [`MarketRegistry`] loads markets, assets and securities once, indexes them by
+//! symbol and by base and quote asset, and reloads them when the cached
+//! [`MarketSnapshot`] is older than the configured time to live.
+//!
+//! ## Example
+//! ```no_run
+//! use std::time::Duration;
+//! use bpx_api_client::{BpxClient, registry::MarketRegistry};
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let registry = MarketRegistry::new(client).with_ttl(Duration::from_secs(60));
+//!
+//! let markets = registry.snapshot().await?;
+//! let sol = markets.market("SOL_USDC").expect("market is listed");
+//! println!("tick size {}, perp: {}", sol.filters.price.tick_size, sol.is_perp());
+//!
+//! for market in markets.markets_by_base("SOL") {
+//!     println!("{}", market.symbol);
+//! }
+//! println!("USDC withdrawals on {:?}", markets.withdrawal_blockchains("USDC"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use bpx_api_types::{
+    Blockchain,
+    markets::{Asset, Market, Security, Token},
+};
+use futures_util::future::try_join3;
+use rust_decimal::Decimal;
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::{BpxClient, Error, Result};
+
+/// Default time to live of cached market metadata.
+const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+/// Market metadata loaded at one point in time.
+#[derive(Debug, Clone)]
+pub struct MarketSnapshot {
+    markets: HashMap<String, Market>,
+    assets: HashMap<String, Asset>,
+    securities: HashMap<String, Security>,
+    by_base: HashMap<String, Vec<String>>,
+    by_quote: HashMap<String, Vec<String>>,
+    fetched_at: Instant,
+}
+
+impl MarketSnapshot {
+    /// Indexes the given markets, assets and securities.
+    pub fn new(markets: Vec<Market>, assets: Vec<Asset>, securities: Vec<Security>) -> Self {
+        let mut by_base: HashMap<String, Vec<String>> = HashMap::new();
+        let mut by_quote: HashMap<String, Vec<String>> = HashMap::new();
+        for market in &markets {
+            by_base
+                .entry(market.base_symbol.clone())
+                .or_default()
+                .push(market.symbol.clone());
+            by_quote
+                .entry(market.quote_symbol.clone())
+                .or_default()
+                .push(market.symbol.clone());
+        }
+        Self {
+            markets: markets
+                .into_iter()
+                .map(|market| (market.symbol.clone(), market))
+                .collect(),
+            assets: assets
+                .into_iter()
+                .map(|asset| (asset.symbol.clone(), asset))
+                .collect(),
+            securities: securities
+                .into_iter()
+                .map(|security| (security.asset.clone(), security))
+                .collect(),
+            by_base,
+            by_quote,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    /// Returns when this snapshot was loaded.
+    pub const fn fetched_at(&self) -> Instant {
+        self.fetched_at
+    }
+
+    /// Returns the market with the given symbol.
+    pub fn market(&self, symbol: &str) -> Option<&Market> {
+        self.markets.get(symbol)
+    }
+
+    /// Iterates over every market.
+    pub fn markets(&self) -> impl Iterator<Item = &Market> {
+        self.markets.values()
+    }
+
+    /// Iterates over the markets whose base asset is `base`.
+    pub fn markets_by_base(&self, base: &str) -> impl Iterator<Item = &Market> {
+        self.indexed(&self.by_base, base)
+    }
+
+    /// Iterates over the markets whose quote asset is `quote`.
+    pub fn markets_by_quote(&self, quote: &str) -> impl Iterator<Item = &Market> {
+        self.indexed(&self.by_quote, quote)
+    }
+
+    /// Returns the asset with the given symbol.
+    pub fn asset(&self, symbol: &str) -> Option<&Asset> {
+        self.assets.get(symbol)
+    }
+
+    /// Iterates over every asset.
+    pub fn assets(&self) -> impl Iterator<Item = &Asset> {
+        self.assets.values()
+    }
+
+    /// Returns the security backing the given asset, for tokenized RWAs.
+    pub fn security(&self, asset: &str) -> Option<&Security> {
+        self.securities.get(asset)
+    }
+
+    /// Returns the price tick size of a market.
+    pub fn tick_size(&self, symbol: &str) -> Option<Decimal> {
+        self.market(symbol)
+            .map(|market| market.filters.price.tick_size)
+    }
+
+    /// Returns the quantity step size of a market.
+    pub fn step_size(&self, symbol: &str) -> Option<Decimal> {
+        self.market(symbol)
+            .map(|market| market.filters.quantity.step_size)
+    }
+
+    /// Returns the blockchains an asset can currently be deposited on.
+    pub fn deposit_blockchains(&self, asset: &str) -> Vec<Blockchain> {
+        self.blockchains(asset, |token| token.deposit_enabled)
+    }
+
+    /// Returns the blockchains an asset can currently be withdrawn on.
+    pub fn withdrawal_blockchains(&self, asset: &str) -> Vec<Blockchain> {
+        self.blockchains(asset, |token| token.withdraw_enabled)
+    }
+
+    fn indexed<'a>(
+        &'a self,
+        index: &'a HashMap<String, Vec<String>>,
+        key: &str,
+    ) -> impl Iterator<Item = &'a Market> {
+        index
+            .get(key)
+            .into_iter()
+            .flatten()
+            .filter_map(|symbol| self.markets.get(symbol))
+    }
+
+    fn blockchains(&self, asset: &str, enabled: impl Fn(&Token) -> bool) -> Vec<Blockchain> {
+        self.asset(asset)
+            .into_iter()
+            .flat_map(|asset| &asset.tokens)
+            .filter(|token| enabled(token))
+            .map(|token| token.blockchain)
+            .collect()
+    }
+}
+
+/// Caches market metadata and reloads it on a time to live. See the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct MarketRegistry {
+    client: BpxClient,
+    ttl: Duration,
+    snapshot: RwLock<Option<Arc<MarketSnapshot>>>,
+    refresh: Mutex<()>,
+}
+
+impl MarketRegistry {
+    /// Creates an empty registry. Metadata is loaded on first use.
+    pub fn new(client: BpxClient) -> Self {
+        Self {
+            client,
+            ttl: DEFAULT_TTL,
+            snapshot: RwLock::new(None),
+            refresh: Mutex::new(()),
+        }
+    }
+
+    /// Sets how long loaded metadata is served before it is reloaded. Defaults to
+    /// five minutes.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Returns the cached snapshot, reloading it first if it is missing or expired.
+    pub async fn snapshot(&self) -> Result<Arc<MarketSnapshot>> {
+        if let Some(snapshot) = self.cached() {
+            return Ok(snapshot);
+        }
+        let _guard = self.refresh.lock().await;
+        // Another caller may have reloaded while we waited for the lock.
+        if let Some(snapshot) = self.cached() {
+            return Ok(snapshot);
+        }
+        self.load().await
+    }
+
+    /// Reloads the metadata regardless of its age.
+    pub async fn refresh(&self) -> Result<Arc<MarketSnapshot>> {
+        let _guard = self.refresh.lock().await;
+        self.load().await
+    }
+
+    /// Returns the market with the given symbol.
+    pub async fn market(&self, symbol: &str) -> Result<Market> {
+        self.snapshot()
+            .await?
+            .market(symbol)
+            .cloned()
+            .ok_or_else(|| Error::InvalidRequest(format!("unknown market {symbol}").into()))
+    }
+
+    fn cached(&self) -> Option<Arc<MarketSnapshot>> {
+        self.snapshot
+            .read()
+            .expect("market registry poisoned")
+            .as_ref()
+            .filter(|snapshot| snapshot.fetched_at.elapsed() < self.ttl)
+            .cloned()
+    }
+
+    async fn load(&self) -> Result<Arc<MarketSnapshot>> {
+        let (markets, assets, securities) = try_join3(
+            self.client.get_markets(),
+            self.client.get_assets(),
+            self.client.get_securities(),
+        )
+        .await?;
+        let snapshot = Arc::new(MarketSnapshot::new(markets, assets, securities));
+        *self.snapshot.write().expect("market registry poisoned") = Some(snapshot.clone());
+        Ok(snapshot)
+    }
+}
@@ -88,7 +88,8 @@ impl BpxClient {
 
     /// Fetches the subaccount's collateral information.
     pub async fn get_collateral(&self) -> Result<Collateral> {
-        let url = self.base_url.join(API_COLLATERAL)?;
+        let mut url = self.base_url.join(API_COLLATERAL)?;
+        self.apply_subaccount(&mut url);
         let res = self.get(url).await?;
         res.json().await.map_err(Into::into)
     }
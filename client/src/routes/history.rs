@@ -1,19 +1,94 @@
-use bpx_api_types::fill::{Fill, FillsHistoryParams};
+use bpx_api_types::{
+    fill::{Fill, FillsHistoryParams},
+    history::{
+        BorrowLendHistoryParams, BorrowLendMovement, FundingPayment, FundingPaymentsParams,
+        InterestHistoryParams, InterestPayment, Settlement, SettlementHistoryParams,
+    },
+};
+use reqwest::Url;
+use serde::{Serialize, de::DeserializeOwned};
 
 use crate::BpxClient;
 use crate::error::{Error, Result};
 
 #[doc(hidden)]
 pub const API_FILLS_HISTORY: &str = "/wapi/v1/history/fills";
+#[doc(hidden)]
+pub const API_FUNDING_HISTORY: &str = "/wapi/v1/history/funding";
+#[doc(hidden)]
+pub const API_INTEREST_HISTORY: &str = "/wapi/v1/history/interest";
+#[doc(hidden)]
+pub const API_SETTLEMENT_HISTORY: &str = "/wapi/v1/history/settlement";
+#[doc(hidden)]
+pub const API_BORROW_LEND_HISTORY: &str = "/wapi/v1/history/borrowLend";
 
 impl BpxClient {
     /// Fetches historical fills with optional filtering and pagination parameters.
     pub async fn get_historical_fills(&self, params: FillsHistoryParams) -> Result<Vec<Fill>> {
-        let query_string = serde_qs::to_string(&params)
-            .map_err(|e| Error::UrlParseError(e.to_string().into_boxed_str()))?;
-        let mut url = self.base_url.join(API_FILLS_HISTORY)?;
-        url.set_query(Some(&query_string));
+        self.get_history(API_FILLS_HISTORY, &params).await
+    }
+
+    /// Fetches the funding payments paid and received on perpetual positions.
+    pub async fn get_funding_payments(
+        &self,
+        params: FundingPaymentsParams,
+    ) -> Result<Vec<FundingPayment>> {
+        self.get_scoped_history(API_FUNDING_HISTORY, &params).await
+    }
+
+    /// Fetches the interest paid and received on borrow/lend positions and
+    /// unrealized PnL.
+    pub async fn get_interest_history(
+        &self,
+        params: InterestHistoryParams,
+    ) -> Result<Vec<InterestPayment>> {
+        self.get_scoped_history(API_INTEREST_HISTORY, &params).await
+    }
+
+    /// Fetches the settlements of PnL, fees and funding into the account balance.
+    pub async fn get_settlement_history(
+        &self,
+        params: SettlementHistoryParams,
+    ) -> Result<Vec<Settlement>> {
+        self.get_history(API_SETTLEMENT_HISTORY, &params).await
+    }
+
+    /// Fetches the borrows, repayments, lends and redemptions of borrow/lend
+    /// positions.
+    pub async fn get_borrow_lend_history(
+        &self,
+        params: BorrowLendHistoryParams,
+    ) -> Result<Vec<BorrowLendMovement>> {
+        self.get_history(API_BORROW_LEND_HISTORY, &params).await
+    }
+
+    async fn get_history<P: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &P,
+    ) -> Result<Vec<T>> {
+        let url = self.history_url(path, params)?;
+        let res = self.get(url).await?;
+        res.json().await.map_err(Into::into)
+    }
+
+    /// Like [`Self::get_history`], for endpoints that accept a `subaccountId`.
+    async fn get_scoped_history<P: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &P,
+    ) -> Result<Vec<T>> {
+        let mut url = self.history_url(path, params)?;
+        self.apply_subaccount(&mut url);
         let res = self.get(url).await?;
         res.json().await.map_err(Into::into)
     }
+
+    fn history_url<P: Serialize>(&self, path: &str, params: &P) -> Result<Url> {
+        let query_string = serde_qs::to_string(params)
+            .map_err(|e| Error::UrlParseError(e.to_string().into_boxed_str()))?;
+        let mut url = self.base_url.join(path)?;
+        url.set_query(Some(&query_string));
+        Ok(url)
+    }
 }
@@ -1,8 +1,13 @@
-use bpx_api_types::markets::{
-    Asset, FundingRate, Kline, MarkPrice, Market, OrderBookDepth, OrderBookDepthLimit, Security,
-    Ticker,
+use bpx_api_types::{
+    markets::{
+        Asset, FundingRate, Kline, KlineInterval, MarkPrice, Market, OpenInterest, OrderBookDepth,
+        OrderBookDepthLimit, Security, Ticker, TickerInterval,
+    },
+    symbol::Symbol,
 };
+use futures_util::future::try_join_all;
 
+use super::market_symbol;
 use crate::BpxClient;
 use crate::error::Result;
 
@@ -15,6 +20,7 @@ const API_KLINES: &str = "/api/v1/klines";
 const API_FUNDING: &str = "/api/v1/fundingRates";
 const API_MARK_PRICES: &str = "/api/v1/markPrices";
 const API_SECURITIES: &str = "/api/v1/securities";
+const API_OPEN_INTEREST: &str = "/api/v1/openInterest";
 
 impl BpxClient {
     /// Fetches available assets and their associated tokens.
@@ -32,35 +38,77 @@ impl BpxClient {
     }
 
     /// Retrieves mark price, index price and the funding rate for the current interval for all symbols, or the symbol specified.
-    pub async fn get_all_mark_prices(&self) -> Result<Vec<MarkPrice>> {
-        let url = self.base_url.join(API_MARK_PRICES)?;
+    pub async fn get_all_mark_prices(
+        &self,
+        symbol: Option<impl Into<Symbol>>,
+    ) -> Result<Vec<MarkPrice>> {
+        let mut url = self.base_url.join(API_MARK_PRICES)?;
+        if let Some(symbol) = symbol {
+            let symbol = market_symbol(symbol)?;
+            url.query_pairs_mut().append_pair("symbol", symbol.as_str());
+        }
         let res = self.get(url).await?;
         res.json().await.map_err(Into::into)
     }
 
-    /// Fetches the ticker information for a given symbol.
-    pub async fn get_ticker(&self, symbol: &str) -> Result<Ticker> {
+    /// Fetches the ticker information for a given symbol, over the last 24 hours
+    /// by default.
+    pub async fn get_ticker(
+        &self,
+        symbol: impl Into<Symbol>,
+        interval: Option<TickerInterval>,
+    ) -> Result<Ticker> {
+        let symbol = market_symbol(symbol)?;
         let mut url = self.base_url.join(API_TICKER)?;
-        url.query_pairs_mut().append_pair("symbol", symbol);
+        url.query_pairs_mut().append_pair("symbol", symbol.as_str());
+        if let Some(interval) = interval {
+            url.query_pairs_mut()
+                .append_pair("interval", interval.as_ref());
+        }
         let res = self.get(url).await?;
         res.json().await.map_err(Into::into)
     }
 
-    /// Fetches the ticker information for all symbols.
-    pub async fn get_tickers(&self) -> Result<Vec<Ticker>> {
-        let url = self.base_url.join(API_TICKERS)?;
+    /// Fetches the ticker information for all symbols, over the last 24 hours by
+    /// default.
+    pub async fn get_tickers(&self, interval: Option<TickerInterval>) -> Result<Vec<Ticker>> {
+        let mut url = self.base_url.join(API_TICKERS)?;
+        if let Some(interval) = interval {
+            url.query_pairs_mut()
+                .append_pair("interval", interval.as_ref());
+        }
         let res = self.get(url).await?;
         res.json().await.map_err(Into::into)
     }
 
+    /// Fetches the ticker information for the given symbols concurrently, one
+    /// request per symbol, in the order of `symbols`.
+    pub async fn get_tickers_for<S: Into<Symbol>>(
+        &self,
+        symbols: impl IntoIterator<Item = S>,
+        interval: Option<TickerInterval>,
+    ) -> Result<Vec<Ticker>> {
+        let symbols = symbols
+            .into_iter()
+            .map(market_symbol)
+            .collect::<Result<Vec<_>>>()?;
+        try_join_all(
+            symbols
+                .into_iter()
+                .map(|symbol| self.get_ticker(symbol, interval)),
+        )
+        .await
+    }
+
     /// Retrieves the order book depth for a given symbol.
     pub async fn get_order_book_depth(
         &self,
-        symbol: &str,
+        symbol: impl Into<Symbol>,
         limit: Option<OrderBookDepthLimit>,
     ) -> Result<OrderBookDepth> {
+        let symbol = market_symbol(symbol)?;
         let mut url = self.base_url.join(API_DEPTH)?;
-        url.query_pairs_mut().append_pair("symbol", symbol);
+        url.query_pairs_mut().append_pair("symbol", symbol.as_str());
         if let Some(limit) = limit {
             url.query_pairs_mut().append_pair("limit", limit.as_ref());
         }
@@ -68,10 +116,40 @@ impl BpxClient {
         res.json().await.map_err(Into::into)
     }
 
-    /// Funding interval rate history for futures.
-    pub async fn get_funding_interval_rates(&self, symbol: &str) -> Result<Vec<FundingRate>> {
+    /// Funding interval rate history for futures, most recent first. `limit`
+    /// defaults to 100 and may be up to 10000.
+    pub async fn get_funding_interval_rates(
+        &self,
+        symbol: impl Into<Symbol>,
+        limit: Option<u64>,
+        offset: Option<u64>,
+    ) -> Result<Vec<FundingRate>> {
+        let symbol = market_symbol(symbol)?;
         let mut url = self.base_url.join(API_FUNDING)?;
-        url.query_pairs_mut().append_pair("symbol", symbol);
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("symbol", symbol.as_str());
+            if let Some(limit) = limit {
+                query.append_pair("limit", &limit.to_string());
+            }
+            if let Some(offset) = offset {
+                query.append_pair("offset", &offset.to_string());
+            }
+        }
+        let res = self.get(url).await?;
+        res.json().await.map_err(Into::into)
+    }
+
+    /// Retrieves the open interest of all futures markets, or of the given symbol.
+    pub async fn get_open_interest(
+        &self,
+        symbol: Option<impl Into<Symbol>>,
+    ) -> Result<Vec<OpenInterest>> {
+        let mut url = self.base_url.join(API_OPEN_INTEREST)?;
+        if let Some(symbol) = symbol {
+            let symbol = market_symbol(symbol)?;
+            url.query_pairs_mut().append_pair("symbol", symbol.as_str());
+        }
         let res = self.get(url).await?;
         res.json().await.map_err(Into::into)
     }
@@ -86,16 +164,17 @@ impl BpxClient {
     /// Fetches historical K-line (candlestick) data for a given symbol and interval.
     pub async fn get_k_lines(
         &self,
-        symbol: &str,
-        kline_interval: &str,
+        symbol: impl Into<Symbol>,
+        kline_interval: KlineInterval,
         start_time: i64,
         end_time: Option<i64>,
     ) -> Result<Vec<Kline>> {
+        let symbol = market_symbol(symbol)?;
         let mut url = self.base_url.join(API_KLINES)?;
         {
             let mut query = url.query_pairs_mut();
-            query.append_pair("symbol", symbol);
-            query.append_pair("interval", kline_interval);
+            query.append_pair("symbol", symbol.as_str());
+            query.append_pair("interval", kline_interval.as_ref());
             query.append_pair("startTime", &start_time.to_string());
             if let Some(end_time) = end_time {
                 query.append_pair("endTime", &end_time.to_string());
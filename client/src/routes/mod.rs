@@ -6,6 +6,20 @@ pub mod history;
 pub mod markets;
 pub mod order;
 pub mod rfq;
+pub mod system;
 pub mod trades;
 pub mod user;
 pub mod vault;
+
+use bpx_api_types::symbol::Symbol;
+
+use crate::error::{Error, Result};
+
+/// Converts a market symbol and checks its format before any request is sent.
+pub(crate) fn market_symbol(symbol: impl Into<Symbol>) -> Result<Symbol> {
+    let symbol = symbol.into();
+    symbol
+        .validate()
+        .map_err(|err| Error::InvalidRequest(err.to_string().into()))?;
+    Ok(symbol)
+}
@@ -1,7 +1,11 @@
-use bpx_api_types::order::{
-    BatchOrderResponse, CancelOpenOrdersPayload, CancelOrderPayload, ExecuteOrderPayload, Order,
+use bpx_api_types::{
+    order::{
+        BatchOrderResponse, CancelOpenOrdersPayload, CancelOrderPayload, ExecuteOrderPayload, Order,
+    },
+    symbol::Symbol,
 };
 
+use super::market_symbol;
 use crate::BpxClient;
 use crate::error::{Error, Result};
 
@@ -14,14 +18,15 @@ impl BpxClient {
     /// Fetches a specific open order by symbol and either order ID or client ID.
     pub async fn get_open_order(
         &self,
-        symbol: &str,
+        symbol: impl Into<Symbol>,
         order_id: Option<&str>,
         client_id: Option<u32>,
     ) -> Result<Order> {
+        let symbol = market_symbol(symbol)?;
         let mut url = self.base_url.join(API_ORDER)?;
         {
             let mut query = url.query_pairs_mut();
-            query.append_pair("symbol", symbol);
+            query.append_pair("symbol", symbol.as_str());
             if let Some(order_id) = order_id {
                 query.append_pair("orderId", order_id);
             } else {
@@ -41,6 +46,7 @@ impl BpxClient {
 
     /// Executes a new order with the given payload.
     pub async fn execute_order(&self, payload: ExecuteOrderPayload) -> Result<Order> {
+        market_symbol(&payload.symbol)?;
         let endpoint = self.base_url.join(API_ORDER)?;
         let res = self.post(endpoint, payload).await?;
         res.json().await.map_err(Into::into)
@@ -49,13 +55,14 @@ impl BpxClient {
     /// Cancels a specific order by symbol and either order ID or client ID.
     pub async fn cancel_order(
         &self,
-        symbol: &str,
+        symbol: impl Into<Symbol>,
         order_id: Option<&str>,
         client_id: Option<u32>,
     ) -> Result<Order> {
+        let symbol = market_symbol(symbol)?;
         let url = self.base_url.join(API_ORDER)?;
         let payload = CancelOrderPayload {
-            symbol: symbol.to_string(),
+            symbol: symbol.into(),
             order_id: order_id.map(|s| s.to_string()),
             client_id,
         };
@@ -65,10 +72,11 @@ impl BpxClient {
     }
 
     /// Retrieves all open orders, optionally filtered by symbol.
-    pub async fn get_open_orders(&self, symbol: Option<&str>) -> Result<Vec<Order>> {
+    pub async fn get_open_orders(&self, symbol: Option<impl Into<Symbol>>) -> Result<Vec<Order>> {
         let mut url = self.base_url.join(API_ORDERS)?;
-        if let Some(s) = symbol {
-            url.query_pairs_mut().append_pair("symbol", s);
+        if let Some(symbol) = symbol {
+            let symbol = market_symbol(symbol)?;
+            url.query_pairs_mut().append_pair("symbol", symbol.as_str());
         }
         let res = self.get(url).await?;
         res.json().await.map_err(Into::into)
@@ -79,6 +87,9 @@ impl BpxClient {
         &self,
         payload: Vec<ExecuteOrderPayload>,
     ) -> Result<Vec<BatchOrderResponse>> {
+        for order in &payload {
+            market_symbol(&order.symbol)?;
+        }
         let endpoint = self.base_url.join(API_ORDERS)?;
         let res = self.post(endpoint, payload).await?;
         res.json().await.map_err(Into::into)
@@ -86,6 +97,7 @@ impl BpxClient {
 
     /// Cancels all open orders matching the specified payload.
     pub async fn cancel_open_orders(&self, payload: CancelOpenOrdersPayload) -> Result<Vec<Order>> {
+        market_symbol(&payload.symbol)?;
         let url = self.base_url.join(API_ORDERS)?;
         let res = self.delete(url, payload).await?;
         res.json().await.map_err(Into::into)
@@ -0,0 +1,66 @@
+use std::time::Instant;
+
+use bpx_api_types::system::{HealthReport, Status};
+use chrono::{DateTime, TimeDelta};
+
+use crate::error::{Error, Result};
+use crate::{BpxClient, now_millis};
+
+#[doc(hidden)]
+pub const API_STATUS: &str = "/api/v1/status";
+#[doc(hidden)]
+pub const API_PING: &str = "/api/v1/ping";
+#[doc(hidden)]
+pub const API_TIME: &str = "/api/v1/time";
+
+impl BpxClient {
+    /// Retrieves the operational status of the exchange.
+    pub async fn get_status(&self) -> Result<Status> {
+        let url = self.base_url.join(API_STATUS)?;
+        let res = self.get(url).await?;
+        res.json().await.map_err(Into::into)
+    }
+
+    /// Checks connectivity to the API.
+    pub async fn ping(&self) -> Result<()> {
+        let url = self.base_url.join(API_PING)?;
+        let res = self.get(url).await?;
+        let body = res.text().await?;
+        if body.trim() != "pong" {
+            return Err(Error::UnexpectedResponse(body.into()));
+        }
+        Ok(())
+    }
+
+    /// Retrieves the current server time in milliseconds.
+    pub async fn get_server_time(&self) -> Result<i64> {
+        let url = self.base_url.join(API_TIME)?;
+        let res = self.get(url).await?;
+        res.json().await.map_err(Into::into)
+    }
+
+    /// Retrieves the exchange status and measures the latency of, and the clock
+    /// offset to, the server.
+    pub async fn health_check(&self) -> Result<HealthReport> {
+        let status = self.get_status().await?;
+
+        let sent_at = now_millis() as i64;
+        let started = Instant::now();
+        let server_time = self.get_server_time().await?;
+        let latency = started.elapsed();
+
+        let local_time = sent_at + (latency.as_millis() / 2) as i64;
+        let clock_offset = TimeDelta::milliseconds(server_time - local_time);
+        let server_time = DateTime::from_timestamp_millis(server_time).ok_or_else(|| {
+            Error::UnexpectedResponse(format!("server time out of range: {server_time}").into())
+        })?;
+
+        Ok(HealthReport {
+            status: status.status,
+            message: status.message,
+            latency,
+            server_time,
+            clock_offset,
+        })
+    }
+}
@@ -1,5 +1,6 @@
-use bpx_api_types::trade::Trade;
+use bpx_api_types::{symbol::Symbol, trade::Trade};
 
+use super::market_symbol;
 use crate::BpxClient;
 use crate::error::Result;
 
@@ -8,11 +9,16 @@ const API_TRADES_HISTORY: &str = "/api/v1/trades/history";
 
 impl BpxClient {
     /// Fetches the most recent trades for a given symbol, with an optional limit.
-    pub async fn get_recent_trades(&self, symbol: &str, limit: Option<i16>) -> Result<Vec<Trade>> {
+    pub async fn get_recent_trades(
+        &self,
+        symbol: impl Into<Symbol>,
+        limit: Option<i16>,
+    ) -> Result<Vec<Trade>> {
+        let symbol = market_symbol(symbol)?;
         let mut url = self.base_url.join(API_TRADES)?;
         {
             let mut query = url.query_pairs_mut();
-            query.append_pair("symbol", symbol);
+            query.append_pair("symbol", symbol.as_str());
             if let Some(limit) = limit {
                 query.append_pair("limit", &limit.to_string());
             }
@@ -24,14 +30,15 @@ impl BpxClient {
     /// Fetches historical trades for a given symbol, with optional limit and offset.
     pub async fn get_historical_trades(
         &self,
-        symbol: &str,
+        symbol: impl Into<Symbol>,
         limit: Option<i64>,
         offset: Option<i64>,
     ) -> Result<Vec<Trade>> {
+        let symbol = market_symbol(symbol)?;
         let mut url = self.base_url.join(API_TRADES_HISTORY)?;
         {
             let mut query = url.query_pairs_mut();
-            query.append_pair("symbol", symbol);
+            query.append_pair("symbol", symbol.as_str());
             if let Some(limit) = limit {
                 query.append_pair("limit", &limit.to_string());
             }
@@ -68,6 +68,7 @@ impl BpxClient {
             .map_err(|e| crate::error::Error::UrlParseError(e.to_string().into_boxed_str()))?;
         let mut url = self.base_url.join(API_VAULT_MINTS_HISTORY)?;
         url.set_query(Some(&query_string));
+        self.apply_subaccount(&mut url);
         let res = self.get(url).await?;
         res.json().await.map_err(Into::into)
     }
@@ -81,6 +82,7 @@ impl BpxClient {
             .map_err(|e| crate::error::Error::UrlParseError(e.to_string().into_boxed_str()))?;
         let mut url = self.base_url.join(API_VAULT_REDEEMS_HISTORY)?;
         url.set_query(Some(&query_string));
+        self.apply_subaccount(&mut url);
         let res = self.get(url).await?;
         res.json().await.map_err(Into::into)
     }
@@ -0,0 +1,531 @@
+//! Deposit and withdrawal lifecycle watcher.
+//!
+//! A [`TransferWatcher`] polls `get_withdrawals` and `get_deposits` for the
+//! transfers it has been asked to watch and emits an event each time one of
+//! them changes status. A transfer stops being watched once it reaches a final
+//! status (`Confirmed` or `Void` for withdrawals, `Confirmed` for deposits) or
+//! once its timeout elapses.
+//!
+//! Withdrawals are matched by id or by the `client_id` sent with
+//! [`RequestWithdrawalPayload`](bpx_api_types::capital::RequestWithdrawalPayload),
+//! deposits by their on-chain transaction hash. Only the most recent
+//! [page](TransferWatcherConfig::with_page_size) of each history is polled.
+//!
+//! ## Example
+//! ```no_run
+//! use std::time::Duration;
+//! use bpx_api_client::{
+//!     BpxClient,
+//!     transfer_watcher::{TransferEvent, TransferWatcher, TransferWatcherConfig},
+//! };
+//! use bpx_api_types::capital::RequestWithdrawalPayload;
+//! use tokio::sync::mpsc;
+//!
+//! # async fn run(client: BpxClient, payload: RequestWithdrawalPayload) -> bpx_api_client::Result<()> {
+//! let config = TransferWatcherConfig::new()
+//!     .with_withdrawal_timeout(Duration::from_secs(3600));
+//! let (tx, mut rx) = mpsc::channel(16);
+//! let watcher = TransferWatcher::spawn(client.clone(), config, tx);
+//!
+//! let withdrawal = client.request_withdrawal(payload).await?;
+//! watcher.watch_withdrawal(&withdrawal);
+//! watcher.watch_deposit("5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnb");
+//!
+//! while let Some(event) = rx.recv().await {
+//!     match event {
+//!         TransferEvent::Withdrawal { withdrawal, .. } => println!("{:?}", withdrawal.status),
+//!         TransferEvent::Deposit { deposit, .. } => println!("{:?}", deposit.status),
+//!         other => println!("{other:?}"),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::Arc,
+    time::Duration,
+};
+
+use bpx_api_types::capital::{Deposit, DepositStatus, Withdrawal, WithdrawalStatus};
+use tokio::{
+    sync::mpsc::{self, Sender, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+    time::{Instant, MissedTickBehavior},
+};
+
+use crate::{BpxClient, Error};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const DEFAULT_WITHDRAWAL_TIMEOUT: Duration = Duration::from_secs(2 * 60 * 60);
+const DEFAULT_DEPOSIT_TIMEOUT: Duration = Duration::from_secs(2 * 60 * 60);
+const DEFAULT_PAGE_SIZE: i64 = 100;
+
+/// Configuration for a [`TransferWatcher`].
+#[derive(Debug, Clone)]
+pub struct TransferWatcherConfig {
+    poll_interval: Duration,
+    withdrawal_timeout: Duration,
+    deposit_timeout: Duration,
+    page_size: i64,
+}
+
+impl Default for TransferWatcherConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransferWatcherConfig {
+    /// Creates a configuration with the default interval and timeouts.
+    pub const fn new() -> Self {
+        Self {
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            withdrawal_timeout: DEFAULT_WITHDRAWAL_TIMEOUT,
+            deposit_timeout: DEFAULT_DEPOSIT_TIMEOUT,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// How often the histories are polled while something is watched.
+    /// Defaults to ten seconds.
+    pub const fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// How long a withdrawal may take to reach a final status, counted from
+    /// when it is watched. Defaults to two hours.
+    pub const fn with_withdrawal_timeout(mut self, timeout: Duration) -> Self {
+        self.withdrawal_timeout = timeout;
+        self
+    }
+
+    /// How long a deposit may take to be confirmed, counted from when it is
+    /// watched. Defaults to two hours.
+    pub const fn with_deposit_timeout(mut self, timeout: Duration) -> Self {
+        self.deposit_timeout = timeout;
+        self
+    }
+
+    /// Number of most recent withdrawals and deposits fetched per poll.
+    /// Defaults to 100.
+    pub fn with_page_size(mut self, page_size: i64) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+}
+
+/// Identifies a watched withdrawal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WithdrawalKey {
+    /// The exchange-assigned withdrawal id.
+    Id(i32),
+    /// The `client_id` sent with the withdrawal request.
+    ClientId(String),
+}
+
+impl WithdrawalKey {
+    /// Returns `true` if `withdrawal` is the one identified by this key.
+    pub fn matches(&self, withdrawal: &Withdrawal) -> bool {
+        match self {
+            Self::Id(id) => withdrawal.id == *id,
+            Self::ClientId(client_id) => withdrawal.client_id.as_deref() == Some(client_id),
+        }
+    }
+}
+
+impl From<&Withdrawal> for WithdrawalKey {
+    fn from(withdrawal: &Withdrawal) -> Self {
+        Self::Id(withdrawal.id)
+    }
+}
+
+impl fmt::Display for WithdrawalKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "id {id}"),
+            Self::ClientId(client_id) => write!(f, "client id {client_id}"),
+        }
+    }
+}
+
+/// Events emitted by a [`TransferWatcher`].
+#[derive(Debug, Clone)]
+pub enum TransferEvent {
+    /// A watched withdrawal was seen for the first time or changed status.
+    /// `previous` is `None` on the first sighting.
+    Withdrawal {
+        key: WithdrawalKey,
+        previous: Option<WithdrawalStatus>,
+        withdrawal: Withdrawal,
+    },
+    /// A watched deposit was seen for the first time or changed status.
+    /// `previous` is `None` on the first sighting.
+    Deposit {
+        transaction_hash: String,
+        previous: Option<DepositStatus>,
+        deposit: Deposit,
+    },
+    /// A withdrawal did not reach a final status in time and is no longer
+    /// watched. `last` is the last status seen, if any.
+    WithdrawalTimedOut {
+        key: WithdrawalKey,
+        last: Option<WithdrawalStatus>,
+    },
+    /// A deposit was not confirmed in time and is no longer watched. `last`
+    /// is the last status seen, if any.
+    DepositTimedOut {
+        transaction_hash: String,
+        last: Option<DepositStatus>,
+    },
+    /// A poll failed and will be retried on the next interval.
+    PollFailed(Arc<Error>),
+}
+
+#[derive(Debug)]
+struct Watched<S> {
+    deadline: Instant,
+    status: Option<S>,
+}
+
+/// The state behind a [`TransferWatcher`], usable on its own to drive the
+/// polling from an existing loop.
+#[derive(Debug, Default)]
+pub struct TransferTracker {
+    withdrawals: HashMap<WithdrawalKey, Watched<WithdrawalStatus>>,
+    deposits: HashMap<String, Watched<DepositStatus>>,
+}
+
+impl TransferTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watches a withdrawal until it reaches a final status or `deadline`
+    /// passes. Watching a key again resets its deadline.
+    pub fn watch_withdrawal(&mut self, key: WithdrawalKey, deadline: Instant) {
+        self.withdrawals
+            .entry(key)
+            .and_modify(|watched| watched.deadline = deadline)
+            .or_insert(Watched {
+                deadline,
+                status: None,
+            });
+    }
+
+    /// Watches a deposit until it is confirmed or `deadline` passes. Watching
+    /// a hash again resets its deadline.
+    pub fn watch_deposit(&mut self, transaction_hash: String, deadline: Instant) {
+        self.deposits
+            .entry(transaction_hash)
+            .and_modify(|watched| watched.deadline = deadline)
+            .or_insert(Watched {
+                deadline,
+                status: None,
+            });
+    }
+
+    /// Returns `true` while at least one withdrawal is watched.
+    pub fn has_withdrawals(&self) -> bool {
+        !self.withdrawals.is_empty()
+    }
+
+    /// Returns `true` while at least one deposit is watched.
+    pub fn has_deposits(&self) -> bool {
+        !self.deposits.is_empty()
+    }
+
+    /// Applies a page of withdrawals and returns the status transitions.
+    /// Withdrawals that reached a final status stop being watched.
+    pub fn apply_withdrawals(&mut self, withdrawals: &[Withdrawal]) -> Vec<TransferEvent> {
+        let mut events = Vec::new();
+        self.withdrawals.retain(|key, watched| {
+            let Some(withdrawal) = withdrawals.iter().find(|w| key.matches(w)) else {
+                return true;
+            };
+            if watched.status != Some(withdrawal.status) {
+                events.push(TransferEvent::Withdrawal {
+                    key: key.clone(),
+                    previous: watched.status.replace(withdrawal.status),
+                    withdrawal: withdrawal.clone(),
+                });
+            }
+            !withdrawal.status.is_final()
+        });
+        events
+    }
+
+    /// Applies a page of deposits and returns the status transitions.
+    /// Confirmed deposits stop being watched.
+    pub fn apply_deposits(&mut self, deposits: &[Deposit]) -> Vec<TransferEvent> {
+        let mut events = Vec::new();
+        self.deposits.retain(|transaction_hash, watched| {
+            let Some(deposit) = deposits
+                .iter()
+                .find(|d| d.transaction_hash.as_ref() == Some(transaction_hash))
+            else {
+                return true;
+            };
+            if watched.status != Some(deposit.status) {
+                events.push(TransferEvent::Deposit {
+                    transaction_hash: transaction_hash.clone(),
+                    previous: watched.status.replace(deposit.status),
+                    deposit: deposit.clone(),
+                });
+            }
+            !deposit.status.is_final()
+        });
+        events
+    }
+
+    /// Stops watching every transfer whose deadline has passed at `now` and
+    /// returns a timeout event for each.
+    pub fn expire(&mut self, now: Instant) -> Vec<TransferEvent> {
+        let mut events = Vec::new();
+        self.withdrawals.retain(|key, watched| {
+            let alive = watched.deadline > now;
+            if !alive {
+                events.push(TransferEvent::WithdrawalTimedOut {
+                    key: key.clone(),
+                    last: watched.status,
+                });
+            }
+            alive
+        });
+        self.deposits.retain(|transaction_hash, watched| {
+            let alive = watched.deadline > now;
+            if !alive {
+                events.push(TransferEvent::DepositTimedOut {
+                    transaction_hash: transaction_hash.clone(),
+                    last: watched.status,
+                });
+            }
+            alive
+        });
+        events
+    }
+}
+
+#[derive(Debug)]
+enum Command {
+    Withdrawal(WithdrawalKey),
+    Deposit(String),
+    Shutdown,
+}
+
+/// A handle used to add transfers to a running [`TransferWatcher`].
+///
+/// Dropping every handle stops the watcher.
+#[derive(Debug, Clone)]
+pub struct TransferWatcherHandle {
+    commands: UnboundedSender<Command>,
+}
+
+impl TransferWatcherHandle {
+    /// Watches a withdrawal returned by `request_withdrawal`.
+    pub fn watch_withdrawal(&self, withdrawal: &Withdrawal) {
+        self.watch_withdrawal_key(withdrawal.into());
+    }
+
+    /// Watches a withdrawal by id or `client_id`.
+    pub fn watch_withdrawal_key(&self, key: WithdrawalKey) {
+        let _ = self.commands.send(Command::Withdrawal(key));
+    }
+
+    /// Watches an incoming deposit by its on-chain transaction hash.
+    pub fn watch_deposit(&self, transaction_hash: impl Into<String>) {
+        let _ = self
+            .commands
+            .send(Command::Deposit(transaction_hash.into()));
+    }
+
+    /// Stops the watcher.
+    pub fn shutdown(&self) {
+        let _ = self.commands.send(Command::Shutdown);
+    }
+}
+
+/// A spawned transfer watcher. See the [module documentation](self).
+#[derive(Debug)]
+pub struct TransferWatcher {
+    handle: TransferWatcherHandle,
+    task: JoinHandle<()>,
+}
+
+impl TransferWatcher {
+    /// Spawns the watcher on the current tokio runtime. Nothing is polled
+    /// until a transfer is watched.
+    ///
+    /// Polling never waits on `tx`. Status transitions and timeouts that do not
+    /// fit in the channel are queued in order until the consumer catches up;
+    /// [`TransferEvent::PollFailed`] events are dropped when the channel is full.
+    pub fn spawn(
+        client: BpxClient,
+        config: TransferWatcherConfig,
+        tx: Sender<TransferEvent>,
+    ) -> Self {
+        let (commands, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run(client, config, rx, tx));
+        Self {
+            handle: TransferWatcherHandle { commands },
+            task,
+        }
+    }
+
+    /// Returns a cloneable handle to the watcher.
+    pub fn handle(&self) -> TransferWatcherHandle {
+        self.handle.clone()
+    }
+
+    /// Waits for the watcher to stop, after a shutdown or once every handle is dropped.
+    pub async fn join(self) {
+        let Self { handle, task } = self;
+        drop(handle);
+        let _ = task.await;
+    }
+}
+
+impl std::ops::Deref for TransferWatcher {
+    type Target = TransferWatcherHandle;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+async fn run(
+    client: BpxClient,
+    config: TransferWatcherConfig,
+    mut commands: UnboundedReceiver<Command>,
+    tx: Sender<TransferEvent>,
+) {
+    let mut tracker = TransferTracker::new();
+    // Events waiting for room in the channel, so a slow consumer never stalls polling.
+    let mut pending = VecDeque::new();
+
+    let mut ticker = tokio::time::interval(config.poll_interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(Command::Withdrawal(key)) => {
+                    tracker.watch_withdrawal(key, Instant::now() + config.withdrawal_timeout);
+                }
+                Some(Command::Deposit(transaction_hash)) => {
+                    tracker.watch_deposit(transaction_hash, Instant::now() + config.deposit_timeout);
+                }
+                Some(Command::Shutdown) | None => return,
+            },
+            _ = ticker.tick() => {
+                let mut events = Vec::new();
+                if tracker.has_withdrawals() {
+                    match client.get_withdrawals(Some(config.page_size), None).await {
+                        Ok(withdrawals) => events.extend(tracker.apply_withdrawals(&withdrawals)),
+                        Err(error) => {
+                            tracing::warn!(%error, "Polling withdrawals failed");
+                            events.push(TransferEvent::PollFailed(Arc::new(error)));
+                        }
+                    }
+                }
+                if tracker.has_deposits() {
+                    match client.get_deposits(Some(config.page_size), None).await {
+                        Ok(deposits) => events.extend(tracker.apply_deposits(&deposits)),
+                        Err(error) => {
+                            tracing::warn!(%error, "Polling deposits failed");
+                            events.push(TransferEvent::PollFailed(Arc::new(error)));
+                        }
+                    }
+                }
+                events.extend(tracker.expire(Instant::now()));
+                for event in events {
+                    let channel_full = pending.len() >= tx.capacity();
+                    if matches!(event, TransferEvent::PollFailed(_)) && channel_full {
+                        tracing::debug!("Event channel full, dropping poll failure");
+                        continue;
+                    }
+                    pending.push_back(event);
+                }
+            }
+            permit = tx.reserve(), if !pending.is_empty() => match permit {
+                Ok(permit) => permit.send(pending.pop_front().expect("pending is not empty")),
+                Err(_) => return,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn withdrawal(id: i32, client_id: Option<&str>, status: &str) -> Withdrawal {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "blockchain": "Solana",
+            "clientId": client_id,
+            "quantity": "1",
+            "fee": "0.01",
+            "symbol": "SOL",
+            "status": status,
+            "toAddress": "addr",
+            "createdAt": "2025-01-01T00:00:00",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn emits_transitions_until_final_status() {
+        let mut tracker = TransferTracker::new();
+        let deadline = Instant::now() + Duration::from_secs(60);
+        tracker.watch_withdrawal(WithdrawalKey::ClientId("treasury-1".into()), deadline);
+
+        assert!(
+            tracker
+                .apply_withdrawals(&[withdrawal(1, None, "pending")])
+                .is_empty()
+        );
+
+        let events = tracker.apply_withdrawals(&[withdrawal(2, Some("treasury-1"), "pending")]);
+        assert!(matches!(
+            events.as_slice(),
+            [TransferEvent::Withdrawal { previous: None, withdrawal, .. }] if withdrawal.id == 2
+        ));
+        // Unchanged status emits nothing.
+        assert!(
+            tracker
+                .apply_withdrawals(&[withdrawal(2, Some("treasury-1"), "pending")])
+                .is_empty()
+        );
+
+        let events = tracker.apply_withdrawals(&[withdrawal(2, Some("treasury-1"), "void")]);
+        assert!(matches!(
+            events.as_slice(),
+            [TransferEvent::Withdrawal {
+                previous: Some(WithdrawalStatus::Pending),
+                withdrawal,
+                ..
+            }] if withdrawal.status == WithdrawalStatus::Void
+        ));
+        assert!(!tracker.has_withdrawals());
+    }
+
+    #[test]
+    fn expires_at_deadline() {
+        let mut tracker = TransferTracker::new();
+        let now = Instant::now();
+        tracker.watch_deposit("hash".into(), now + Duration::from_secs(1));
+        assert!(tracker.expire(now).is_empty());
+
+        let events = tracker.expire(now + Duration::from_secs(1));
+        assert!(matches!(
+            events.as_slice(),
+            [TransferEvent::DepositTimedOut { transaction_hash, last: None }] if transaction_hash == "hash"
+        ));
+        assert!(!tracker.has_deposits());
+    }
+}
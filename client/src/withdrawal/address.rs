@@ -0,0 +1,280 @@
+//! Per-chain address format validation.
+//!
+//! Only the format is checked: the encoding, the length and, where it needs
+//! no hash function, the checksum. Bech32 and bech32m checksums are verified;
+//! base58check checksums and EIP-55 mixed-case checksums are not.
+
+use bpx_api_types::Blockchain;
+
+use crate::{Error, Result};
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// The address encoding used by a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressFormat {
+    /// A base58 encoded 32-byte public key, as used by Solana and SVM chains.
+    Solana,
+    /// `0x` followed by 40 hex digits.
+    Evm,
+    /// A base58 P2PKH or P2SH address, or a bech32 segwit address.
+    Bitcoin,
+    /// `0x` followed by 64 hex digits, as used by Sui and Aptos.
+    Move,
+    /// A base58 address starting with `T`.
+    Tron,
+    /// Any non-empty address without whitespace.
+    Other,
+}
+
+impl AddressFormat {
+    /// Returns the address format of `blockchain`.
+    pub const fn of(blockchain: Blockchain) -> Self {
+        match blockchain {
+            Blockchain::Solana | Blockchain::Eclipse | Blockchain::Fogo => Self::Solana,
+            Blockchain::Ethereum
+            | Blockchain::Polygon
+            | Blockchain::Arbitrum
+            | Blockchain::Base
+            | Blockchain::Optimism
+            | Blockchain::Bsc
+            | Blockchain::Berachain
+            | Blockchain::HyperEVM
+            | Blockchain::Hyperliquid
+            | Blockchain::Plasma
+            | Blockchain::Monad
+            | Blockchain::Story
+            | Blockchain::Stable
+            | Blockchain::ZeroG => Self::Evm,
+            Blockchain::Bitcoin => Self::Bitcoin,
+            Blockchain::Sui | Blockchain::Aptos => Self::Move,
+            Blockchain::Tron => Self::Tron,
+            _ => Self::Other,
+        }
+    }
+
+    /// Returns `true` if `address` is well formed in this format.
+    pub fn is_valid(self, address: &str) -> bool {
+        match self {
+            Self::Solana => base58_decode(address).is_some_and(|bytes| bytes.len() == 32),
+            Self::Evm => is_hex_address(address, 40),
+            Self::Bitcoin => is_bitcoin_address(address),
+            Self::Move => is_hex_address(address, 64),
+            Self::Tron => {
+                address.starts_with('T')
+                    && base58_decode(address).is_some_and(|bytes| bytes.len() == 25)
+            }
+            Self::Other => {
+                !address.is_empty() && !address.chars().any(|c| c.is_whitespace() || c.is_control())
+            }
+        }
+    }
+
+    /// Returns `true` if two addresses in this format refer to the same
+    /// account. Hex and bech32 addresses compare case-insensitively.
+    pub fn same_address(self, a: &str, b: &str) -> bool {
+        match self {
+            Self::Evm | Self::Move => a.eq_ignore_ascii_case(b),
+            Self::Bitcoin if is_bech32_prefix(a) => a.eq_ignore_ascii_case(b),
+            _ => a == b,
+        }
+    }
+}
+
+/// Checks that `address` is well formed on `blockchain`.
+pub fn validate_address(blockchain: Blockchain, address: &str) -> Result<()> {
+    if AddressFormat::of(blockchain).is_valid(address) {
+        Ok(())
+    } else {
+        Err(Error::InvalidRequest(
+            format!("{address:?} is not a valid {blockchain} address").into(),
+        ))
+    }
+}
+
+fn is_hex_address(address: &str, digits: usize) -> bool {
+    address
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == digits && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+fn is_bitcoin_address(address: &str) -> bool {
+    if is_bech32_prefix(address) {
+        return is_segwit_address(address);
+    }
+    // P2PKH addresses start with 1 and P2SH addresses with 3; both decode to a
+    // version byte, a 20-byte hash and a 4-byte checksum.
+    matches!(address.as_bytes().first(), Some(b'1' | b'3'))
+        && base58_decode(address).is_some_and(|bytes| bytes.len() == 25)
+}
+
+fn is_bech32_prefix(address: &str) -> bool {
+    address
+        .get(..3)
+        .is_some_and(|hrp| hrp.eq_ignore_ascii_case("bc1"))
+}
+
+/// Decodes a base58 string, keeping leading zero bytes.
+fn base58_decode(input: &str) -> Option<Vec<u8>> {
+    if input.is_empty() {
+        return None;
+    }
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in input.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = input.bytes().take_while(|&c| c == b'1').count();
+    let mut decoded = vec![0; zeros];
+    decoded.extend(bytes);
+    Some(decoded)
+}
+
+/// Validates a segwit address: bech32 for version 0, bech32m for later
+/// versions, with a 20 or 32-byte program for version 0.
+fn is_segwit_address(address: &str) -> bool {
+    let lower = address.to_ascii_lowercase();
+    if lower != address && address.to_ascii_uppercase() != address {
+        return false;
+    }
+    if !(14..=74).contains(&lower.len()) {
+        return false;
+    }
+    let Some((hrp, data)) = lower.rsplit_once('1') else {
+        return false;
+    };
+    if hrp != "bc" || data.len() < 7 {
+        return false;
+    }
+    let Some(values) = data
+        .bytes()
+        .map(|c| BECH32_CHARSET.iter().position(|&a| a == c).map(|v| v as u8))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return false;
+    };
+
+    let mut checked = hrp.bytes().map(|c| c >> 5).collect::<Vec<_>>();
+    checked.push(0);
+    checked.extend(hrp.bytes().map(|c| c & 31));
+    checked.extend(&values);
+    let version = values[0];
+    let expected = if version == 0 { 1 } else { 0x2bc8_30a3 };
+    if bech32_polymod(&checked) != expected || version > 16 {
+        return false;
+    }
+
+    let program_bits = (values.len() - 7) * 5;
+    let program_len = program_bits / 8;
+    if program_bits % 8 > 4 || !(2..=40).contains(&program_len) {
+        return false;
+    }
+    version != 0 || program_len == 20 || program_len == 32
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut checksum = 1u32;
+    for &value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ff_ffff) << 5) ^ u32::from(value);
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_addresses_per_chain() {
+        let valid = [
+            (Blockchain::Solana, "11111111111111111111111111111111"),
+            (
+                Blockchain::Solana,
+                "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            ),
+            (
+                Blockchain::Arbitrum,
+                "0x52908400098527886E0F7030069857D2E4169EE7",
+            ),
+            (Blockchain::Bitcoin, "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2"),
+            (Blockchain::Bitcoin, "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy"),
+            (
+                Blockchain::Bitcoin,
+                "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            ),
+            (
+                Blockchain::Bitcoin,
+                "bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297",
+            ),
+            (Blockchain::Tron, "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t"),
+            (
+                Blockchain::Sui,
+                "0x0000000000000000000000000000000000000000000000000000000000000002",
+            ),
+            (Blockchain::XRP, "rEb8TK3gBgk5auZkwc6sHnwrGVJH8DuaLh"),
+        ];
+        for (blockchain, address) in valid {
+            assert!(
+                validate_address(blockchain, address).is_ok(),
+                "{blockchain} {address}"
+            );
+        }
+
+        let invalid = [
+            // Too short to be a 32-byte key.
+            (Blockchain::Solana, "1111111111111111111111111111111"),
+            // `0` is not in the base58 alphabet.
+            (
+                Blockchain::Solana,
+                "0PjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            ),
+            (
+                Blockchain::Ethereum,
+                "0x52908400098527886E0F7030069857D2E4169EE",
+            ),
+            (
+                Blockchain::Ethereum,
+                "52908400098527886E0F7030069857D2E4169EE7",
+            ),
+            // Last character changed, breaking the bech32 checksum.
+            (
+                Blockchain::Bitcoin,
+                "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdp",
+            ),
+            (
+                Blockchain::Bitcoin,
+                "0x52908400098527886E0F7030069857D2E4169EE7",
+            ),
+            (Blockchain::Dogecoin, "D7Y55 r6KWQ"),
+            (Blockchain::Litecoin, ""),
+        ];
+        for (blockchain, address) in invalid {
+            assert!(
+                validate_address(blockchain, address).is_err(),
+                "{blockchain} {address}"
+            );
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Checked withdrawals.
+//!
+//! [`WithdrawalWorkflow::submit`] runs a set of pre-flight checks before
+//! calling `request_withdrawal`:
+//!
+//! - the destination address is well formed for the chosen [`Blockchain`] and
+//!   present in a local [`AddressAllowlist`],
+//! - the asset can be withdrawn on that chain, and the quantity lies between
+//!   the chain's minimum and maximum withdrawal,
+//! - the quantity does not exceed `get_account_max_withdrawal`.
+//!
+//! If the workflow is configured with [`WithdrawalWorkflow::with_two_factor`],
+//! a two-factor token is requested and attached to the payload when it does
+//! not carry one already.
+//!
+//! ## Example
+//! ```no_run
+//! use bpx_api_client::{
+//!     BpxClient,
+//!     withdrawal::{AddressAllowlist, WithdrawalWorkflow},
+//! };
+//! use bpx_api_types::{Blockchain, capital::RequestWithdrawalPayload};
+//! use rust_decimal::Decimal;
+//!
+//! # async fn run(client: BpxClient) -> bpx_api_client::Result<()> {
+//! let allowlist = AddressAllowlist::new()
+//!     .with_address(Blockchain::Solana, "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")?;
+//! let workflow = WithdrawalWorkflow::new(client, allowlist);
+//!
+//! let withdrawal = workflow
+//!     .submit(RequestWithdrawalPayload {
+//!         address: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(),
+//!         blockchain: Blockchain::Solana,
+//!         quantity: Decimal::ONE_HUNDRED,
+//!         symbol: "USDC".to_string(),
+//!         ..Default::default()
+//!     })
+//!     .await?;
+//! println!("submitted withdrawal {}", withdrawal.id);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, path::Path};
+
+use bpx_api_types::{
+    Blockchain,
+    capital::{RequestWithdrawalPayload, Withdrawal},
+    markets::Token,
+    user::RequestTwoFactorPayload,
+};
+use rust_decimal::Decimal;
+
+use crate::{BpxClient, Error, Result};
+
+pub mod address;
+
+use address::{AddressFormat, validate_address};
+
+/// The destination addresses withdrawals may be sent to, per chain.
+#[derive(Debug, Clone, Default)]
+pub struct AddressAllowlist {
+    addresses: HashMap<Blockchain, Vec<String>>,
+}
+
+impl AddressAllowlist {
+    /// Creates an empty allowlist, which rejects every address.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads an allowlist from a JSON file mapping chains to addresses, e.g.
+    /// `{"Solana": ["EPjF..."], "Ethereum": ["0x5290..."]}`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let entries: HashMap<Blockchain, Vec<String>> =
+            serde_json::from_slice(&std::fs::read(path)?)?;
+        let mut allowlist = Self::new();
+        for (blockchain, addresses) in entries {
+            for address in addresses {
+                allowlist.insert(blockchain, address)?;
+            }
+        }
+        Ok(allowlist)
+    }
+
+    /// Allows `address` on `blockchain`. Fails if the address is not well
+    /// formed for that chain.
+    pub fn insert(&mut self, blockchain: Blockchain, address: impl Into<String>) -> Result<()> {
+        let address = address.into();
+        validate_address(blockchain, &address)?;
+        if !self.contains(blockchain, &address) {
+            self.addresses.entry(blockchain).or_default().push(address);
+        }
+        Ok(())
+    }
+
+    /// Allows `address` on `blockchain`.
+    pub fn with_address(
+        mut self,
+        blockchain: Blockchain,
+        address: impl Into<String>,
+    ) -> Result<Self> {
+        self.insert(blockchain, address)?;
+        Ok(self)
+    }
+
+    /// Returns `true` if `address` is allowed on `blockchain`.
+    pub fn contains(&self, blockchain: Blockchain, address: &str) -> bool {
+        let format = AddressFormat::of(blockchain);
+        self.addresses.get(&blockchain).is_some_and(|addresses| {
+            addresses
+                .iter()
+                .any(|allowed| format.same_address(allowed, address))
+        })
+    }
+}
+
+/// The outcome of the pre-flight checks.
+#[derive(Debug, Clone)]
+pub struct WithdrawalPreflight {
+    /// The asset's entry for the chosen chain.
+    pub token: Token,
+    /// The largest quantity the account can currently withdraw.
+    pub max_withdrawal_quantity: Decimal,
+}
+
+impl WithdrawalPreflight {
+    /// The fee charged by the exchange for a withdrawal on this chain.
+    pub fn fee(&self) -> Decimal {
+        self.token.withdrawal_fee
+    }
+}
+
+/// Submits withdrawals after checking them. See the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct WithdrawalWorkflow {
+    client: BpxClient,
+    allowlist: AddressAllowlist,
+    two_factor: Option<RequestTwoFactorPayload>,
+}
+
+impl WithdrawalWorkflow {
+    /// Creates a workflow sending withdrawals only to addresses in `allowlist`.
+    pub fn new(client: BpxClient, allowlist: AddressAllowlist) -> Self {
+        Self {
+            client,
+            allowlist,
+            two_factor: None,
+        }
+    }
+
+    /// Requests a two-factor token with `payload` before each withdrawal that
+    /// does not already carry one.
+    pub fn with_two_factor(mut self, payload: RequestTwoFactorPayload) -> Self {
+        self.two_factor = Some(payload);
+        self
+    }
+
+    /// Returns the allowlist.
+    pub fn allowlist(&self) -> &AddressAllowlist {
+        &self.allowlist
+    }
+
+    /// Runs every check without submitting the withdrawal.
+    pub async fn preflight(
+        &self,
+        payload: &RequestWithdrawalPayload,
+    ) -> Result<WithdrawalPreflight> {
+        let RequestWithdrawalPayload {
+            address,
+            blockchain,
+            quantity,
+            symbol,
+            ..
+        } = payload;
+
+        validate_address(*blockchain, address)?;
+        if !self.allowlist.contains(*blockchain, address) {
+            return Err(Error::InvalidRequest(
+                format!("{address} is not in the {blockchain} allowlist").into(),
+            ));
+        }
+        if *quantity <= Decimal::ZERO {
+            return Err(Error::InvalidRequest(
+                format!("withdrawal quantity {quantity} must be positive").into(),
+            ));
+        }
+
+        let token = self
+            .client
+            .get_assets()
+            .await?
+            .into_iter()
+            .find(|asset| asset.symbol == *symbol)
+            .ok_or_else(|| Error::InvalidRequest(format!("unknown asset {symbol}").into()))?
+            .tokens
+            .into_iter()
+            .find(|token| token.blockchain == *blockchain)
+            .ok_or_else(|| {
+                Error::InvalidRequest(format!("{symbol} is not available on {blockchain}").into())
+            })?;
+        check_token(&token, symbol, *quantity)?;
+
+        let max_withdrawal_quantity = self
+            .client
+            .get_account_max_withdrawal(symbol, payload.auto_borrow, payload.auto_lend_redeem)
+            .await?
+            .max_withdrawal_quantity;
+        if *quantity > max_withdrawal_quantity {
+            return Err(Error::InvalidRequest(
+                format!(
+                    "withdrawal of {quantity} {symbol} exceeds the available {max_withdrawal_quantity}"
+                )
+                .into(),
+            ));
+        }
+
+        Ok(WithdrawalPreflight {
+            token,
+            max_withdrawal_quantity,
+        })
+    }
+
+    /// Runs the pre-flight checks, attaches a two-factor token if configured
+    /// and submits the withdrawal.
+    pub async fn submit(&self, mut payload: RequestWithdrawalPayload) -> Result<Withdrawal> {
+        self.preflight(&payload).await?;
+        if let Some(two_factor) = &self.two_factor
+            && payload.two_factor_token.is_none()
+        {
+            let response = self.client.request_two_factor(two_factor.clone()).await?;
+            payload.two_factor_token = Some(response.signature);
+        }
+        self.client.request_withdrawal(payload).await
+    }
+}
+
+/// Checks a quantity against the withdrawal limits of a token.
+fn check_token(token: &Token, symbol: &str, quantity: Decimal) -> Result<()> {
+    let blockchain = token.blockchain;
+    if !token.withdraw_enabled {
+        return Err(Error::InvalidRequest(
+            format!("withdrawals of {symbol} on {blockchain} are disabled").into(),
+        ));
+    }
+    if quantity < token.minimum_withdrawal {
+        return Err(Error::InvalidRequest(
+            format!(
+                "withdrawal of {quantity} {symbol} is below the {blockchain} minimum of {}",
+                token.minimum_withdrawal
+            )
+            .into(),
+        ));
+    }
+    if let Some(maximum) = token.maximum_withdrawal
+        && quantity > maximum
+    {
+        return Err(Error::InvalidRequest(
+            format!(
+                "withdrawal of {quantity} {symbol} is above the {blockchain} maximum of {maximum}"
+            )
+            .into(),
+        ));
+    }
+    if quantity <= token.withdrawal_fee {
+        return Err(Error::InvalidRequest(
+            format!(
+                "withdrawal of {quantity} {symbol} does not cover the {} fee",
+                token.withdrawal_fee
+            )
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn allowlist_matches_per_chain() {
+        let allowlist = AddressAllowlist::new()
+            .with_address(
+                Blockchain::Ethereum,
+                "0x52908400098527886E0F7030069857D2E4169EE7",
+            )
+            .unwrap();
+        assert!(allowlist.contains(
+            Blockchain::Ethereum,
+            "0x52908400098527886e0f7030069857d2e4169ee7"
+        ));
+        assert!(!allowlist.contains(
+            Blockchain::Base,
+            "0x52908400098527886E0F7030069857D2E4169EE7"
+        ));
+        assert!(
+            AddressAllowlist::new()
+                .with_address(
+                    Blockchain::Solana,
+                    "0x52908400098527886E0F7030069857D2E4169EE7"
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn checks_token_limits() {
+        let token: Token = serde_json::from_value(serde_json::json!({
+            "blockchain": "Solana",
+            "contractAddress": "",
+            "depositEnabled": true,
+            "displayName": "USDC",
+            "minimumDeposit": "1",
+            "withdrawEnabled": true,
+            "minimumWithdrawal": "5",
+            "maximumWithdrawal": "1000",
+            "withdrawalFee": "1",
+        }))
+        .unwrap();
+        assert!(check_token(&token, "USDC", dec!(10)).is_ok());
+        assert!(check_token(&token, "USDC", dec!(4)).is_err());
+        assert!(check_token(&token, "USDC", dec!(1001)).is_err());
+
+        let disabled = Token {
+            withdraw_enabled: false,
+            ..token
+        };
+        assert!(check_token(&disabled, "USDC", dec!(10)).is_err());
+    }
+}
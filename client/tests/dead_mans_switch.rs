@@ -0,0 +1,278 @@
+#![cfg(feature = "runtime")]
+
+mod common;
+
+use std::time::Duration;
+
+use bpx_api_client::{
+    BpxClient, Error,
+    dead_mans_switch::{DeadMansSwitch, DeadMansSwitchConfig, DeadMansSwitchEvent, TriggerReason},
+};
+use serde_json::json;
+use tokio::sync::mpsc;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{body_partial_json, method, path},
+};
+
+async fn mock_exchange() -> (MockServer, BpxClient) {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/orders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<()>::new()))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/orders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<()>::new()))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+    (mock_server, client)
+}
+
+async fn next_event(rx: &mut mpsc::Receiver<DeadMansSwitchEvent>) -> DeadMansSwitchEvent {
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("switch should emit an event")
+        .expect("switch should still be running")
+}
+
+#[tokio::test]
+async fn dead_mans_switch_cancels_after_missed_heartbeat() {
+    let (mock_server, client) = mock_exchange().await;
+
+    let config = DeadMansSwitchConfig::new(["SOL_USDC", "BTC_USDC"], Duration::from_millis(100))
+        .unwrap()
+        .with_check_interval(Duration::from_millis(10));
+    let (tx, mut rx) = mpsc::channel(16);
+    let switch = DeadMansSwitch::spawn(client, config, tx);
+    switch.heartbeat();
+
+    assert!(matches!(
+        next_event(&mut rx).await,
+        DeadMansSwitchEvent::Triggered(TriggerReason::HeartbeatTimeout)
+    ));
+    for symbol in ["SOL_USDC", "BTC_USDC"] {
+        match next_event(&mut rx).await {
+            DeadMansSwitchEvent::Cancelled { symbol: s, orders } => {
+                assert_eq!(s, symbol);
+                assert!(orders.is_empty());
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+    }
+    assert!(matches!(
+        next_event(&mut rx).await,
+        DeadMansSwitchEvent::Confirmed(TriggerReason::HeartbeatTimeout)
+    ));
+
+    switch.heartbeat();
+    assert!(matches!(
+        next_event(&mut rx).await,
+        DeadMansSwitchEvent::Rearmed
+    ));
+
+    switch.shutdown();
+    assert!(matches!(
+        next_event(&mut rx).await,
+        DeadMansSwitchEvent::Triggered(TriggerReason::Shutdown)
+    ));
+    switch.join().await;
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let cancels = requests
+        .iter()
+        .filter(|request| request.method.as_str() == "DELETE")
+        .count();
+    assert!(cancels >= 4);
+}
+
+#[tokio::test]
+async fn dead_mans_switch_cancels_after_prolonged_disconnect() {
+    let (_mock_server, client) = mock_exchange().await;
+
+    let config = DeadMansSwitchConfig::new(["SOL_USDC"], Duration::from_secs(60))
+        .unwrap()
+        .with_disconnect_timeout(Duration::from_millis(50))
+        .with_check_interval(Duration::from_millis(10));
+    let (tx, mut rx) = mpsc::channel(16);
+    let switch = DeadMansSwitch::spawn(client, config, tx);
+    switch.ws_disconnected();
+
+    assert!(matches!(
+        next_event(&mut rx).await,
+        DeadMansSwitchEvent::Triggered(TriggerReason::Disconnected)
+    ));
+}
+
+#[test]
+fn dead_mans_switch_rejects_malformed_symbols() {
+    let timeout = Duration::from_secs(10);
+    for symbols in [vec![], vec!["SOL_USDC", "SOL-USDC"], vec![""]] {
+        assert!(
+            matches!(
+                DeadMansSwitchConfig::new(symbols.clone(), timeout),
+                Err(Error::InvalidRequest(_))
+            ),
+            "{symbols:?} should be rejected"
+        );
+    }
+}
+
+#[tokio::test]
+async fn dead_mans_switch_retries_only_failed_symbols() {
+    let mock_server = MockServer::start().await;
+    // BTC_USDC is rejected, SOL_USDC fails once and then succeeds.
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/orders"))
+        .and(body_partial_json(json!({ "symbol": "BTC_USDC" })))
+        .respond_with(ResponseTemplate::new(400).set_body_string("unknown market"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/orders"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("unavailable"))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/orders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<()>::new()))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/orders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<()>::new()))
+        .mount(&mock_server)
+        .await;
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let config = DeadMansSwitchConfig::new(["SOL_USDC", "BTC_USDC"], Duration::from_secs(60))
+        .unwrap()
+        .with_retry_interval(Duration::from_millis(20));
+    let (tx, mut rx) = mpsc::channel(16);
+    let switch = DeadMansSwitch::spawn(client, config, tx);
+    switch.trigger();
+
+    assert!(matches!(
+        next_event(&mut rx).await,
+        DeadMansSwitchEvent::Triggered(TriggerReason::Manual)
+    ));
+    let mut events = Vec::new();
+    while events.len() < 3 {
+        events.push(match next_event(&mut rx).await {
+            DeadMansSwitchEvent::CancelFailed {
+                symbol, attempt, ..
+            } => format!("failed {symbol} {attempt}"),
+            DeadMansSwitchEvent::CancelAbandoned { symbol, .. } => format!("abandoned {symbol}"),
+            DeadMansSwitchEvent::Cancelled { symbol, .. } => format!("cancelled {symbol}"),
+            other => panic!("unexpected event {other:?}"),
+        });
+    }
+    assert_eq!(
+        events,
+        [
+            "failed SOL_USDC 1",
+            "abandoned BTC_USDC",
+            "cancelled SOL_USDC"
+        ]
+    );
+
+    // BTC_USDC was not retried, and the book is not reported clear.
+    switch.heartbeat();
+    assert!(matches!(
+        next_event(&mut rx).await,
+        DeadMansSwitchEvent::Rearmed
+    ));
+    let requests = mock_server.received_requests().await.unwrap();
+    let btc_cancels = requests
+        .iter()
+        .filter(|request| {
+            request.method.as_str() == "DELETE"
+                && String::from_utf8_lossy(&request.body).contains("BTC_USDC")
+        })
+        .count();
+    assert_eq!(btc_cancels, 1);
+}
+
+#[tokio::test]
+async fn dead_mans_switch_shutdown_is_bounded_and_never_waits_on_events() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/orders"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("unavailable"))
+        .mount(&mock_server)
+        .await;
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let config = DeadMansSwitchConfig::new(["SOL_USDC"], Duration::from_secs(60))
+        .unwrap()
+        .with_retry_interval(Duration::from_millis(10))
+        .with_shutdown_timeout(Duration::from_millis(200));
+    // Nobody reads the events while the switch shuts down.
+    let (tx, mut rx) = mpsc::channel(1);
+    let switch = DeadMansSwitch::spawn(client, config, tx);
+    switch.shutdown();
+
+    tokio::time::timeout(Duration::from_secs(5), switch.join())
+        .await
+        .expect("shutdown should stop retrying at the deadline");
+    assert!(matches!(
+        rx.recv().await,
+        Some(DeadMansSwitchEvent::Triggered(TriggerReason::Shutdown))
+    ));
+    assert!(rx.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn dead_mans_switch_cancels_when_every_handle_is_dropped() {
+    let (mock_server, client) = mock_exchange().await;
+
+    let config = DeadMansSwitchConfig::new(["SOL_USDC"], Duration::from_secs(60)).unwrap();
+    let (tx, mut rx) = mpsc::channel(16);
+    let switch = DeadMansSwitch::spawn(client, config, tx);
+
+    // The strategy task holding the last handle panics.
+    let handle = switch.handle();
+    let strategy = tokio::spawn(async move {
+        handle.heartbeat();
+        panic!("strategy failed");
+    });
+    assert!(strategy.await.is_err());
+    tokio::time::timeout(Duration::from_secs(5), switch.join())
+        .await
+        .expect("switch should stop after cancelling");
+
+    assert!(matches!(
+        next_event(&mut rx).await,
+        DeadMansSwitchEvent::Triggered(TriggerReason::HandlesDropped)
+    ));
+    assert!(matches!(
+        next_event(&mut rx).await,
+        DeadMansSwitchEvent::Cancelled { .. }
+    ));
+    assert!(matches!(
+        next_event(&mut rx).await,
+        DeadMansSwitchEvent::Confirmed(TriggerReason::HandlesDropped)
+    ));
+    let requests = mock_server.received_requests().await.unwrap();
+    assert!(
+        requests
+            .iter()
+            .any(|request| request.method.as_str() == "DELETE")
+    );
+}
@@ -0,0 +1,277 @@
+mod common;
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use bpx_api_client::{
+    BpxClient,
+    batch::{BatchOrderOutcome, MAX_BATCH_ORDERS},
+    types::order::{ExecuteOrderPayload, Order, OrderType, Side},
+};
+use rust_decimal_macros::dec;
+use serde_json::{Value, json};
+use wiremock::{
+    Mock, MockServer, Request, Respond, ResponseTemplate,
+    matchers::{method, path},
+};
+
+/// Accepts every order except those with an odd client id, which are rejected
+/// the first time they are seen.
+struct RejectOddOnce {
+    seen: Arc<Mutex<HashSet<u64>>>,
+}
+
+impl Respond for RejectOddOnce {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let payloads: Vec<Value> = serde_json::from_slice(&request.body).unwrap();
+        let mut seen = self.seen.lock().unwrap();
+        let responses = payloads
+            .iter()
+            .map(|payload| {
+                let client_id = payload["clientId"].as_u64().unwrap();
+                if client_id % 2 == 1 && seen.insert(client_id) {
+                    json!({
+                        "code": "INVALID_ORDER",
+                        "message": "rejected",
+                        "operation": "CREATE_ORDER",
+                    })
+                } else {
+                    json!({
+                        "orderType": "Limit",
+                        "id": client_id.to_string(),
+                        "clientId": client_id,
+                        "symbol": payload["symbol"],
+                        "side": payload["side"],
+                        "quantity": payload["quantity"],
+                        "executedQuantity": "0",
+                        "executedQuoteQuantity": "0",
+                        "price": payload["price"],
+                        "timeInForce": "GTC",
+                        "selfTradePrevention": "RejectTaker",
+                        "postOnly": false,
+                        "status": "New",
+                        "createdAt": 0,
+                    })
+                }
+            })
+            .collect::<Vec<_>>();
+        ResponseTemplate::new(200).set_body_json(responses)
+    }
+}
+
+fn payloads(count: u32) -> Vec<ExecuteOrderPayload> {
+    (0..count)
+        .map(|client_id| ExecuteOrderPayload {
+            client_id: Some(client_id),
+            order_type: OrderType::Limit,
+            price: Some(dec!(100)),
+            quantity: Some(dec!(1)),
+            side: Side::Bid,
+            symbol: "SOL_USDC".to_string(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn execute_orders_chunked_pairs_payloads_and_retries_failures() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/orders"))
+        .respond_with(RejectOddOnce {
+            seen: Default::default(),
+        })
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let count = MAX_BATCH_ORDERS as u32 * 2 + 10;
+    let mut report = client.execute_orders_chunked(payloads(count), 2).await;
+
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(report.items.len(), count as usize);
+    assert!(!report.is_complete());
+
+    for (index, item) in report.items.iter().enumerate() {
+        assert_eq!(item.payload.client_id, Some(index as u32));
+        match &item.outcome {
+            BatchOrderOutcome::Order(Order::Limit(order)) => {
+                assert_eq!(order.client_id, item.payload.client_id);
+            }
+            BatchOrderOutcome::Rejected(error) => {
+                assert_eq!(index % 2, 1);
+                assert_eq!(error.code, "INVALID_ORDER");
+            }
+            other => panic!("unexpected outcome {other:?}"),
+        }
+    }
+    assert_eq!(report.failed_payloads().len(), count as usize / 2);
+
+    report.retry_failed(&client, 2).await;
+    assert!(report.is_complete());
+    assert_eq!(report.orders().count(), count as usize);
+}
+
+#[tokio::test]
+async fn execute_orders_chunked_reports_request_failures_per_payload() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/orders"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("unavailable"))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let report = client.execute_orders_chunked(payloads(3), 4).await;
+    assert_eq!(report.items.len(), 3);
+    assert!(
+        report
+            .items
+            .iter()
+            .all(|item| matches!(item.outcome, BatchOrderOutcome::Failed(_)))
+    );
+}
+
+#[tokio::test]
+async fn execute_orders_chunked_reports_invalid_payloads_without_sending_them() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/orders"))
+        .respond_with(RejectOddOnce {
+            seen: Default::default(),
+        })
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let mut payloads = payloads(60);
+    payloads[10].symbol = "SOLUSDC".to_string();
+    let mut report = client.execute_orders_chunked(payloads, 2).await;
+
+    // The remaining 59 orders are sent in two chunks.
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(report.items.len(), 60);
+    for (index, item) in report.items.iter().enumerate() {
+        assert_eq!(item.payload.client_id, Some(index as u32));
+        match &item.outcome {
+            BatchOrderOutcome::NotSent(_) => assert_eq!(index, 10),
+            BatchOrderOutcome::Order(_) => assert_eq!(index % 2, 0),
+            BatchOrderOutcome::Rejected(_) => assert_eq!(index % 2, 1),
+            other => panic!("unexpected outcome {other:?}"),
+        }
+    }
+
+    // Rejected orders are resubmitted, the invalid one is not.
+    report.retry_failed(&client, 2).await;
+    assert_eq!(report.orders().count(), 59);
+    report.retry_unconfirmed(&client, 2).await.unwrap();
+    assert!(matches!(
+        report.items[10].outcome,
+        BatchOrderOutcome::NotSent(_)
+    ));
+}
+
+#[tokio::test]
+async fn failed_orders_are_only_resubmitted_once_unconfirmed() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/orders"))
+        .respond_with(ResponseTemplate::new(504).set_body_string("timeout"))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/orders"))
+        .respond_with(RejectOddOnce {
+            seen: Default::default(),
+        })
+        .mount(&mock_server)
+        .await;
+    // The timed out batch reached the exchange: order 0 rests and order 1 filled.
+    Mock::given(method("GET"))
+        .and(path("/api/v1/orders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "orderType": "Limit",
+            "id": "0",
+            "clientId": 0,
+            "symbol": "SOL_USDC",
+            "side": "Bid",
+            "quantity": "1",
+            "executedQuantity": "0",
+            "executedQuoteQuantity": "0",
+            "price": "100",
+            "timeInForce": "GTC",
+            "selfTradePrevention": "RejectTaker",
+            "postOnly": false,
+            "status": "New",
+            "createdAt": 0,
+        }])))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/history/fills"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "tradeId": 1,
+            "clientId": "1",
+            "orderId": "1",
+            "symbol": "SOL_USDC",
+            "feeSymbol": "USDC",
+            "price": "100",
+            "quantity": "1",
+            "fee": "0",
+            "side": "Bid",
+            "timestamp": "2025-01-01T00:00:00",
+            "isMaker": false,
+        }])))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let mut report = client.execute_orders_chunked(payloads(3), 1).await;
+    assert!(report.items.iter().all(|item| item.outcome.is_failed()));
+
+    // Failed requests may have been accepted, so they are not retried by default.
+    report.retry_failed(&client, 1).await;
+    assert!(report.items.iter().all(|item| item.outcome.is_failed()));
+
+    report.retry_unconfirmed(&client, 1).await.unwrap();
+    assert!(report.items[0].outcome.is_order());
+    assert!(report.items[1].outcome.is_failed());
+    assert!(report.items[2].outcome.is_order());
+
+    let posts = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|request| request.method.as_str() == "POST")
+        .collect::<Vec<_>>();
+    assert_eq!(posts.len(), 2);
+    let resubmitted: Vec<Value> = serde_json::from_slice(&posts[1].body).unwrap();
+    assert_eq!(resubmitted.len(), 1);
+    assert_eq!(resubmitted[0]["clientId"], 2);
+}
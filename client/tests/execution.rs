@@ -0,0 +1,464 @@
+#![cfg(feature = "runtime")]
+
+mod common;
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use bpx_api_client::{
+    BpxClient, Error,
+    execution::{ExecutionState, iceberg::IcebergParams, slicer::SliceParams},
+    types::{
+        markets::Market,
+        order::{OrderUpdate, Side},
+    },
+};
+use rust_decimal_macros::dec;
+use serde_json::{Value, json};
+use wiremock::{
+    Mock, MockServer, Request, ResponseTemplate,
+    matchers::{method, path, query_param},
+};
+
+fn market() -> Market {
+    serde_json::from_value(json!({
+        "symbol": "SOL_USDC",
+        "baseSymbol": "SOL",
+        "quoteSymbol": "USDC",
+        "marketType": "SPOT",
+        "filters": {
+            "price": { "minPrice": "0.01", "tickSize": "0.01" },
+            "quantity": { "minQuantity": "0.1", "stepSize": "0.1" }
+        },
+        "orderBookState": "Open",
+        "createdAt": "2025-01-21T06:34:54.691858",
+        "visible": true
+    }))
+    .unwrap()
+}
+
+static ORDER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Fills every child order in full at a price of 10.
+fn fill_order(request: &Request) -> ResponseTemplate {
+    let payload: Value = serde_json::from_slice(&request.body).unwrap();
+    let quantity: rust_decimal::Decimal = payload["quantity"].as_str().unwrap().parse().unwrap();
+    ResponseTemplate::new(200).set_body_json(json!({
+        "orderType": "Market",
+        "id": ORDER_ID.fetch_add(1, Ordering::Relaxed).to_string(),
+        "symbol": payload["symbol"],
+        "side": payload["side"],
+        "quantity": payload["quantity"],
+        "executedQuantity": payload["quantity"],
+        "executedQuoteQuantity": (quantity * dec!(10)).to_string(),
+        "timeInForce": "IOC",
+        "selfTradePrevention": "RejectTaker",
+        "status": "Filled",
+        "createdAt": 0,
+    }))
+}
+
+#[tokio::test]
+async fn twap_slices_parent_order_into_rounded_child_orders() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/order"))
+        .respond_with(fill_order)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let params = SliceParams::new(&market(), Side::Bid, dec!(1), Duration::from_millis(90), 3);
+    let execution = client.twap(params);
+    let progress = tokio::time::timeout(Duration::from_secs(5), execution.wait())
+        .await
+        .expect("execution should finish");
+
+    assert_eq!(progress.state, ExecutionState::Completed);
+    assert_eq!(progress.child_orders, 3);
+    assert_eq!(progress.filled_quantity, dec!(1));
+    assert_eq!(progress.average_price(), Some(dec!(10)));
+
+    let quantities = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            let payload: Value = serde_json::from_slice(&request.body).unwrap();
+            payload["quantity"].as_str().unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(quantities, ["0.3", "0.3", "0.4"]);
+}
+
+/// Public trades of the given quantities, all at `timestamp`, numbered from `first_id` down.
+fn trades(first_id: i64, quantities: &[&str], timestamp: i64) -> Value {
+    json!(
+        quantities
+            .iter()
+            .enumerate()
+            .map(|(index, quantity)| json!({
+                "id": first_id - index as i64,
+                "price": "10",
+                "quantity": quantity,
+                "quoteQuantity": "0",
+                "timestamp": timestamp,
+                "isBuyerMaker": true,
+            }))
+            .collect::<Vec<_>>()
+    )
+}
+
+#[tokio::test]
+async fn capped_twap_pages_market_volume_and_expires_unfilled() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/order"))
+        .respond_with(fill_order)
+        .mount(&mock_server)
+        .await;
+    // A full first page of recent trades, then recent and older trades.
+    let recent = i64::MAX / 2;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/trades/history"))
+        .and(query_param("offset", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(trades(
+            2000,
+            &["0.01"; 1000],
+            recent,
+        )))
+        .mount(&mock_server)
+        .await;
+    let mut older = trades(1000, &["5"], recent);
+    older
+        .as_array_mut()
+        .unwrap()
+        .extend(trades(999, &["100"], 0).as_array().unwrap().iter().cloned());
+    Mock::given(method("GET"))
+        .and(path("/api/v1/trades/history"))
+        .and(query_param("offset", "1000"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(older))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    // 15 traded in the window caps each child order at 1.5.
+    let params = SliceParams::new(&market(), Side::Bid, dec!(10), Duration::from_millis(60), 2)
+        .with_max_participation(dec!(0.1));
+    let execution = client.twap(params);
+    let progress = tokio::time::timeout(Duration::from_secs(5), execution.wait())
+        .await
+        .expect("execution should finish");
+
+    assert_eq!(progress.state, ExecutionState::Expired);
+    assert_eq!(progress.child_orders, 2);
+    assert_eq!(progress.filled_quantity, dec!(3));
+    assert_eq!(progress.remaining_quantity(), dec!(7));
+}
+
+#[tokio::test]
+async fn cancelled_execution_stops_submitting_child_orders() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/order"))
+        .respond_with(fill_order)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let params = SliceParams::new(&market(), Side::Ask, dec!(10), Duration::from_secs(60), 10);
+    let execution = client.twap(params);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    execution.pause();
+    execution.cancel();
+    let progress = execution.wait().await;
+
+    assert_eq!(progress.state, ExecutionState::Cancelled);
+    assert_eq!(progress.child_orders, 1);
+    assert_eq!(progress.filled_quantity, dec!(1));
+}
+
+#[tokio::test]
+async fn dropped_handle_cancels_the_execution() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/order"))
+        .respond_with(fill_order)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let params = SliceParams::new(&market(), Side::Ask, dec!(10), Duration::from_secs(60), 10);
+    let execution = client.twap(params);
+    let mut progress = execution.watch_progress();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    drop(execution);
+    let progress = tokio::time::timeout(
+        Duration::from_secs(5),
+        progress.wait_for(|progress| progress.state.is_finished()),
+    )
+    .await
+    .expect("execution should stop once the handle is dropped")
+    .unwrap()
+    .clone();
+
+    assert_eq!(progress.state, ExecutionState::Cancelled);
+    assert_eq!(progress.child_orders, 1);
+}
+
+/// Rests every child order on the book, numbering order ids from 1.
+fn rest_order() -> impl Fn(&Request) -> ResponseTemplate + Send + Sync {
+    let ids = AtomicU64::new(1);
+    move |request: &Request| {
+        let payload: Value = serde_json::from_slice(&request.body).unwrap();
+        ResponseTemplate::new(200).set_body_json(json!({
+            "orderType": "Limit",
+            "id": ids.fetch_add(1, Ordering::Relaxed).to_string(),
+            "symbol": payload["symbol"],
+            "side": payload["side"],
+            "quantity": payload["quantity"],
+            "executedQuantity": "0",
+            "executedQuoteQuantity": "0",
+            "price": payload["price"],
+            "timeInForce": "GTC",
+            "selfTradePrevention": "RejectTaker",
+            "postOnly": false,
+            "status": "New",
+            "createdAt": 0,
+        }))
+    }
+}
+
+fn fill_update(order_id: u64, quantity: &str) -> OrderUpdate {
+    serde_json::from_value(json!({
+        "e": "orderFill", "E": 1, "s": "SOL_USDC", "i": order_id.to_string(), "S": "Ask",
+        "o": "LIMIT", "f": "GTC", "q": quantity, "p": "10", "X": "Filled", "z": quantity,
+        "Z": "4", "l": quantity, "L": "10", "t": order_id, "V": "RejectTaker", "O": "USER",
+        "T": 1,
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn iceberg_refills_display_quantity_and_cancels_resting_order() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/order"))
+        .respond_with(rest_order())
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/order"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "orderType": "Limit", "id": "2", "symbol": "SOL_USDC", "side": "Ask",
+            "quantity": "0.4", "executedQuantity": "0", "executedQuoteQuantity": "0",
+            "price": "10", "timeInForce": "GTC", "selfTradePrevention": "RejectTaker",
+            "postOnly": false, "status": "Cancelled", "createdAt": 0,
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let params = IcebergParams::new(&market(), Side::Ask, dec!(1), dec!(0.4), dec!(10)).unwrap();
+    let execution = client.iceberg(params);
+    let mut progress = execution.watch_progress();
+
+    progress
+        .wait_for(|progress| progress.child_orders == 1)
+        .await
+        .unwrap();
+    execution.on_order_update(fill_update(1, "0.4"));
+    progress
+        .wait_for(|progress| progress.child_orders == 2)
+        .await
+        .unwrap();
+    execution.cancel();
+
+    let progress = tokio::time::timeout(Duration::from_secs(5), execution.wait())
+        .await
+        .expect("execution should finish");
+    assert_eq!(progress.state, ExecutionState::Cancelled);
+    assert_eq!(progress.filled_quantity, dec!(0.4));
+    assert_eq!(progress.average_price(), Some(dec!(10)));
+}
+
+#[test]
+fn iceberg_rejects_display_quantity_below_minimum() {
+    for display_quantity in [dec!(0), dec!(0.05), dec!(0.09)] {
+        assert!(matches!(
+            IcebergParams::new(&market(), Side::Ask, dec!(1), display_quantity, dec!(10)),
+            Err(Error::InvalidRequest(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn iceberg_expires_when_the_remainder_is_below_minimum() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/order"))
+        .respond_with(rest_order())
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let params = IcebergParams::new(&market(), Side::Ask, dec!(0.45), dec!(0.4), dec!(10)).unwrap();
+    let execution = client.iceberg(params);
+    let mut progress = execution.watch_progress();
+
+    progress
+        .wait_for(|progress| progress.child_orders == 1)
+        .await
+        .unwrap();
+    execution.on_order_update(fill_update(1, "0.4"));
+
+    let progress = tokio::time::timeout(Duration::from_secs(5), execution.wait())
+        .await
+        .expect("execution should finish");
+    assert_eq!(progress.state, ExecutionState::Expired);
+    assert_eq!(progress.filled_quantity, dec!(0.4));
+}
+
+fn cancelled_order(order_id: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "orderType": "Limit", "id": order_id, "symbol": "SOL_USDC", "side": "Ask",
+        "quantity": "0.4", "executedQuantity": "0", "executedQuoteQuantity": "0",
+        "price": "10", "timeInForce": "GTC", "selfTradePrevention": "RejectTaker",
+        "postOnly": false, "status": "Cancelled", "createdAt": 0,
+    }))
+}
+
+async fn cancel_requests(mock_server: &MockServer) -> usize {
+    mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .filter(|request| request.method.as_str() == "DELETE")
+        .count()
+}
+
+#[tokio::test]
+async fn failed_cancel_keeps_the_order_and_does_not_report_cancelled() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/order"))
+        .respond_with(rest_order())
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/order"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("internal error"))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let params = IcebergParams::new(&market(), Side::Ask, dec!(1), dec!(0.4), dec!(10)).unwrap();
+    let execution = client.iceberg(params);
+    let mut progress = execution.watch_progress();
+    progress
+        .wait_for(|progress| progress.child_orders == 1)
+        .await
+        .unwrap();
+    execution.cancel();
+
+    let progress = tokio::time::timeout(Duration::from_secs(5), execution.wait())
+        .await
+        .expect("execution should finish");
+    assert_eq!(progress.state, ExecutionState::Failed);
+    assert!(progress.last_error.is_some());
+    assert_eq!(cancel_requests(&mock_server).await, 3);
+}
+
+#[tokio::test]
+async fn paused_execution_retries_the_cancel_before_reporting_paused() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/order"))
+        .respond_with(rest_order())
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/order"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("internal error"))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/order"))
+        .respond_with(cancelled_order("1"))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let params = IcebergParams::new(&market(), Side::Ask, dec!(1), dec!(0.4), dec!(10)).unwrap();
+    let execution = client.iceberg(params);
+    let mut progress = execution.watch_progress();
+    progress
+        .wait_for(|progress| progress.child_orders == 1)
+        .await
+        .unwrap();
+    execution.pause();
+
+    tokio::time::timeout(
+        Duration::from_secs(5),
+        progress.wait_for(|progress| progress.state == ExecutionState::Paused),
+    )
+    .await
+    .expect("execution should pause")
+    .unwrap();
+    assert_eq!(cancel_requests(&mock_server).await, 2);
+
+    execution.cancel();
+    let progress = tokio::time::timeout(Duration::from_secs(5), execution.wait())
+        .await
+        .expect("execution should finish");
+    assert_eq!(progress.state, ExecutionState::Cancelled);
+    assert_eq!(progress.child_orders, 1);
+    assert_eq!(cancel_requests(&mock_server).await, 2);
+}
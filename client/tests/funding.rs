@@ -0,0 +1,89 @@
+use bpx_api_client::BpxClient;
+use chrono::DateTime;
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path, query_param},
+};
+
+/// Funding rates for the 8 hour intervals `from` down to `to`, most recent first.
+fn rates(from: i64, to: i64) -> serde_json::Value {
+    let rates = (to..=from)
+        .rev()
+        .map(|interval| {
+            let end = DateTime::from_timestamp(interval * 8 * 3600, 0).unwrap();
+            json!({
+                "symbol": "SOL_USDC_PERP",
+                "intervalEndTimestamp": end.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                "fundingRate": "0.0001"
+            })
+        })
+        .collect::<Vec<_>>();
+    json!(rates)
+}
+
+#[tokio::test]
+async fn test_get_funding_rates_between_pages_until_start() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/fundingRates"))
+        .and(query_param("offset", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(rates(2999, 2000)))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/fundingRates"))
+        .and(query_param("offset", "1000"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(rates(1999, 1000)))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    let start = DateTime::from_timestamp(1500 * 8 * 3600, 0).unwrap();
+    let end = DateTime::from_timestamp(2500 * 8 * 3600, 0).unwrap();
+    let rates = client
+        .get_funding_rates_between("SOL_USDC_PERP", start, end)
+        .await
+        .unwrap();
+
+    assert_eq!(rates.len(), 1000);
+    assert_eq!(rates[0].interval_end_timestamp, start);
+    assert!(
+        rates
+            .windows(2)
+            .all(|w| w[0].interval_end_timestamp < w[1].interval_end_timestamp)
+    );
+}
+
+#[tokio::test]
+async fn test_get_funding_rates_between_stops_at_the_first_older_interval() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/fundingRates"))
+        .and(query_param("offset", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(rates(2999, 2000)))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    let start = DateTime::from_timestamp(2990 * 8 * 3600, 0).unwrap();
+    let end = DateTime::from_timestamp(2995 * 8 * 3600, 0).unwrap();
+    let rates = client
+        .get_funding_rates_between("SOL_USDC_PERP", start, end)
+        .await
+        .unwrap();
+
+    assert_eq!(rates.len(), 5);
+    assert_eq!(rates[0].interval_end_timestamp, start);
+}
@@ -4,6 +4,7 @@ use bpx_api_client::{
     BpxClient,
     types::{fill::Fill, fill::FillsHistoryParams, history::SortDirection, order::Side},
 };
+use chrono::DateTime;
 use rust_decimal_macros::dec;
 use std::collections::BTreeMap;
 use wiremock::{
@@ -25,7 +26,7 @@ async fn get_historical_fills_omits_none_query_params() {
         quantity: dec!(100),
         fee: dec!(0),
         side: Side::Ask,
-        timestamp: "1970-01-01T00:00:01".to_string(),
+        timestamp: DateTime::from_timestamp(1, 0).unwrap(),
         is_maker: false,
         system_order_type: None,
     }];
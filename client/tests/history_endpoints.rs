@@ -0,0 +1,120 @@
+mod common;
+
+use bpx_api_client::{
+    BpxClient,
+    types::history::{
+        BorrowLendEventType, BorrowLendHistoryParams, BorrowLendSide, FundingPaymentsParams,
+        InterestHistoryParams, InterestPaymentType, SettlementHistoryParams, SettlementSource,
+    },
+};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{header_exists, method, path, query_param},
+};
+
+async fn client(mock_server: &MockServer) -> BpxClient {
+    BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build")
+}
+
+#[tokio::test]
+async fn test_cash_flow_history_endpoints_are_signed() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/history/funding"))
+        .and(query_param("symbol", "SOL_USDC_PERP"))
+        .and(header_exists("x-signature"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "userId": 1,
+            "subaccountId": null,
+            "symbol": "SOL_USDC_PERP",
+            "quantity": "-0.0123",
+            "intervalEndTimestamp": "2025-01-01T08:00:00",
+            "fundingRate": "0.0001"
+        }])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/history/interest"))
+        .and(query_param("positionId", "7"))
+        .and(header_exists("x-signature"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "paymentType": "Lend",
+            "interestRate": "0.05",
+            "interval": 3600000,
+            "marketSymbol": "USDC",
+            "positionId": "7",
+            "quantity": "0.01",
+            "symbol": "USDC",
+            "timestamp": "2025-01-01T01:00:00"
+        }])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/history/settlement"))
+        .and(query_param("source", "FundingPayment"))
+        .and(header_exists("x-signature"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "userId": 1,
+            "subaccountId": 2,
+            "quantity": "-0.0123",
+            "source": "FundingPayment",
+            "timestamp": "2025-01-01T08:00:00"
+        }])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/history/borrowLend"))
+        .and(query_param("type", "Borrow"))
+        .and(header_exists("x-signature"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "eventType": "BorrowRepay",
+            "positionId": "9",
+            "positionQuantity": "0",
+            "quantity": "10",
+            "source": "AutoBorrowRepay",
+            "symbol": "USDC",
+            "timestamp": "2025-01-01T02:00:00"
+        }])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = client(&mock_server).await;
+    let funding = client
+        .get_funding_payments(FundingPaymentsParams::default().with_symbol("SOL_USDC_PERP"))
+        .await
+        .unwrap();
+    assert_eq!(funding[0].quantity, dec!(-0.0123));
+
+    let interest = client
+        .get_interest_history(InterestHistoryParams::default().with_position_id("7"))
+        .await
+        .unwrap();
+    assert_eq!(interest[0].payment_type, InterestPaymentType::Lend);
+
+    let settlements = client
+        .get_settlement_history(
+            SettlementHistoryParams::default().with_source(SettlementSource::FundingPayment),
+        )
+        .await
+        .unwrap();
+    assert_eq!(settlements[0].subaccount_id, Some(2));
+
+    let movements = client
+        .get_borrow_lend_history(
+            BorrowLendHistoryParams::default().with_side(BorrowLendSide::Borrow),
+        )
+        .await
+        .unwrap();
+    assert_eq!(movements[0].event_type, BorrowLendEventType::BorrowRepay);
+    assert_eq!(movements[0].spot_margin_order_id, None);
+}
@@ -0,0 +1,215 @@
+mod common;
+
+use bpx_api_client::{
+    BpxClient,
+    history_sync::{HistoryKind, HistoryStore, HistorySync, jsonl::JsonlStore},
+};
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path, query_param},
+};
+
+fn fill(trade_id: i64, symbol: &str, second: i64) -> serde_json::Value {
+    json!({
+        "tradeId": trade_id,
+        "orderId": format!("order-{trade_id}"),
+        "symbol": symbol,
+        "feeSymbol": "USDC",
+        "price": "100",
+        "quantity": "1",
+        "fee": "0",
+        "side": "Bid",
+        "timestamp": format!("2025-01-01T00:00:0{second}"),
+        "isMaker": false
+    })
+}
+
+fn fills(trade_ids: &[i64]) -> serde_json::Value {
+    let fills = trade_ids
+        .iter()
+        .map(|id| fill(*id, "SOL_USDC", *id))
+        .collect::<Vec<_>>();
+    json!(fills)
+}
+
+fn deposits(statuses: &[(i64, &str)]) -> serde_json::Value {
+    let deposits = statuses
+        .iter()
+        .map(|(id, status)| {
+            json!({
+                "id": id,
+                "transactionHash": format!("0x{id}"),
+                "source": "ethereum",
+                "status": status,
+                "symbol": "USDC",
+                "quantity": "25",
+                "createdAt": format!("2025-01-01T00:00:0{id}"),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!(deposits)
+}
+
+fn temp_root(name: &str) -> std::path::PathBuf {
+    let root = std::env::temp_dir().join(format!("bpx-history-sync-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    root
+}
+
+async fn client(mock_server: &MockServer) -> BpxClient {
+    BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build")
+}
+
+#[tokio::test]
+async fn test_history_sync_resumes_fills_from_cursor() {
+    let root = temp_root("resume");
+
+    let first = MockServer::start().await;
+    for (offset, ids) in [("0", &[1, 2][..]), ("2", &[])] {
+        Mock::given(method("GET"))
+            .and(path("/wapi/v1/history/fills"))
+            .and(query_param("sort_direction", "Asc"))
+            .and(query_param("offset", offset))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fills(ids)))
+            .expect(1)
+            .mount(&first)
+            .await;
+    }
+    let mut sync =
+        HistorySync::new(client(&first).await, JsonlStore::new(&root), "main").with_page_size(2);
+    let report = sync.sync(HistoryKind::Fills).await.unwrap();
+    assert_eq!(report.written, 2);
+    assert_eq!(report.cursor.unwrap().id, "SOL_USDC-2-Bid");
+
+    // The second run starts at the timestamp of the last fill, which is
+    // returned again and skipped.
+    let second = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/history/fills"))
+        .and(query_param("from", "1735689602000"))
+        .and(query_param("offset", "0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fills(&[2, 3])))
+        .expect(1)
+        .mount(&second)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/history/fills"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fills(&[])))
+        .mount(&second)
+        .await;
+    let mut sync =
+        HistorySync::new(client(&second).await, JsonlStore::new(&root), "main").with_page_size(2);
+    let report = sync.sync(HistoryKind::Fills).await.unwrap();
+    assert_eq!(report.written, 1);
+
+    let ids: Vec<_> = sync
+        .store()
+        .records("main", HistoryKind::Fills)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.id)
+        .collect();
+    assert_eq!(ids, ["SOL_USDC-1-Bid", "SOL_USDC-2-Bid", "SOL_USDC-3-Bid"]);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn test_history_sync_keeps_fills_sharing_a_timestamp_across_pages() {
+    let root = temp_root("same-timestamp");
+
+    // Fills at the same millisecond in two markets may come in any trade id
+    // order, and the same trade id may be used in both markets.
+    let mock_server = MockServer::start().await;
+    for (offset, page) in [
+        ("0", json!([fill(1, "SOL_USDC", 1), fill(9, "SOL_USDC", 2)])),
+        ("2", json!([fill(4, "BTC_USDC", 2), fill(9, "BTC_USDC", 2)])),
+        ("4", json!([])),
+    ] {
+        Mock::given(method("GET"))
+            .and(path("/wapi/v1/history/fills"))
+            .and(query_param("offset", offset))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+    }
+    let mut sync = HistorySync::new(client(&mock_server).await, JsonlStore::new(&root), "main")
+        .with_page_size(2);
+    let report = sync.sync(HistoryKind::Fills).await.unwrap();
+    assert_eq!(report.written, 4);
+
+    let mut ids: Vec<_> = sync
+        .store()
+        .records("main", HistoryKind::Fills)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.id)
+        .collect();
+    ids.sort();
+    assert_eq!(
+        ids,
+        [
+            "BTC_USDC-4-Bid",
+            "BTC_USDC-9-Bid",
+            "SOL_USDC-1-Bid",
+            "SOL_USDC-9-Bid"
+        ]
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn test_history_sync_updates_pending_deposits() {
+    let root = temp_root("pending");
+
+    let first = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/capital/deposits"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(deposits(&[
+            (3, "pending"),
+            (2, "confirmed"),
+            (1, "pending"),
+        ])))
+        .expect(1)
+        .mount(&first)
+        .await;
+    let mut sync = HistorySync::new(client(&first).await, JsonlStore::new(&root), "main");
+    let report = sync.sync(HistoryKind::Deposits).await.unwrap();
+    assert_eq!((report.written, report.updated), (3, 0));
+
+    // Both pending deposits are fetched again and the confirmed one updated.
+    let second = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/capital/deposits"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(deposits(&[
+            (4, "pending"),
+            (3, "pending"),
+            (2, "confirmed"),
+            (1, "confirmed"),
+        ])))
+        .expect(1)
+        .mount(&second)
+        .await;
+    let mut sync = HistorySync::new(client(&second).await, JsonlStore::new(&root), "main");
+    let report = sync.sync(HistoryKind::Deposits).await.unwrap();
+    assert_eq!((report.written, report.updated), (1, 1));
+    assert_eq!(report.cursor.unwrap().id, "4");
+
+    let statuses: Vec<_> = sync
+        .store()
+        .records("main", HistoryKind::Deposits)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.record["status"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(statuses, ["confirmed", "confirmed", "pending", "pending"]);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -10,7 +10,10 @@
 
 #[cfg(feature = "integration-tests")]
 mod tests {
-    use bpx_api_client::{BpxClient, Result};
+    use bpx_api_client::{
+        BpxClient, Result,
+        types::{markets::KlineInterval, symbol::Symbol},
+    };
 
     // BTC_USDC might not always be a market, so this makes the tests slightly brittle.
     // We can change this if that ever happens.
@@ -79,7 +82,7 @@ mod tests {
         #[tokio::test]
         async fn test_get_all_mark_prices() -> Result<()> {
             let client = BpxClient::builder().build().unwrap();
-            let mark_prices = client.get_all_mark_prices().await?;
+            let mark_prices = client.get_all_mark_prices(None::<Symbol>).await?;
 
             // Should return at least some mark prices
             assert!(!mark_prices.is_empty());
@@ -91,7 +94,7 @@ mod tests {
         async fn test_get_ticker() -> Result<()> {
             let client = BpxClient::builder().build().unwrap();
 
-            let ticker = client.get_ticker(BTC_USDC).await?;
+            let ticker = client.get_ticker(BTC_USDC, None).await?;
 
             // Ticker should have valid data
             assert_eq!(ticker.symbol, BTC_USDC);
@@ -102,7 +105,7 @@ mod tests {
         #[tokio::test]
         async fn test_get_tickers() -> Result<()> {
             let client = BpxClient::builder().build().unwrap();
-            let tickers = client.get_tickers().await?;
+            let tickers = client.get_tickers(None).await?;
 
             // Should return at least some tickers
             assert!(!tickers.is_empty());
@@ -121,7 +124,9 @@ mod tests {
         #[tokio::test]
         async fn test_get_funding_interval_rates() -> Result<()> {
             let client = BpxClient::builder().build().unwrap();
-            let funding_rates = client.get_funding_interval_rates(BTC_USDC_PERP).await?;
+            let funding_rates = client
+                .get_funding_interval_rates(BTC_USDC_PERP, None, None)
+                .await?;
 
             // Should return at least some funding rates
             assert!(!funding_rates.is_empty());
@@ -137,7 +142,12 @@ mod tests {
             let end_time = now_millis() / 1000;
             let start_time = end_time - 3600 * 2; // 2 hour ago
             let klines = client
-                .get_k_lines(BTC_USDC, "15m", start_time as i64, Some(end_time as i64))
+                .get_k_lines(
+                    BTC_USDC,
+                    KlineInterval::FifteenMinutes,
+                    start_time as i64,
+                    Some(end_time as i64),
+                )
                 .await?;
 
             // Should return at least some klines
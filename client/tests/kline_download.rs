@@ -0,0 +1,132 @@
+use bpx_api_client::{BpxClient, types::markets::KlineInterval};
+use chrono::{DateTime, TimeDelta};
+use serde_json::{Value, json};
+use wiremock::{
+    Mock, MockServer, Request, ResponseTemplate,
+    matchers::{method, path},
+};
+
+const START: i64 = 1_726_056_000; // 2024-09-11 12:00:00 UTC
+const MISSING: i64 = START + 7 * 60;
+const NO_TRADES: i64 = START + 12 * 60;
+
+/// Serves one-minute K-lines for `[startTime, endTime]`, including the K-line
+/// starting at `endTime` so windows overlap. One K-line is missing and one has
+/// no trades.
+fn klines(request: &Request) -> ResponseTemplate {
+    let param = |name: &str| -> i64 {
+        request
+            .url
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .unwrap()
+            .1
+            .parse()
+            .unwrap()
+    };
+    let klines = (param("startTime")..=param("endTime"))
+        .step_by(60)
+        .filter(|&start| start != MISSING)
+        .map(|start| {
+            let time = |seconds: i64| {
+                DateTime::from_timestamp(seconds, 0)
+                    .unwrap()
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            };
+            let price = if start == NO_TRADES {
+                Value::Null
+            } else {
+                json!((start - START).to_string())
+            };
+            json!({
+                "start": time(start),
+                "end": time(start + 60),
+                "open": price,
+                "high": price,
+                "low": price,
+                "close": price,
+                "volume": if start == NO_TRADES { "0" } else { "1" },
+                "trades": if start == NO_TRADES { "0" } else { "1" },
+            })
+        })
+        .collect::<Vec<_>>();
+    ResponseTemplate::new(200).set_body_json(klines)
+}
+
+#[tokio::test]
+async fn downloads_windows_and_reports_gaps() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/klines"))
+        .respond_with(klines)
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    let start = DateTime::from_timestamp(START + 30, 0).unwrap();
+    let end = DateTime::from_timestamp(START + 30 * 60, 0).unwrap();
+    let download = client
+        .kline_downloader("SOL_USDC", KlineInterval::OneMinute)
+        .with_window(10)
+        .download(start, end)
+        .await
+        .unwrap();
+
+    assert_eq!(download.klines.len(), 29);
+    assert!(
+        download
+            .klines
+            .windows(2)
+            .all(|pair| pair[0].start < pair[1].start)
+    );
+    assert_eq!(download.klines[0].start.timestamp(), START);
+    assert_eq!(download.gaps.len(), 1);
+    assert_eq!(download.gaps[0].start.timestamp(), MISSING);
+    assert_eq!(
+        download.gaps[0].end - download.gaps[0].start,
+        TimeDelta::minutes(1)
+    );
+    assert_eq!(download.filled, 0);
+}
+
+#[tokio::test]
+async fn forward_fills_intervals_without_trades() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/klines"))
+        .respond_with(klines)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    let start = DateTime::from_timestamp(START, 0).unwrap();
+    let end = DateTime::from_timestamp(START + 30 * 60, 0).unwrap();
+    let download = client
+        .kline_downloader("SOL_USDC", KlineInterval::OneMinute)
+        .with_window(10)
+        .with_concurrency(1)
+        .with_forward_fill(true)
+        .download(start, end)
+        .await
+        .unwrap();
+
+    assert_eq!(download.klines.len(), 30);
+    assert!(download.gaps.is_empty());
+    assert_eq!(download.filled, 2);
+
+    let filled = &download.klines[7];
+    assert_eq!(filled.start.timestamp(), MISSING);
+    assert_eq!(filled.close, download.klines[6].close);
+    assert!(filled.volume.is_zero());
+    assert_eq!(download.klines[12].close, download.klines[11].close);
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use bpx_api_client::{BpxClient, mark_price::MarkPriceCache, types::markets::MarkPriceUpdate};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use tokio::sync::mpsc;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path, query_param},
+};
+
+fn update(symbol: &str, mark_price: &str, engine_timestamp: i64) -> MarkPriceUpdate {
+    serde_json::from_value(json!({
+        "e": "markPrice",
+        "E": engine_timestamp,
+        "s": symbol,
+        "p": mark_price,
+        "f": "0.0001",
+        "i": "100",
+        "n": 1735718400000000u64,
+        "T": engine_timestamp
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_mark_price_cache_seeds_and_applies_updates() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/markPrices"))
+        .and(query_param("symbol", "SOL_USDC_PERP"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/markPrices"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "symbol": "SOL_USDC_PERP",
+            "fundingRate": "0.00005",
+            "indexPrice": "99.5",
+            "markPrice": "99.9",
+            "nextFundingTimestamp": 1735718400000u64
+        }])))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    assert!(
+        client
+            .get_all_mark_prices(Some("SOL_USDC_PERP"))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let cache = MarkPriceCache::seed(&client).await.unwrap();
+    let seeded = cache.get("SOL_USDC_PERP").unwrap();
+    assert_eq!(seeded.mark_price, dec!(99.9));
+    assert_eq!(seeded.next_funding_time.unwrap().timestamp(), 1735718400);
+
+    let (tx, rx) = mpsc::channel(8);
+    let task = tokio::spawn(cache.clone().run(rx));
+    tx.send(update("SOL_USDC_PERP", "101", 2_000))
+        .await
+        .unwrap();
+    // Out of order updates are ignored.
+    tx.send(update("SOL_USDC_PERP", "100", 1_000))
+        .await
+        .unwrap();
+    tx.send(update("BTC_USDC_PERP", "60000", 1_000))
+        .await
+        .unwrap();
+    drop(tx);
+    task.await.unwrap();
+
+    assert_eq!(cache.mark_price("SOL_USDC_PERP"), Some(dec!(101)));
+    assert_eq!(cache.index_price("SOL_USDC_PERP"), Some(dec!(100)));
+    assert_eq!(cache.funding_rate("BTC_USDC_PERP"), Some(dec!(0.0001)));
+    assert_eq!(cache.snapshot().len(), 2);
+}
+
+#[tokio::test]
+async fn test_mark_price_cache_refresh_keeps_websocket_prices() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/markPrices"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {
+                "symbol": "SOL_USDC_PERP",
+                "fundingRate": "0.00005",
+                "indexPrice": "99.5",
+                "markPrice": "99.9",
+                "nextFundingTimestamp": 1735718400000u64
+            },
+            {
+                "symbol": "BTC_USDC_PERP",
+                "fundingRate": "0.00005",
+                "indexPrice": "59000",
+                "markPrice": "59000",
+                "nextFundingTimestamp": 1735718400000u64
+            }
+        ])))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    let cache = MarkPriceCache::new();
+    cache.apply(&update("SOL_USDC_PERP", "101", 2_000));
+    cache.refresh(&client).await.unwrap();
+
+    let sol = cache.get("SOL_USDC_PERP").unwrap();
+    assert_eq!(sol.mark_price, dec!(101));
+    assert_eq!(sol.engine_timestamp, Some(2_000));
+    assert_eq!(cache.mark_price("BTC_USDC_PERP"), Some(dec!(59000)));
+
+    // REST entries are still replaced by later refreshes and updates.
+    cache.refresh(&client).await.unwrap();
+    cache.apply(&update("BTC_USDC_PERP", "60000", 1_000));
+    assert_eq!(cache.mark_price("BTC_USDC_PERP"), Some(dec!(60000)));
+    assert_eq!(cache.mark_price("SOL_USDC_PERP"), Some(dec!(101)));
+}
+
+#[tokio::test]
+async fn test_mark_price_cache_refresh_replaces_stale_websocket_prices() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/markPrices"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "symbol": "SOL_USDC_PERP",
+            "fundingRate": "0.00005",
+            "indexPrice": "99.5",
+            "markPrice": "99.9",
+            "nextFundingTimestamp": 1735718400000u64
+        }])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    let cache = MarkPriceCache::new().with_max_websocket_age(Duration::from_millis(10));
+    cache.apply(&update("SOL_USDC_PERP", "101", 2_000));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(cache.get("SOL_USDC_PERP").unwrap().age() >= Duration::from_millis(20));
+
+    cache.refresh(&client).await.unwrap();
+    let sol = cache.get("SOL_USDC_PERP").unwrap();
+    assert_eq!(sol.mark_price, dec!(99.9));
+    assert_eq!(sol.engine_timestamp, None);
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use bpx_api_client::{BpxClient, registry::MarketRegistry, types::Blockchain};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+};
+
+fn market(symbol: &str, base: &str, quote: &str, market_type: &str) -> serde_json::Value {
+    json!({
+        "symbol": symbol,
+        "baseSymbol": base,
+        "quoteSymbol": quote,
+        "marketType": market_type,
+        "filters": {
+            "price": { "minPrice": "0.01", "tickSize": "0.01" },
+            "quantity": { "minQuantity": "0.01", "stepSize": "0.01" }
+        },
+        "orderBookState": "Open",
+        "createdAt": "2025-01-21T06:34:54.691858",
+        "visible": true
+    })
+}
+
+fn token(blockchain: &str, deposit_enabled: bool, withdraw_enabled: bool) -> serde_json::Value {
+    json!({
+        "blockchain": blockchain,
+        "contractAddress": "",
+        "depositEnabled": deposit_enabled,
+        "displayName": "USDC",
+        "minimumDeposit": "1",
+        "withdrawEnabled": withdraw_enabled,
+        "minimumWithdrawal": "1",
+        "maximumWithdrawal": null,
+        "withdrawalFee": "1"
+    })
+}
+
+async fn mount(mock_server: &MockServer, loads: u64) {
+    Mock::given(method("GET"))
+        .and(path("/api/v1/markets"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            market("SOL_USDC", "SOL", "USDC", "SPOT"),
+            market("SOL_USDC_PERP", "SOL", "USDC", "PERP"),
+            market("BTC_USDC", "BTC", "USDC", "SPOT"),
+        ])))
+        .expect(loads)
+        .mount(mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/assets"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "coingeckoId": "usd-coin",
+            "displayName": "USD Coin",
+            "symbol": "USDC",
+            "tokens": [
+                token("Solana", true, true),
+                token("Ethereum", true, false),
+            ]
+        }])))
+        .expect(loads)
+        .mount(mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/securities"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .expect(loads)
+        .mount(mock_server)
+        .await;
+}
+
+#[tokio::test]
+async fn indexes_markets_and_assets() {
+    let mock_server = MockServer::start().await;
+    mount(&mock_server, 1).await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+    let registry = MarketRegistry::new(client);
+
+    let snapshot = registry.snapshot().await.unwrap();
+    let perp = snapshot.market("SOL_USDC_PERP").unwrap();
+    assert!(perp.is_perp());
+    assert!(!perp.is_spot());
+    assert!(snapshot.market("SOL_USDC").unwrap().is_spot());
+    assert_eq!(snapshot.tick_size("BTC_USDC"), Some(dec!(0.01)));
+    assert_eq!(snapshot.step_size("ETH_USDC"), None);
+
+    let mut sol_markets = snapshot
+        .markets_by_base("SOL")
+        .map(|market| market.symbol.as_str())
+        .collect::<Vec<_>>();
+    sol_markets.sort();
+    assert_eq!(sol_markets, ["SOL_USDC", "SOL_USDC_PERP"]);
+    assert_eq!(snapshot.markets_by_quote("USDC").count(), 3);
+
+    assert_eq!(
+        snapshot.deposit_blockchains("USDC"),
+        [Blockchain::Solana, Blockchain::Ethereum]
+    );
+    assert_eq!(
+        snapshot.withdrawal_blockchains("USDC"),
+        [Blockchain::Solana]
+    );
+    assert!(snapshot.withdrawal_blockchains("SOL").is_empty());
+
+    // Served from the cache.
+    assert_eq!(
+        registry.market("BTC_USDC").await.unwrap().symbol,
+        "BTC_USDC"
+    );
+    assert!(registry.market("ETH_USDC").await.is_err());
+}
+
+#[tokio::test]
+async fn reloads_after_ttl() {
+    let mock_server = MockServer::start().await;
+    mount(&mock_server, 2).await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+    let registry = MarketRegistry::new(client).with_ttl(Duration::from_millis(50));
+
+    let first = registry.snapshot().await.unwrap();
+    let cached = registry.snapshot().await.unwrap();
+    assert_eq!(first.fetched_at(), cached.fetched_at());
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    let reloaded = registry.snapshot().await.unwrap();
+    assert!(reloaded.fetched_at() > first.fetched_at());
+}
@@ -0,0 +1,56 @@
+use bpx_api_client::BpxClient;
+use futures_util::TryStreamExt;
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path, query_param},
+};
+
+fn trades(ids: &[i64]) -> serde_json::Value {
+    let trades = ids
+        .iter()
+        .map(|id| {
+            json!({
+                "id": id,
+                "price": "100",
+                "quantity": "1",
+                "quoteQuantity": "100",
+                "timestamp": 1735689600000i64 + id,
+                "isBuyerMaker": false
+            })
+        })
+        .collect::<Vec<_>>();
+    json!(trades)
+}
+
+#[tokio::test]
+async fn test_historical_trades_paginator_walks_until_short_page() {
+    let mock_server = MockServer::start().await;
+    // A new trade arrives after the first page, shifting trade 4 onto the second page.
+    for (offset, ids) in [("0", &[5, 4][..]), ("2", &[4, 3]), ("4", &[2])] {
+        Mock::given(method("GET"))
+            .and(path("/api/v1/trades/history"))
+            .and(query_param("symbol", "SOL_USDC"))
+            .and(query_param("limit", "2"))
+            .and(query_param("offset", offset))
+            .respond_with(ResponseTemplate::new(200).set_body_json(trades(ids)))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+    }
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    let ids: Vec<i64> = client
+        .historical_trades_paginator("SOL_USDC")
+        .with_page_size(2)
+        .into_stream()
+        .map_ok(|trade| trade.id)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![5, 4, 3, 2]);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use std::time::Duration;
+
+use bpx_api_client::{
+    BpxClient,
+    pool::{BpxClientPool, total_balances},
+};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{header, header_exists, method, path},
+};
+
+#[tokio::test]
+async fn pool_fans_out_and_isolates_account_errors() {
+    let healthy = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/capital"))
+        .and(header_exists("x-api-key"))
+        .and(header_exists("x-signature"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "USDC": { "available": "10", "locked": "1", "staked": "0" },
+        })))
+        .mount(&healthy)
+        .await;
+    let failing = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/capital"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("unavailable"))
+        .mount(&failing)
+        .await;
+
+    let mut pool = BpxClientPool::new()
+        .unwrap()
+        .with_base_url(healthy.uri())
+        .with_rate_limit(10, Duration::from_secs(1));
+    pool.add_account("alpha", common::test_secret()).unwrap();
+    pool.add_account("beta", common::test_secret()).unwrap();
+    pool.insert(
+        "gamma",
+        BpxClient::builder()
+            .base_url(failing.uri())
+            .secret(common::test_secret()),
+    )
+    .unwrap();
+    assert_eq!(
+        pool.labels().collect::<Vec<_>>(),
+        ["alpha", "beta", "gamma"]
+    );
+
+    // Each account signs with its own key and waits on its own limiter.
+    let alpha = pool.get("alpha").unwrap();
+    let beta = pool.get("beta").unwrap();
+    assert_ne!(alpha.verifying_key(), beta.verifying_key());
+    assert!(alpha.rate_limiter().is_some());
+
+    let balances = pool.get_balances().await;
+    assert!(balances["alpha"].is_ok());
+    assert!(balances["beta"].is_ok());
+    assert!(balances["gamma"].is_err());
+
+    let totals = total_balances(&balances);
+    assert_eq!(totals["USDC"].available, dec!(20));
+    assert_eq!(totals["USDC"].total(), dec!(22));
+}
+
+#[tokio::test]
+async fn shared_http_client_sends_the_client_user_agent() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/capital"))
+        .and(header("user-agent", "bpx-rust-client"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut pool =
+        BpxClientPool::with_http_client(reqwest::Client::new()).with_base_url(server.uri());
+    pool.add_account("alpha", common::test_secret()).unwrap();
+
+    let balances = pool.get_balances().await;
+    assert!(balances["alpha"].is_ok());
+}
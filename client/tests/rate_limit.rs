@@ -0,0 +1,52 @@
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bpx_api_client::{BpxClient, rate_limit::RateLimiter};
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+};
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+#[tokio::test]
+async fn signed_requests_are_signed_after_waiting_for_the_rate_limiter() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/capital"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .mount(&mock_server)
+        .await;
+
+    let limiter = RateLimiter::new(1, Duration::from_millis(300));
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .rate_limiter(limiter.clone())
+        .build()
+        .expect("client should build");
+
+    // Another user of the shared limiter takes the whole budget.
+    limiter.acquire().await;
+    let queued_at = now_millis();
+    client.get_balances().await.unwrap();
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let timestamp: i64 = requests[0].headers["x-timestamp"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(
+        timestamp >= queued_at + 250,
+        "request signed at {timestamp}, {}ms after it was queued",
+        timestamp - queued_at
+    );
+}
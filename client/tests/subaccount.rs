@@ -0,0 +1,63 @@
+mod common;
+
+use bpx_api_client::BpxClient;
+use bpx_api_types::history::{FundingPaymentsParams, SettlementHistoryParams};
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{header_exists, method, path},
+};
+
+fn subaccount_ids(request: &wiremock::Request) -> Vec<String> {
+    request
+        .url
+        .query_pairs()
+        .filter(|(key, _)| key == "subaccountId")
+        .map(|(_, value)| value.into_owned())
+        .collect()
+}
+
+#[tokio::test]
+async fn subaccount_scope_is_sent_and_signed() {
+    let mock_server = MockServer::start().await;
+    for endpoint in ["/wapi/v1/history/funding", "/wapi/v1/history/settlement"] {
+        Mock::given(method("GET"))
+            .and(path(endpoint))
+            .and(header_exists("x-signature"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<()>::new()))
+            .mount(&mock_server)
+            .await;
+    }
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .subaccount_id(3)
+        .build()
+        .expect("client should build");
+    assert_eq!(client.subaccount_id(), Some(3));
+
+    client
+        .get_funding_payments(FundingPaymentsParams::default())
+        .await
+        .unwrap();
+    client
+        .get_funding_payments(FundingPaymentsParams::default().with_subaccount_id(5))
+        .await
+        .unwrap();
+    client
+        .with_subaccount(None)
+        .get_funding_payments(FundingPaymentsParams::default())
+        .await
+        .unwrap();
+    client
+        .get_settlement_history(SettlementHistoryParams::default())
+        .await
+        .unwrap();
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let scopes: Vec<_> = requests.iter().map(subaccount_ids).collect();
+    assert_eq!(
+        scopes,
+        [vec!["3".to_string()], vec!["5".to_string()], vec![], vec![]]
+    );
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use bpx_api_client::{
+    BpxClient, Error,
+    types::{order::CancelOpenOrdersPayload, symbol::Symbol},
+};
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{any, method, path, query_param},
+};
+
+#[tokio::test]
+async fn malformed_symbols_fail_before_any_request() {
+    let mock_server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    for symbol in ["", "SOL USDC", "SOLUSDC", "SOL-USDC", "SOL__USDC"] {
+        let err = client.get_ticker(symbol, None).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidRequest(_)), "{symbol}: {err}");
+    }
+    assert!(matches!(
+        client.get_recent_trades("SOL_USDC ", None).await,
+        Err(Error::InvalidRequest(_))
+    ));
+    for symbol in ["SOLUSDC", "SOL-USDC", "SOL_USDC_"] {
+        assert!(matches!(
+            client.get_open_orders(Some(symbol)).await,
+            Err(Error::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            client.get_all_mark_prices(Some(symbol)).await,
+            Err(Error::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            client.get_open_interest(Some(symbol)).await,
+            Err(Error::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            client
+                .cancel_open_orders(CancelOpenOrdersPayload {
+                    symbol: symbol.to_string(),
+                })
+                .await,
+            Err(Error::InvalidRequest(_))
+        ));
+    }
+    assert!(matches!(
+        client
+            .cancel_open_orders(CancelOpenOrdersPayload {
+                symbol: String::new(),
+            })
+            .await,
+        Err(Error::InvalidRequest(_))
+    ));
+}
+
+#[tokio::test]
+async fn optional_symbols_can_be_omitted() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/orders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    let orders = client.get_open_orders(None::<Symbol>).await.unwrap();
+    assert!(orders.is_empty());
+}
+
+#[tokio::test]
+async fn accepts_strings_and_parsed_symbols() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/ticker"))
+        .and(query_param("symbol", "SPCX.US_USDC"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "symbol": "SPCX.US_USDC",
+            "firstPrice": "1",
+            "lastPrice": "1",
+            "priceChange": "0",
+            "priceChangePercent": "0",
+            "high": "1",
+            "low": "1",
+            "volume": "0",
+            "trades": "0"
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    client.get_ticker("SPCX.US_USDC", None).await.unwrap();
+    let symbol: Symbol = "SPCX.US_USDC".parse().unwrap();
+    client.get_ticker(&symbol, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn dated_and_prediction_symbols_reach_the_server() {
+    let mock_server = MockServer::start().await;
+    for symbol in ["BTC_USDC_20261225", "ELECTION_USDC_PREDICTION_YES"] {
+        Mock::given(method("GET"))
+            .and(path("/api/v1/orders"))
+            .and(query_param("symbol", symbol))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+    }
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+
+    for symbol in ["BTC_USDC_20261225", "ELECTION_USDC_PREDICTION_YES"] {
+        let symbol: Symbol = symbol.parse().unwrap();
+        let orders = client.get_open_orders(Some(&symbol)).await.unwrap();
+        assert!(orders.is_empty());
+    }
+}
@@ -0,0 +1,62 @@
+use bpx_api_client::{BpxClient, types::system::SystemStatus};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path, query_param},
+};
+
+#[tokio::test]
+async fn test_health_check_and_system_endpoints() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/status"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": "Maintenance",
+            "message": "Back shortly"
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/ping"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("pong"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/time"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("1735689600000"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/openInterest"))
+        .and(query_param("symbol", "SOL_USDC_PERP"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "symbol": "SOL_USDC_PERP",
+            "openInterest": "1234.5",
+            "timestamp": 1735689600000i64
+        }])))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    client.ping().await.expect("ping should succeed");
+    assert_eq!(client.get_server_time().await.unwrap(), 1735689600000);
+
+    let open_interest = client
+        .get_open_interest(Some("SOL_USDC_PERP"))
+        .await
+        .unwrap();
+    assert_eq!(open_interest[0].open_interest, dec!(1234.5));
+
+    let report = client.health_check().await.unwrap();
+    assert_eq!(report.status, SystemStatus::Maintenance);
+    assert!(!report.is_ok());
+    assert_eq!(report.message.as_deref(), Some("Back shortly"));
+    assert_eq!(report.server_time.timestamp_millis(), 1735689600000);
+    // The mock serves a fixed time in the past.
+    assert!(report.clock_offset.num_days() < 0);
+}
@@ -0,0 +1,57 @@
+use bpx_api_client::{BpxClient, types::markets::TickerInterval};
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path, query_param},
+};
+
+fn ticker(symbol: &str, trades: &str) -> serde_json::Value {
+    json!({
+        "symbol": symbol,
+        "firstPrice": "1",
+        "lastPrice": "1",
+        "priceChange": "0",
+        "priceChangePercent": "0",
+        "high": "1",
+        "low": "1",
+        "volume": "0",
+        "trades": trades
+    })
+}
+
+#[tokio::test]
+async fn test_get_tickers_for_symbols_with_interval() {
+    let mock_server = MockServer::start().await;
+    for (symbol, trades) in [("SOL_USDC", "12"), ("BTC_USDC", "34")] {
+        Mock::given(method("GET"))
+            .and(path("/api/v1/ticker"))
+            .and(query_param("symbol", symbol))
+            .and(query_param("interval", "1w"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ticker(symbol, trades)))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+    }
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .build()
+        .expect("client should build");
+
+    let tickers = client
+        .get_tickers_for(["SOL_USDC", "BTC_USDC"], Some(TickerInterval::OneWeek))
+        .await
+        .unwrap();
+    assert_eq!(tickers.len(), 2);
+    assert_eq!(tickers[0].symbol, "SOL_USDC");
+    assert_eq!(tickers[0].trades, 12);
+    assert_eq!(tickers[1].trades, 34);
+
+    // Malformed symbols fail before any request is sent.
+    assert!(
+        client
+            .get_tickers_for(["SOL_USDC", "sol-usdc"], None)
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,175 @@
+#![cfg(feature = "runtime")]
+
+mod common;
+
+use std::time::Duration;
+
+use bpx_api_client::{
+    BpxClient,
+    transfer_watcher::{TransferEvent, TransferWatcher, TransferWatcherConfig, WithdrawalKey},
+};
+use bpx_api_types::capital::{DepositStatus, WithdrawalStatus};
+use serde_json::json;
+use tokio::sync::mpsc;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+};
+
+fn withdrawal(status: &str) -> serde_json::Value {
+    json!([{
+        "id": 7,
+        "blockchain": "Solana",
+        "clientId": "treasury-7",
+        "quantity": "10",
+        "fee": "0.01",
+        "symbol": "USDC",
+        "status": status,
+        "toAddress": "addr",
+        "createdAt": "2025-01-01T00:00:00",
+    }])
+}
+
+fn deposit(status: &str) -> serde_json::Value {
+    json!([{
+        "id": 3,
+        "transactionHash": "0xabc",
+        "source": "ethereum",
+        "status": status,
+        "symbol": "USDC",
+        "quantity": "25",
+        "createdAt": "2025-01-01T00:00:00",
+    }])
+}
+
+async fn next_event(rx: &mut mpsc::Receiver<TransferEvent>) -> TransferEvent {
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("watcher should emit an event")
+        .expect("watcher should still be running")
+}
+
+#[tokio::test]
+async fn transfer_watcher_reports_transitions_and_timeouts() {
+    let mock_server = MockServer::start().await;
+    // The first poll sees both transfers pending, later polls see them settled.
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/capital/withdrawals"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(withdrawal("pending")))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/capital/withdrawals"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(withdrawal("confirmed")))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/capital/deposits"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(deposit("pending")))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+    let config = TransferWatcherConfig::new()
+        .with_poll_interval(Duration::from_millis(20))
+        .with_deposit_timeout(Duration::from_millis(200));
+    let (tx, mut rx) = mpsc::channel(16);
+    let watcher = TransferWatcher::spawn(client, config, tx);
+    watcher.watch_withdrawal_key(WithdrawalKey::ClientId("treasury-7".into()));
+    watcher.watch_deposit("0xabc");
+
+    let mut withdrawal_statuses = Vec::new();
+    let mut deposit_statuses = Vec::new();
+    let mut timed_out = None;
+    while timed_out.is_none() {
+        match next_event(&mut rx).await {
+            TransferEvent::Withdrawal {
+                previous,
+                withdrawal,
+                ..
+            } => withdrawal_statuses.push((previous, withdrawal.status)),
+            TransferEvent::Deposit {
+                previous, deposit, ..
+            } => deposit_statuses.push((previous, deposit.status)),
+            TransferEvent::DepositTimedOut {
+                transaction_hash,
+                last,
+            } => timed_out = Some((transaction_hash, last)),
+            other => panic!("unexpected event {other:?}"),
+        }
+    }
+
+    assert_eq!(
+        withdrawal_statuses,
+        [
+            (None, WithdrawalStatus::Pending),
+            (Some(WithdrawalStatus::Pending), WithdrawalStatus::Confirmed),
+        ]
+    );
+    assert_eq!(deposit_statuses, [(None, DepositStatus::Pending)]);
+    assert_eq!(
+        timed_out,
+        Some(("0xabc".to_string(), Some(DepositStatus::Pending)))
+    );
+
+    watcher.shutdown();
+    watcher.join().await;
+}
+
+#[tokio::test]
+async fn transfer_watcher_keeps_polling_while_consumer_is_slow() {
+    let mock_server = MockServer::start().await;
+    for status in ["pending", "verifying"] {
+        Mock::given(method("GET"))
+            .and(path("/wapi/v1/capital/withdrawals"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(withdrawal(status)))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/capital/withdrawals"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(withdrawal("void")))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/wapi/v1/capital/deposits"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(deposit("confirmed")))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+    let config = TransferWatcherConfig::new().with_poll_interval(Duration::from_millis(10));
+    let (tx, mut rx) = mpsc::channel(1);
+    let watcher = TransferWatcher::spawn(client, config, tx);
+    watcher.watch_withdrawal_key(WithdrawalKey::Id(7));
+
+    // Nothing is read until every withdrawal poll has happened.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    // Commands are still handled while events wait for the consumer.
+    watcher.watch_deposit("0xabc");
+
+    let mut statuses = Vec::new();
+    while statuses.len() < 4 {
+        match next_event(&mut rx).await {
+            TransferEvent::Withdrawal { withdrawal, .. } => {
+                statuses.push(withdrawal.status.to_string())
+            }
+            TransferEvent::Deposit { deposit, .. } => statuses.push(deposit.status.to_string()),
+            other => panic!("unexpected event {other:?}"),
+        }
+    }
+    assert_eq!(statuses, ["pending", "verifying", "void", "confirmed"]);
+
+    watcher.shutdown();
+    watcher.join().await;
+}
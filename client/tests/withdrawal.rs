@@ -0,0 +1,137 @@
+mod common;
+
+use bpx_api_client::{
+    BpxClient,
+    withdrawal::{AddressAllowlist, WithdrawalWorkflow},
+};
+use bpx_api_types::{Blockchain, capital::RequestWithdrawalPayload, user::RequestTwoFactorPayload};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{body_partial_json, method, path, query_param},
+};
+
+const ADDRESS: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+async fn mock_exchange() -> (MockServer, BpxClient) {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/assets"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "symbol": "USDC",
+            "displayName": "USD Coin",
+            "tokens": [{
+                "blockchain": "Solana",
+                "contractAddress": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                "depositEnabled": true,
+                "displayName": "USDC",
+                "minimumDeposit": "1",
+                "withdrawEnabled": true,
+                "minimumWithdrawal": "5",
+                "maximumWithdrawal": null,
+                "withdrawalFee": "1",
+            }],
+        }])))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/account/limits/withdrawal"))
+        .and(query_param("symbol", "USDC"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "maxWithdrawalQuantity": "50",
+            "symbol": "USDC",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let client = BpxClient::builder()
+        .base_url(mock_server.uri())
+        .secret(common::test_secret())
+        .build()
+        .expect("client should build");
+    (mock_server, client)
+}
+
+fn payload(quantity: rust_decimal::Decimal) -> RequestWithdrawalPayload {
+    RequestWithdrawalPayload {
+        address: ADDRESS.to_string(),
+        blockchain: Blockchain::Solana,
+        quantity,
+        symbol: "USDC".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn withdrawal_workflow_attaches_two_factor_token() {
+    let (mock_server, client) = mock_exchange().await;
+    Mock::given(method("POST"))
+        .and(path("/wapi/v1/user/2fa"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "signature": "2fa-token" })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/wapi/v1/capital/withdrawals"))
+        .and(body_partial_json(json!({
+            "address": ADDRESS,
+            "quantity": "20",
+            "twoFactorToken": "2fa-token",
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 11,
+            "blockchain": "Solana",
+            "quantity": "20",
+            "fee": "1",
+            "symbol": "USDC",
+            "status": "pending",
+            "toAddress": ADDRESS,
+            "createdAt": "2025-01-01T00:00:00",
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let allowlist = AddressAllowlist::new()
+        .with_address(Blockchain::Solana, ADDRESS)
+        .unwrap();
+    let workflow =
+        WithdrawalWorkflow::new(client, allowlist).with_two_factor(RequestTwoFactorPayload {
+            app: Some("authenticator".to_string()),
+            email: None,
+        });
+    let withdrawal = workflow.submit(payload(dec!(20))).await.unwrap();
+    assert_eq!(withdrawal.id, 11);
+}
+
+#[tokio::test]
+async fn withdrawal_workflow_rejects_before_submitting() {
+    let (mock_server, client) = mock_exchange().await;
+    Mock::given(method("POST"))
+        .and(path("/wapi/v1/capital/withdrawals"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let workflow = WithdrawalWorkflow::new(client.clone(), AddressAllowlist::new());
+    let error = workflow.submit(payload(dec!(20))).await.unwrap_err();
+    assert!(error.to_string().contains("allowlist"), "{error}");
+
+    let allowlist = AddressAllowlist::new()
+        .with_address(Blockchain::Solana, ADDRESS)
+        .unwrap();
+    let workflow = WithdrawalWorkflow::new(client, allowlist);
+    let error = workflow.submit(payload(dec!(60))).await.unwrap_err();
+    assert!(
+        error.to_string().contains("exceeds the available 50"),
+        "{error}"
+    );
+    let error = workflow.submit(payload(dec!(2))).await.unwrap_err();
+    assert!(error.to_string().contains("minimum"), "{error}");
+
+    let preflight = workflow.preflight(&payload(dec!(20))).await.unwrap();
+    assert_eq!(preflight.fee(), dec!(1));
+    assert_eq!(preflight.max_withdrawal_quantity, dec!(50));
+}
@@ -58,6 +58,13 @@ pub enum DepositStatus {
     Unknown,
 }
 
+impl DepositStatus {
+    /// Returns `true` once a deposit can no longer change status.
+    pub const fn is_final(self) -> bool {
+        matches!(self, Self::Confirmed)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DepositAddress {
@@ -114,6 +121,13 @@ pub enum WithdrawalStatus {
     Unknown,
 }
 
+impl WithdrawalStatus {
+    /// Returns `true` once a withdrawal can no longer change status.
+    pub const fn is_final(self) -> bool {
+        matches!(self, Self::Confirmed | Self::Void)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Collateral {
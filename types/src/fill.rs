@@ -1,5 +1,11 @@
-use super::{history::SortDirection, order::Side};
+use super::{
+    history::SortDirection,
+    markets::MarketType,
+    order::{Side, SystemOrderType},
+};
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer};
 
 #[derive(
     Debug,
@@ -30,7 +36,8 @@ pub enum FillType {
 #[serde(rename_all = "camelCase")]
 pub struct Fill {
     pub trade_id: Option<i64>,
-    pub client_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_client_id")]
+    pub client_id: Option<u32>,
     pub order_id: String,
     pub symbol: String,
     pub fee_symbol: String,
@@ -38,9 +45,49 @@ pub struct Fill {
     pub quantity: Decimal,
     pub fee: Decimal,
     pub side: Side,
-    pub timestamp: String,
+    /// Time of the fill. The API sends it in UTC without an offset.
+    #[serde(with = "crate::timestamp")]
+    pub timestamp: DateTime<Utc>,
     pub is_maker: bool,
-    pub system_order_type: Option<String>,
+    #[serde(default)]
+    pub system_order_type: Option<SystemOrderType>,
+}
+
+impl Fill {
+    /// Whether the fill comes from the liquidation of a position, on the book,
+    /// by the backstop or by auto-deleveraging.
+    pub fn is_liquidation(&self) -> bool {
+        matches!(
+            self.system_order_type,
+            Some(
+                SystemOrderType::LiquidatePositionOnBook
+                    | SystemOrderType::LiquidatePositionOnBackstop
+                    | SystemOrderType::LiquidatePositionOnAdl
+            )
+        )
+    }
+}
+
+/// Client ids of fills are sent as strings, while orders use integers.
+fn deserialize_client_id<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ClientId {
+        Integer(u32),
+        String(String),
+    }
+
+    match Option::<ClientId>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(ClientId::Integer(client_id)) => Ok(Some(client_id)),
+        Some(ClientId::String(client_id)) => client_id
+            .parse()
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
@@ -59,7 +106,7 @@ pub struct FillsHistoryParams {
     pub fill_type: Option<FillType>,
     /// Filter by market type
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub market_type: Option<String>,
+    pub market_type: Option<MarketType>,
     /// Filter by order ID
     #[serde(skip_serializing_if = "Option::is_none")]
     pub order_id: Option<String>,
@@ -98,7 +145,7 @@ impl FillsHistoryParams {
         self
     }
 
-    pub fn with_market_type(mut self, market_type: String) -> Self {
+    pub fn with_market_type(mut self, market_type: MarketType) -> Self {
         self.market_type = Some(market_type);
         self
     }
@@ -133,6 +180,35 @@ impl FillsHistoryParams {
 mod tests {
     use super::*;
 
+    #[test]
+    fn fill_parses_timestamp_client_id_and_system_order_type() {
+        let fill: Fill = serde_json::from_str(
+            r#"{"tradeId":1,"clientId":"42","orderId":"1","symbol":"SOL_USDC_PERP",
+                "feeSymbol":"USDC","price":"1","quantity":"1","fee":"0","side":"Ask",
+                "timestamp":"2025-01-01T00:00:00.123","isMaker":false,
+                "systemOrderType":"LiquidatePositionOnBook"}"#,
+        )
+        .unwrap();
+        assert_eq!(fill.client_id, Some(42));
+        assert_eq!(fill.timestamp.timestamp_millis(), 1735689600123);
+        assert!(fill.is_liquidation());
+
+        let json = serde_json::to_value(&fill).unwrap();
+        assert_eq!(json["timestamp"], "2025-01-01 00:00:00.123");
+        assert_eq!(serde_json::from_value::<Fill>(json).unwrap(), fill);
+
+        let fill: Fill = serde_json::from_str(
+            r#"{"tradeId":null,"clientId":null,"orderId":"1","symbol":"SOL_USDC",
+                "feeSymbol":"USDC","price":"1","quantity":"1","fee":"0","side":"Ask",
+                "timestamp":"2025-01-01T00:00:00","isMaker":false,
+                "systemOrderType":"SomethingNew"}"#,
+        )
+        .unwrap();
+        assert_eq!(fill.client_id, None);
+        assert_eq!(fill.system_order_type, Some(SystemOrderType::Unknown));
+        assert!(!fill.is_liquidation());
+    }
+
     #[test]
     fn fills_history_params_omits_none_fields_in_query_string() {
         let params = FillsHistoryParams::default()
@@ -1,3 +1,9 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::timestamp;
+
 #[derive(
     Debug,
     strum::Display,
@@ -16,3 +22,472 @@ pub enum SortDirection {
     Asc,
     Desc,
 }
+
+/// Source of an interest payment.
+#[derive(
+    Debug,
+    strum::Display,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::EnumString,
+    PartialEq,
+    Eq,
+    Hash,
+)]
+#[strum(serialize_all = "PascalCase")]
+#[serde(rename_all = "PascalCase")]
+pub enum InterestSource {
+    /// Interest on borrow and lend positions.
+    BorrowLend,
+    /// Interest on unrealized PnL.
+    UnrealizedPnl,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Kind of an interest payment.
+#[derive(
+    Debug,
+    strum::Display,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::EnumString,
+    PartialEq,
+    Eq,
+    Hash,
+)]
+#[strum(serialize_all = "PascalCase")]
+#[serde(rename_all = "PascalCase")]
+pub enum InterestPaymentType {
+    EntryFee,
+    Borrow,
+    Lend,
+    UnrealizedPositivePnl,
+    UnrealizedNegativePnl,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Source of a PnL settlement.
+#[derive(
+    Debug,
+    strum::Display,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::EnumString,
+    PartialEq,
+    Eq,
+    Hash,
+)]
+#[strum(serialize_all = "PascalCase")]
+#[serde(rename_all = "PascalCase")]
+pub enum SettlementSource {
+    BackstopLiquidation,
+    CulledBorrowInterest,
+    CulledRealizePnl,
+    CulledRealizePnlBookUtilization,
+    FundingPayment,
+    RealizePnl,
+    TradingFees,
+    TradingFeesSystem,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Side of a borrow/lend movement.
+#[derive(
+    Debug,
+    strum::Display,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::EnumString,
+    PartialEq,
+    Eq,
+    Hash,
+)]
+#[strum(serialize_all = "PascalCase")]
+#[serde(rename_all = "PascalCase")]
+pub enum BorrowLendSide {
+    Borrow,
+    Lend,
+}
+
+/// Kind of a borrow/lend movement.
+#[derive(
+    Debug,
+    strum::Display,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::EnumString,
+    PartialEq,
+    Eq,
+    Hash,
+)]
+#[strum(serialize_all = "PascalCase")]
+#[serde(rename_all = "PascalCase")]
+pub enum BorrowLendEventType {
+    Borrow,
+    BorrowRepay,
+    Lend,
+    LendRedeem,
+    #[serde(other)]
+    Unknown,
+}
+
+/// What caused a borrow/lend movement.
+#[derive(
+    Debug,
+    strum::Display,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::EnumString,
+    PartialEq,
+    Eq,
+    Hash,
+)]
+#[strum(serialize_all = "PascalCase")]
+#[serde(rename_all = "PascalCase")]
+pub enum BorrowLendSource {
+    AdlProvider,
+    AutoBorrowRepay,
+    AutoLend,
+    BackstopProvider,
+    Interest,
+    Liquidation,
+    LiquidationAdl,
+    LiquidationBackstop,
+    Manual,
+    Reconciliation,
+    SpotMargin,
+    Withdrawal,
+    #[serde(other)]
+    Unknown,
+}
+
+/// A funding payment paid or received on a perpetual position.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundingPayment {
+    pub user_id: i64,
+    pub subaccount_id: Option<u64>,
+    pub symbol: String,
+    /// Amount received, negative when paid.
+    pub quantity: Decimal,
+    /// End of the funding interval.
+    #[serde(with = "timestamp")]
+    pub interval_end_timestamp: DateTime<Utc>,
+    pub funding_rate: Decimal,
+}
+
+/// Parameters for fetching funding payments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundingPaymentsParams {
+    /// Filter for a subaccount.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subaccount_id: Option<u64>,
+    /// Filter by market symbol.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Maximum number to return. Default 100, maximum 1000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Offset. Default 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_direction: Option<SortDirection>,
+}
+
+impl FundingPaymentsParams {
+    pub fn with_subaccount_id(mut self, subaccount_id: u64) -> Self {
+        self.subaccount_id = Some(subaccount_id);
+        self
+    }
+
+    pub fn with_symbol<S: Into<String>>(mut self, symbol: S) -> Self {
+        self.symbol = Some(symbol.into());
+        self
+    }
+
+    pub fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn with_sort_direction(mut self, sort_direction: SortDirection) -> Self {
+        self.sort_direction = Some(sort_direction);
+        self
+    }
+}
+
+/// An interest payment on a borrow/lend position or on unrealized PnL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterestPayment {
+    pub payment_type: InterestPaymentType,
+    pub interest_rate: Decimal,
+    /// Length of the interest interval in milliseconds.
+    pub interval: u64,
+    pub market_symbol: String,
+    pub position_id: String,
+    /// Amount received, negative when paid.
+    pub quantity: Decimal,
+    /// Asset the interest is paid in.
+    pub symbol: String,
+    #[serde(with = "timestamp")]
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Parameters for fetching interest payments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterestHistoryParams {
+    /// Filter for a subaccount.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subaccount_id: Option<u64>,
+    /// Filter by the asset the interest is paid in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>,
+    /// Filter by market symbol.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Filter by position.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position_id: Option<String>,
+    /// Filter by the source of the interest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<InterestSource>,
+    /// Maximum number to return. Default 100, maximum 1000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Offset. Default 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_direction: Option<SortDirection>,
+}
+
+impl InterestHistoryParams {
+    pub fn with_subaccount_id(mut self, subaccount_id: u64) -> Self {
+        self.subaccount_id = Some(subaccount_id);
+        self
+    }
+
+    pub fn with_asset<S: Into<String>>(mut self, asset: S) -> Self {
+        self.asset = Some(asset.into());
+        self
+    }
+
+    pub fn with_symbol<S: Into<String>>(mut self, symbol: S) -> Self {
+        self.symbol = Some(symbol.into());
+        self
+    }
+
+    pub fn with_position_id<S: Into<String>>(mut self, position_id: S) -> Self {
+        self.position_id = Some(position_id.into());
+        self
+    }
+
+    pub fn with_source(mut self, source: InterestSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn with_sort_direction(mut self, sort_direction: SortDirection) -> Self {
+        self.sort_direction = Some(sort_direction);
+        self
+    }
+}
+
+/// A settlement of PnL, fees or funding into the account balance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settlement {
+    pub user_id: i64,
+    pub subaccount_id: Option<u64>,
+    /// Amount credited, negative when debited.
+    pub quantity: Decimal,
+    pub source: SettlementSource,
+    #[serde(with = "timestamp")]
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Parameters for fetching settlements.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettlementHistoryParams {
+    /// Filter by the source of the settlement.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<SettlementSource>,
+    /// Maximum number to return. Default 100, maximum 1000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Offset. Default 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_direction: Option<SortDirection>,
+}
+
+impl SettlementHistoryParams {
+    pub fn with_source(mut self, source: SettlementSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn with_sort_direction(mut self, sort_direction: SortDirection) -> Self {
+        self.sort_direction = Some(sort_direction);
+        self
+    }
+}
+
+/// A change of a borrow/lend position.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BorrowLendMovement {
+    pub event_type: BorrowLendEventType,
+    pub position_id: String,
+    /// Quantity of the position after the movement.
+    pub position_quantity: Decimal,
+    pub quantity: Decimal,
+    pub source: BorrowLendSource,
+    pub symbol: String,
+    #[serde(with = "timestamp")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub spot_margin_order_id: Option<String>,
+}
+
+/// Parameters for fetching borrow/lend movements.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BorrowLendHistoryParams {
+    /// Filter by side.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub side: Option<BorrowLendSide>,
+    /// Filter by sources, comma separated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources: Option<String>,
+    /// Filter by position.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position_id: Option<String>,
+    /// Filter by asset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Maximum number to return. Default 100, maximum 1000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Offset. Default 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_direction: Option<SortDirection>,
+}
+
+impl BorrowLendHistoryParams {
+    pub fn with_side(mut self, side: BorrowLendSide) -> Self {
+        self.side = Some(side);
+        self
+    }
+
+    pub fn with_sources(mut self, sources: impl IntoIterator<Item = BorrowLendSource>) -> Self {
+        let sources: Vec<_> = sources
+            .into_iter()
+            .map(|source| source.to_string())
+            .collect();
+        self.sources = Some(sources.join(","));
+        self
+    }
+
+    pub fn with_position_id<S: Into<String>>(mut self, position_id: S) -> Self {
+        self.position_id = Some(position_id.into());
+        self
+    }
+
+    pub fn with_symbol<S: Into<String>>(mut self, symbol: S) -> Self {
+        self.symbol = Some(symbol.into());
+        self
+    }
+
+    pub fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn with_sort_direction(mut self, sort_direction: SortDirection) -> Self {
+        self.sort_direction = Some(sort_direction);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrow_lend_params_use_api_names() {
+        let params = BorrowLendHistoryParams::default()
+            .with_side(BorrowLendSide::Lend)
+            .with_sources([BorrowLendSource::AutoLend, BorrowLendSource::Manual])
+            .with_position_id("7")
+            .with_sort_direction(SortDirection::Desc);
+        assert_eq!(
+            serde_qs::to_string(&params).unwrap(),
+            "type=Lend&sources=AutoLend,Manual&positionId=7&sortDirection=Desc"
+        );
+    }
+
+    #[test]
+    fn history_records_parse_unknown_sources() {
+        let settlement: Settlement = serde_json::from_str(
+            r#"{"userId":1,"subaccountId":null,"quantity":"-0.5","source":"NewSource",
+                "timestamp":"2025-01-01T00:00:00"}"#,
+        )
+        .unwrap();
+        assert_eq!(settlement.source, SettlementSource::Unknown);
+        assert_eq!(settlement.timestamp.timestamp(), 1735689600);
+    }
+}
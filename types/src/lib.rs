@@ -16,10 +16,14 @@ pub mod margin;
 pub mod markets;
 pub mod order;
 pub mod rfq;
+pub mod symbol;
+pub mod system;
 pub mod trade;
 pub mod user;
 pub mod vault;
 
+mod timestamp;
+
 #[derive(
     Debug,
     Display,
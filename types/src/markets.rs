@@ -1,11 +1,12 @@
+use chrono::{DateTime, Datelike, Months, NaiveTime, TimeDelta, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::{Blockchain, margin::MarginFunction};
+use crate::{Blockchain, margin::MarginFunction, timestamp};
 
 /// An asset is most of the time a crypto coin that can have multiple representations
 /// across different blockchains. For example, USDT.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Asset {
     /// CoinGecko ID for price tracking
@@ -18,7 +19,7 @@ pub struct Asset {
     pub tokens: Vec<Token>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Ticker {
     pub symbol: String,
@@ -29,7 +30,35 @@ pub struct Ticker {
     pub high: Decimal,
     pub low: Decimal,
     pub volume: Decimal,
-    pub trades: String,
+    /// Number of trades in the window.
+    // Sent as a string by the REST API.
+    #[serde(deserialize_with = "deserialize_str_or_u64")]
+    pub trades: u64,
+}
+
+/// The rolling window of a [`Ticker`].
+#[derive(
+    Debug,
+    strum::Display,
+    Clone,
+    Copy,
+    Default,
+    Serialize,
+    Deserialize,
+    strum::EnumString,
+    strum::AsRefStr,
+    PartialEq,
+    Eq,
+    Hash,
+)]
+pub enum TickerInterval {
+    #[default]
+    #[serde(rename = "1d")]
+    #[strum(serialize = "1d")]
+    OneDay,
+    #[serde(rename = "1w")]
+    #[strum(serialize = "1w")]
+    OneWeek,
 }
 
 /// Sent by an exchange to indicate a change in the order book, such as the execution of a bid or ask.
@@ -71,7 +100,7 @@ pub struct TickerUpdate {
 
 /// A market is where two assets are exchanged. Most notably, in a `BTC/USDC` pair
 /// `BTC` is the base and `USDC` is the quote.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Market {
     /// The `Market` identifier.
@@ -80,9 +109,8 @@ pub struct Market {
     pub base_symbol: String,
     /// The quote asset for the market.
     pub quote_symbol: String,
-    /// The type of the market. Can be `SPOT`, `PERP`, `IPERP`, `DATED`, `PREDICTION`, `RFQ` or `MONAD`.
-    /// New market types may also be added in the future.
-    pub market_type: String,
+    /// The type of the market. See [`MarketType`].
+    pub market_type: MarketType,
     /// The real-world-asset type backing the market, when the market is a
     /// tokenized RWA (e.g. `STOCK` for tokenized equities such as
     /// `SPCX.US_USDC`). `None` for regular crypto markets.
@@ -126,6 +154,59 @@ impl Market {
     pub const fn quantity_decimal_places(&self) -> u32 {
         self.filters.quantity.step_size.scale()
     }
+
+    /// Returns `true` for spot markets.
+    pub fn is_spot(&self) -> bool {
+        self.market_type == MarketType::Spot
+    }
+
+    /// Returns `true` for perpetual futures markets, including `IPERP` markets.
+    pub fn is_perp(&self) -> bool {
+        matches!(self.market_type, MarketType::Perp | MarketType::Iperp)
+    }
+
+    /// Returns `true` for prediction markets.
+    pub fn is_prediction(&self) -> bool {
+        self.market_type == MarketType::Prediction
+    }
+}
+
+/// The type of a market.
+///
+/// New types may be added by the exchange in the future; unrecognized values
+/// deserialize to [`MarketType::Unknown`].
+#[derive(
+    Debug,
+    strum::Display,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::EnumString,
+    PartialEq,
+    Eq,
+    Hash,
+)]
+#[strum(serialize_all = "UPPERCASE")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MarketType {
+    /// Spot market.
+    Spot,
+    /// Perpetual futures market.
+    Perp,
+    /// `IPERP` perpetual futures market.
+    Iperp,
+    /// Dated futures market.
+    Dated,
+    /// Prediction market.
+    Prediction,
+    /// Market only tradable through RFQs.
+    Rfq,
+    /// Monad market.
+    Monad,
+    /// Any type not recognized by this client version.
+    #[serde(other)]
+    Unknown,
 }
 
 /// The state of a market's order book.
@@ -194,7 +275,7 @@ pub enum RwaMarketType {
     Unknown,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MarketFilters {
     pub price: PriceFilter,
@@ -202,7 +283,7 @@ pub struct MarketFilters {
     pub leverage: Option<LeverageFilter>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PriceFilter {
     /// Minimum price the order book will allow.
@@ -244,7 +325,7 @@ pub struct PriceFilter {
     pub min_price_update_multiplier: Option<Decimal>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PriceBandMarkPrice {
     /// Maximum allowed multiplier move from mean price.
@@ -253,7 +334,7 @@ pub struct PriceBandMarkPrice {
     pub min_multiplier: Decimal,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PriceBandPremium {
     /// Latest index price.
@@ -271,7 +352,7 @@ pub struct PriceBandPremium {
     pub tolerance_pct: Decimal,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PriceBandDiscoveryBound {
     /// Maximum allowed multiplier move from the external oracle price.
@@ -280,7 +361,7 @@ pub struct PriceBandDiscoveryBound {
     pub min_multiplier: Decimal,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QuantityFilter {
     pub min_quantity: Decimal,
@@ -288,7 +369,7 @@ pub struct QuantityFilter {
     pub step_size: Decimal,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LeverageFilter {
     pub min_leverage: Decimal,
@@ -296,7 +377,7 @@ pub struct LeverageFilter {
     pub step_size: Decimal,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Token {
     pub blockchain: Blockchain,
@@ -335,7 +416,7 @@ pub enum OrderBookDepthLimit {
     OneThousand,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OrderBookDepth {
     /// Resting limit orders on ask side, listed as price-quantity pairs
@@ -389,17 +470,199 @@ pub struct OrderBookDepthUpdate {
     pub bids: Vec<(Decimal, Decimal)>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The interval of a K-line (candlestick).
+#[derive(
+    Debug,
+    strum::Display,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    strum::EnumString,
+    strum::AsRefStr,
+    strum::EnumIter,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+)]
+pub enum KlineInterval {
+    #[serde(rename = "1m")]
+    #[strum(serialize = "1m")]
+    OneMinute,
+    #[serde(rename = "3m")]
+    #[strum(serialize = "3m")]
+    ThreeMinutes,
+    #[serde(rename = "5m")]
+    #[strum(serialize = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    #[strum(serialize = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "30m")]
+    #[strum(serialize = "30m")]
+    ThirtyMinutes,
+    #[serde(rename = "1h")]
+    #[strum(serialize = "1h")]
+    OneHour,
+    #[serde(rename = "2h")]
+    #[strum(serialize = "2h")]
+    TwoHours,
+    #[serde(rename = "4h")]
+    #[strum(serialize = "4h")]
+    FourHours,
+    #[serde(rename = "6h")]
+    #[strum(serialize = "6h")]
+    SixHours,
+    #[serde(rename = "8h")]
+    #[strum(serialize = "8h")]
+    EightHours,
+    #[serde(rename = "12h")]
+    #[strum(serialize = "12h")]
+    TwelveHours,
+    #[serde(rename = "1d")]
+    #[strum(serialize = "1d")]
+    OneDay,
+    #[serde(rename = "3d")]
+    #[strum(serialize = "3d")]
+    ThreeDays,
+    #[serde(rename = "1w")]
+    #[strum(serialize = "1w")]
+    OneWeek,
+    #[serde(rename = "1month")]
+    #[strum(serialize = "1month")]
+    OneMonth,
+}
+
+impl KlineInterval {
+    /// Returns the length of one K-line, or `None` for [`KlineInterval::OneMonth`]
+    /// whose length depends on the month.
+    pub fn duration(&self) -> Option<TimeDelta> {
+        let seconds = match self {
+            Self::OneMinute => 60,
+            Self::ThreeMinutes => 3 * 60,
+            Self::FiveMinutes => 5 * 60,
+            Self::FifteenMinutes => 15 * 60,
+            Self::ThirtyMinutes => 30 * 60,
+            Self::OneHour => 3600,
+            Self::TwoHours => 2 * 3600,
+            Self::FourHours => 4 * 3600,
+            Self::SixHours => 6 * 3600,
+            Self::EightHours => 8 * 3600,
+            Self::TwelveHours => 12 * 3600,
+            Self::OneDay => 86400,
+            Self::ThreeDays => 3 * 86400,
+            Self::OneWeek => 7 * 86400,
+            Self::OneMonth => return None,
+        };
+        Some(TimeDelta::seconds(seconds))
+    }
+
+    /// Returns the start of the K-line containing `time`. K-lines are aligned to
+    /// the Unix epoch, except weekly K-lines which start on Mondays and monthly
+    /// K-lines which start on the first day of the month, all in UTC.
+    pub fn floor(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let seconds = time.timestamp();
+        match self {
+            Self::OneMonth => time
+                .date_naive()
+                .with_day(1)
+                .expect("every month has a first day")
+                .and_time(NaiveTime::MIN)
+                .and_utc(),
+            // The Unix epoch was a Thursday, the first Monday is four days later.
+            Self::OneWeek => {
+                let offset = 4 * 86400;
+                let length = 7 * 86400;
+                timestamp_seconds(seconds - (seconds - offset).rem_euclid(length))
+            }
+            _ => {
+                let length = self
+                    .duration()
+                    .expect("fixed length interval")
+                    .num_seconds();
+                timestamp_seconds(seconds - seconds.rem_euclid(length))
+            }
+        }
+    }
+
+    /// Returns the start of the K-line following the one starting at `start`.
+    pub fn next(&self, start: DateTime<Utc>) -> DateTime<Utc> {
+        match self.duration() {
+            Some(duration) => start + duration,
+            None => start
+                .checked_add_months(Months::new(1))
+                .expect("timestamp in range"),
+        }
+    }
+
+    /// Returns the number of K-lines starting in `[start, end)`.
+    pub fn count(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> u64 {
+        let mut first = self.floor(start);
+        if first < start {
+            first = self.next(first);
+        }
+        if first >= end {
+            return 0;
+        }
+        match self.duration() {
+            Some(duration) => {
+                let span = (end - first).num_seconds();
+                let length = duration.num_seconds();
+                ((span + length - 1) / length) as u64
+            }
+            None => {
+                let months =
+                    (end.year() - first.year()) * 12 + end.month() as i32 - first.month() as i32;
+                let last = first
+                    .checked_add_months(Months::new(months as u32))
+                    .expect("timestamp in range");
+                months as u64 + u64::from(last < end)
+            }
+        }
+    }
+}
+
+/// A K-line (candlestick) returned by the REST API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Kline {
-    pub start: String,
+    #[serde(with = "timestamp")]
+    pub start: DateTime<Utc>,
     pub open: Option<Decimal>,
     pub high: Option<Decimal>,
     pub low: Option<Decimal>,
     pub close: Option<Decimal>,
-    pub end: Option<String>,
+    #[serde(default, with = "timestamp::option")]
+    pub end: Option<DateTime<Utc>>,
     pub volume: Decimal,
-    pub trades: String,
+    /// Number of trades in the K-line.
+    // Sent as a string by the REST API.
+    #[serde(deserialize_with = "deserialize_str_or_u64")]
+    pub trades: u64,
+}
+
+impl TryFrom<&KlineUpdate> for Kline {
+    type Error = &'static str;
+
+    /// Converts a closed K-line update so live K-lines can be merged with K-lines
+    /// fetched from the REST API.
+    fn try_from(update: &KlineUpdate) -> Result<Self, Self::Error> {
+        if !update.is_closed {
+            return Err("KlineUpdate is not closed");
+        }
+        Ok(Kline {
+            start: update.start_time,
+            open: Some(update.open_price),
+            high: Some(update.high_price),
+            low: Some(update.low_price),
+            close: Some(update.close_price),
+            end: Some(update.end_time),
+            volume: update.volume,
+            trades: update.trades,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -416,13 +679,13 @@ pub struct KlineUpdate {
     #[serde(rename = "s")]
     pub symbol: String,
 
-    /// K-Line start time in seconds
-    #[serde(rename = "t")]
-    pub start_time: i64,
+    /// K-Line start time
+    #[serde(rename = "t", with = "timestamp::seconds")]
+    pub start_time: DateTime<Utc>,
 
-    /// K-Line end time in seconds
-    #[serde(rename = "T")]
-    pub end_time: i64,
+    /// K-Line end time
+    #[serde(rename = "T", with = "timestamp::seconds")]
+    pub end_time: DateTime<Utc>,
 
     /// Open price
     #[serde(rename = "o")]
@@ -457,7 +720,9 @@ pub struct KlineUpdate {
 #[serde(rename_all = "camelCase")]
 pub struct FundingRate {
     pub symbol: String,
-    pub interval_end_timestamp: String,
+    /// End of the funding interval.
+    #[serde(with = "timestamp")]
+    pub interval_end_timestamp: DateTime<Utc>,
     pub funding_rate: Decimal,
 }
 
@@ -471,6 +736,16 @@ pub struct MarkPrice {
     pub next_funding_timestamp: u64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenInterest {
+    pub symbol: String,
+    /// Open interest in the base asset.
+    pub open_interest: Decimal,
+    /// Timestamp in milliseconds
+    pub timestamp: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MarkPriceUpdate {
@@ -594,6 +869,19 @@ where
     deserializer.deserialize_any(StringOrI64Visitor)
 }
 
+/// Deserializes a value that can be either a string or an integer into a u64.
+fn deserialize_str_or_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = deserialize_str_or_i64(deserializer)?;
+    u64::try_from(value).map_err(|_| serde::de::Error::custom("value is negative"))
+}
+
+fn timestamp_seconds(seconds: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(seconds, 0).expect("timestamp in range")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -604,7 +892,7 @@ mod test {
             symbol: "TEST_MARKET".to_string(),
             base_symbol: "TEST".to_string(),
             quote_symbol: "MARKET".to_string(),
-            market_type: "SPOT".to_string(),
+            market_type: MarketType::Spot,
             rwa_market_type: None,
             filters: super::MarketFilters {
                 price: PriceFilter {
@@ -704,8 +992,113 @@ mod test {
 
         let kline_update: KlineUpdate = serde_json::from_str(data).unwrap();
         assert_eq!(kline_update.symbol, "SOL_USD".to_string());
-        assert_eq!(kline_update.start_time, 123400000);
+        assert_eq!(kline_update.start_time.timestamp(), 123400000);
         assert_eq!(kline_update.open_price, dec!(18.75));
+        assert!(Kline::try_from(&kline_update).is_err());
+
+        let closed = data
+            .replace("\"X\": false", "\"X\": true")
+            .replace("123400000", "\"2024-09-11T12:00:00\"")
+            .replace("123460000", "1726056060000000");
+        let kline_update: KlineUpdate = serde_json::from_str(&closed).unwrap();
+        let kline = Kline::try_from(&kline_update).unwrap();
+        assert_eq!(kline.start.to_string(), "2024-09-11 12:00:00 UTC");
+        assert_eq!(kline.end.unwrap().to_string(), "2024-09-11 12:01:00 UTC");
+        assert_eq!(kline.close, Some(dec!(19.25)));
+        assert_eq!(kline.trades, 93828);
+
+        // Updates serialize back to their wire format.
+        let value = serde_json::to_value(&kline_update).unwrap();
+        assert_eq!(value["t"], 1726056000);
+        assert_eq!(value["T"], 1726056060);
+    }
+
+    #[test]
+    fn test_kline_parse() {
+        let data = r#"
+[
+  {
+    "start": "2024-09-11 12:00:00",
+    "end": "2024-09-11 12:01:00",
+    "open": "18.75",
+    "high": "19.80",
+    "low": "18.50",
+    "close": "19.25",
+    "volume": "32123",
+    "quoteVolume": "619471.52",
+    "trades": "93828"
+  },
+  {
+    "start": "2024-09-11 12:01:00",
+    "open": null,
+    "high": null,
+    "low": null,
+    "close": null,
+    "volume": "0",
+    "trades": "0"
+  }
+]
+        "#;
+
+        let klines: Vec<Kline> = serde_json::from_str(data).unwrap();
+        assert_eq!(klines[0].start.timestamp(), 1726056000);
+        assert_eq!(klines[0].end, Some(klines[1].start));
+        assert_eq!(klines[1].end, None);
+        assert_eq!(
+            serde_json::to_value(&klines[0]).unwrap()["start"],
+            "2024-09-11 12:00:00"
+        );
+    }
+
+    #[test]
+    fn test_kline_interval_math() {
+        use std::str::FromStr;
+
+        let time = DateTime::parse_from_rfc3339("2024-09-11T12:34:56Z")
+            .unwrap()
+            .to_utc();
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().to_utc();
+
+        assert_eq!(
+            KlineInterval::from_str("15m"),
+            Ok(KlineInterval::FifteenMinutes)
+        );
+        assert_eq!(KlineInterval::OneMonth.as_ref(), "1month");
+        assert_eq!(
+            KlineInterval::FifteenMinutes.floor(time),
+            at("2024-09-11T12:30:00Z")
+        );
+        assert_eq!(
+            KlineInterval::FourHours.floor(time),
+            at("2024-09-11T12:00:00Z")
+        );
+        // 2024-09-09 was a Monday.
+        assert_eq!(
+            KlineInterval::OneWeek.floor(time),
+            at("2024-09-09T00:00:00Z")
+        );
+        assert_eq!(
+            KlineInterval::OneMonth.floor(time),
+            at("2024-09-01T00:00:00Z")
+        );
+        assert_eq!(
+            KlineInterval::OneMonth.next(at("2024-01-01T00:00:00Z")),
+            at("2024-02-01T00:00:00Z")
+        );
+
+        assert_eq!(
+            KlineInterval::OneMinute.count(at("2024-09-11T12:00:00Z"), at("2024-09-11T13:00:00Z")),
+            60
+        );
+        assert_eq!(
+            KlineInterval::OneHour.count(at("2024-09-11T12:30:00Z"), at("2024-09-11T14:00:01Z")),
+            2
+        );
+        assert_eq!(
+            KlineInterval::OneMonth.count(at("2024-01-01T00:00:00Z"), at("2024-12-15T00:00:00Z")),
+            12
+        );
+        assert_eq!(KlineInterval::OneDay.count(time, time), 0);
     }
 
     #[test]
@@ -738,6 +1131,46 @@ mod test {
         assert_eq!(depth.last_update_id, 94978271);
     }
 
+    #[test]
+    fn test_ticker_trades_as_integer() {
+        let data = r#"
+{
+  "symbol": "SOL_USDC",
+  "firstPrice": "140.10",
+  "lastPrice": "142.35",
+  "priceChange": "2.25",
+  "priceChangePercent": "0.016060",
+  "high": "143.00",
+  "low": "139.50",
+  "volume": "10234.12",
+  "quoteVolume": "1450000.12",
+  "trades": "48213"
+}
+        "#;
+
+        let ticker: Ticker = serde_json::from_str(data).unwrap();
+        assert_eq!(ticker.trades, 48213);
+        assert_eq!(
+            "1w".parse::<TickerInterval>().unwrap(),
+            TickerInterval::OneWeek
+        );
+    }
+
+    #[test]
+    fn test_funding_rate_interval_end_as_datetime() {
+        let data = r#"
+{
+  "symbol": "SOL_USDC_PERP",
+  "intervalEndTimestamp": "2025-01-21T08:00:00",
+  "fundingRate": "0.0000125"
+}
+        "#;
+
+        let rate: FundingRate = serde_json::from_str(data).unwrap();
+        // 2025-01-21 08:00:00 UTC
+        assert_eq!(rate.interval_end_timestamp.timestamp(), 1737446400);
+    }
+
     #[test]
     fn test_market_order_book_state_and_visible_parse() {
         let data = r#"
@@ -766,6 +1199,27 @@ mod test {
         assert_eq!(market.order_book_state, OrderBookState::Unknown);
     }
 
+    #[test]
+    fn test_market_type_parse() {
+        for (raw, expected) in [
+            ("SPOT", MarketType::Spot),
+            ("PERP", MarketType::Perp),
+            ("IPERP", MarketType::Iperp),
+            ("DATED", MarketType::Dated),
+            ("PREDICTION", MarketType::Prediction),
+            ("RFQ", MarketType::Rfq),
+            ("MONAD", MarketType::Monad),
+        ] {
+            let parsed: MarketType = serde_json::from_str(&format!("\"{raw}\"")).unwrap();
+            assert_eq!(parsed, expected);
+            assert_eq!(parsed.to_string(), raw);
+        }
+
+        // Unrecognized types fall back to `Unknown` rather than failing the parse.
+        let parsed: MarketType = serde_json::from_str("\"SOME_FUTURE_TYPE\"").unwrap();
+        assert_eq!(parsed, MarketType::Unknown);
+    }
+
     #[test]
     fn test_market_rwa_market_type_parse() {
         let data = r#"
@@ -226,6 +226,8 @@ pub enum SystemOrderType {
     CollateralConversion,
     FutureExpiry,
     OrderBookClosed,
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(
@@ -0,0 +1,246 @@
+//! Market symbols.
+//!
+//! Market symbols have the form `BASE_QUOTE` for spot markets and
+//! `BASE_QUOTE_PERP` for perpetual futures markets, e.g. `SOL_USDC`,
+//! `SOL_USDC_PERP` or, for tokenized real-world assets, `SPCX.US_USDC`. Other
+//! markets, such as dated futures or prediction markets, add their own suffix
+//! after the quote asset.
+//! Validation only rejects clearly malformed symbols, such as empty strings,
+//! symbols containing whitespace or symbols without a `BASE_QUOTE` pair; use the
+//! symbols returned by `get_markets` to check that a market exists.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// The kind of market a [`Symbol`] names.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Spot,
+    Perp,
+    /// Any other suffix after the quote asset, e.g. a dated expiry, kept as is.
+    Other(String),
+}
+
+/// A market symbol such as `SOL_USDC` or `SOL_USDC_PERP`.
+///
+/// Converting from a string with [`From`] keeps the string as is, so that
+/// conversions never fail; use [`Symbol::validate`] or parse with [`FromStr`] to
+/// reject malformed symbols.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Symbol(String);
+
+/// Error returned for a malformed market symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSymbolError {
+    symbol: String,
+}
+
+impl fmt::Display for ParseSymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid market symbol `{}`, expected BASE_QUOTE or BASE_QUOTE_SUFFIX",
+            self.symbol
+        )
+    }
+}
+
+impl std::error::Error for ParseSymbolError {}
+
+impl Symbol {
+    /// Builds the symbol of the market trading `base` against `quote`.
+    pub fn new(base: &str, quote: &str, kind: SymbolKind) -> Self {
+        match kind {
+            SymbolKind::Spot => Self(format!("{base}_{quote}")),
+            SymbolKind::Perp => Self(format!("{base}_{quote}_PERP")),
+            SymbolKind::Other(suffix) => Self(format!("{base}_{quote}_{suffix}")),
+        }
+    }
+
+    /// Returns the symbol as sent to the exchange.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Checks that the symbol is not clearly malformed: it must have at least a
+    /// `BASE_QUOTE` pair, no empty `_` separated parts and no whitespace.
+    pub fn validate(&self) -> Result<(), ParseSymbolError> {
+        self.parts().map(|_| ()).ok_or_else(|| ParseSymbolError {
+            symbol: self.0.clone(),
+        })
+    }
+
+    /// Returns the base asset, e.g. `SOL` for `SOL_USDC_PERP`, or `None` if the
+    /// symbol is malformed.
+    pub fn base(&self) -> Option<&str> {
+        self.parts().map(|(base, _, _)| base)
+    }
+
+    /// Returns the quote asset, e.g. `USDC` for `SOL_USDC_PERP`, or `None` if the
+    /// symbol is malformed.
+    pub fn quote(&self) -> Option<&str> {
+        self.parts().map(|(_, quote, _)| quote)
+    }
+
+    /// Returns the kind of market the symbol names, or `None` if the symbol is
+    /// malformed.
+    pub fn kind(&self) -> Option<SymbolKind> {
+        self.parts().map(|(_, _, suffix)| match suffix {
+            None => SymbolKind::Spot,
+            Some("PERP") => SymbolKind::Perp,
+            Some(suffix) => SymbolKind::Other(suffix.to_string()),
+        })
+    }
+
+    /// Splits the symbol into base, quote and the optional suffix after them.
+    fn parts(&self) -> Option<(&str, &str, Option<&str>)> {
+        if self.0.contains(char::is_whitespace) || self.0.split('_').any(str::is_empty) {
+            return None;
+        }
+        let mut parts = self.0.splitn(3, '_');
+        let (Some(base), Some(quote)) = (parts.next(), parts.next()) else {
+            return None;
+        };
+        Some((base, quote, parts.next()))
+    }
+}
+
+impl FromStr for Symbol {
+    type Err = ParseSymbolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let symbol = Self(s.to_string());
+        symbol.validate()?;
+        Ok(symbol)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(symbol: &str) -> Self {
+        Self(symbol.to_string())
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(symbol: &String) -> Self {
+        Self(symbol.clone())
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(symbol: String) -> Self {
+        Self(symbol)
+    }
+}
+
+impl From<&Symbol> for Symbol {
+    fn from(symbol: &Symbol) -> Self {
+        symbol.clone()
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.0
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_spot_perp_and_rwa_symbols() {
+        let spot: Symbol = "SOL_USDC".parse().unwrap();
+        assert_eq!(spot.base(), Some("SOL"));
+        assert_eq!(spot.quote(), Some("USDC"));
+        assert_eq!(spot.kind(), Some(SymbolKind::Spot));
+
+        let perp: Symbol = "kBONK_USDC_PERP".parse().unwrap();
+        assert_eq!(perp.base(), Some("kBONK"));
+        assert_eq!(perp.kind(), Some(SymbolKind::Perp));
+        assert_eq!(Symbol::new("kBONK", "USDC", SymbolKind::Perp), perp);
+
+        let rwa: Symbol = "SPCX.US_USDC".parse().unwrap();
+        assert_eq!(rwa.base(), Some("SPCX.US"));
+        assert_eq!(rwa.quote(), Some("USDC"));
+    }
+
+    #[test]
+    fn keeps_unknown_suffixes() {
+        let dated: Symbol = "BTC_USDC_20261225".parse().unwrap();
+        assert_eq!(dated.base(), Some("BTC"));
+        assert_eq!(dated.quote(), Some("USDC"));
+        assert_eq!(
+            dated.kind(),
+            Some(SymbolKind::Other("20261225".to_string()))
+        );
+        assert_eq!(
+            Symbol::new("BTC", "USDC", SymbolKind::Other("20261225".to_string())),
+            dated
+        );
+
+        let prediction: Symbol = "ELECTION_USDC_PREDICTION_YES".parse().unwrap();
+        assert_eq!(
+            prediction.kind(),
+            Some(SymbolKind::Other("PREDICTION_YES".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_symbols() {
+        for symbol in [
+            "",
+            "SOL",
+            "SOLUSDC",
+            "SOL-USDC",
+            "SOL USDC",
+            "SOL_USDC ",
+            " SOL_USDC",
+            "SOL_USDC_",
+            "SOL__USDC",
+            "_SOL_USDC",
+        ] {
+            assert!(symbol.parse::<Symbol>().is_err(), "{symbol:?} should fail");
+        }
+
+        // Conversions keep the raw string and defer validation.
+        let symbol = Symbol::from("SOL-USDC");
+        assert_eq!(symbol, "SOL-USDC");
+        assert!(symbol.validate().is_err());
+        assert_eq!(symbol.base(), None);
+    }
+
+    #[test]
+    fn serializes_as_a_plain_string() {
+        let symbol: Symbol = serde_json::from_str("\"SOL_USDC_PERP\"").unwrap();
+        assert_eq!(symbol.as_str(), "SOL_USDC_PERP");
+        assert_eq!(serde_json::to_string(&symbol).unwrap(), "\"SOL_USDC_PERP\"");
+    }
+}
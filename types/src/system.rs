@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The operational status of the exchange.
+///
+/// New statuses may be added by the exchange in the future; unrecognized values
+/// deserialize to [`SystemStatus::Unknown`].
+#[derive(
+    Debug,
+    strum::Display,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    strum::EnumString,
+    PartialEq,
+    Eq,
+    Hash,
+)]
+#[strum(serialize_all = "PascalCase")]
+#[serde(rename_all = "PascalCase")]
+pub enum SystemStatus {
+    /// The exchange is operating normally.
+    Ok,
+    /// The exchange is under maintenance.
+    Maintenance,
+    /// Any status not recognized by this client version.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+    pub status: SystemStatus,
+    /// Details about the current status, e.g. the expected end of maintenance.
+    pub message: Option<String>,
+}
+
+/// The exchange status combined with the latency and clock offset of the
+/// client, as returned by `BpxClient::health_check`.
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    pub status: SystemStatus,
+    pub message: Option<String>,
+    /// Round trip time of a server time request.
+    pub latency: Duration,
+    /// Time reported by the server.
+    pub server_time: DateTime<Utc>,
+    /// Server time minus local time, assuming the server read its clock half
+    /// way through the round trip.
+    pub clock_offset: TimeDelta,
+}
+
+impl HealthReport {
+    /// Whether the exchange reports normal operation.
+    pub fn is_ok(&self) -> bool {
+        self.status == SystemStatus::Ok
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_status_deserialization() {
+        let status: Status =
+            serde_json::from_str(r#"{"status":"Maintenance","message":"Back at 12:00 UTC"}"#)
+                .unwrap();
+        assert_eq!(status.status, SystemStatus::Maintenance);
+        assert_eq!(status.message.as_deref(), Some("Back at 12:00 UTC"));
+
+        let status: Status = serde_json::from_str(r#"{"status":"Degraded"}"#).unwrap();
+        assert_eq!(status.status, SystemStatus::Unknown);
+        assert_eq!(status.message, None);
+    }
+}
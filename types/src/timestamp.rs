@@ -0,0 +1,121 @@
+//! (De)serializes timestamps sent in several formats, such as those of K-lines,
+//! funding rates and history records. The REST API sends `YYYY-MM-DD HH:MM:SS`
+//! (or ISO 8601) strings in UTC, while integer timestamps are accepted in
+//! seconds, milliseconds or microseconds, told apart by their magnitude.
+//!
+//! Timestamps are serialized as `YYYY-MM-DD HH:MM:SS` strings, with fractional
+//! seconds only when present, or as integer seconds with [`seconds`].
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Deserializer, Serializer, de::Visitor};
+use std::fmt;
+
+const FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+pub fn serialize<S>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(&time.format(FORMAT))
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(TimestampVisitor)
+}
+
+fn from_integer<E>(value: i64) -> Result<DateTime<Utc>, E>
+where
+    E: serde::de::Error,
+{
+    let time = match value.unsigned_abs() {
+        100_000_000_000_000.. => DateTime::from_timestamp_micros(value),
+        100_000_000_000.. => DateTime::from_timestamp_millis(value),
+        _ => DateTime::from_timestamp(value, 0),
+    };
+    time.ok_or_else(|| E::custom("timestamp out of range"))
+}
+
+struct TimestampVisitor;
+
+impl Visitor<'_> for TimestampVisitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a datetime string or an integer timestamp")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<DateTime<Utc>, E>
+    where
+        E: serde::de::Error,
+    {
+        if let Ok(value) = value.parse::<i64>() {
+            return from_integer(value);
+        }
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+            .map(|time| time.and_utc())
+            .or_else(|_| DateTime::parse_from_rfc3339(value).map(|time| time.to_utc()))
+            .map_err(E::custom)
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<DateTime<Utc>, E>
+    where
+        E: serde::de::Error,
+    {
+        from_integer(value)
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<DateTime<Utc>, E>
+    where
+        E: serde::de::Error,
+    {
+        from_integer(i64::try_from(value).map_err(|_| E::custom("value too large"))?)
+    }
+}
+
+/// Optional timestamps, serialized as `null` when absent.
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S>(time: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match time {
+            Some(time) => super::serialize(time, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Timestamp(#[serde(with = "super")] DateTime<Utc>);
+
+        Ok(Option::<Timestamp>::deserialize(deserializer)?.map(|Timestamp(time)| time))
+    }
+}
+
+/// Timestamps serialized as integer seconds, as in websocket K-line updates.
+pub mod seconds {
+    use super::*;
+
+    pub fn serialize<S>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i64(time.timestamp())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        super::deserialize(deserializer)
+    }
+}
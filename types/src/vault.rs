@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
 
 /// Public vault information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -162,7 +163,7 @@ pub struct VaultRedeemHistoryParams {
     pub offset: Option<u64>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumString)]
 pub enum VaultRedeemStatus {
     Requested,
     Redeemed,